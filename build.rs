@@ -1,4 +1,5 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::compile_protos("src/proto/executor.proto")?;
+    tonic_build::compile_protos("src/proto/health.proto")?;
     Ok(())
-}
\ No newline at end of file
+}
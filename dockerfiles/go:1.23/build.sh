@@ -0,0 +1,12 @@
+#!/bin/bash
+
+# Exit on any error
+set -e
+
+# Get the directory of this script
+DIR="$( cd "$( dirname "${BASH_SOURCE[0]}" )" && pwd )"
+
+# Build the Docker image
+docker build -t code-executor-go-1.23 "$DIR"
+
+echo "Go 1.23 Docker image built successfully!" 
@@ -0,0 +1,12 @@
+#!/bin/bash
+
+# Exit on any error
+set -e
+
+# Get the directory of this script
+DIR="$( cd "$( dirname "${BASH_SOURCE[0]}" )" && pwd )"
+
+# Build the Docker image
+docker build -t code-executor-rust-1.83 "$DIR"
+
+echo "Rust 1.83 Docker image built successfully!" 
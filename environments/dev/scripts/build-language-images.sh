@@ -10,6 +10,11 @@ echo "Building Python 3.12 language image..."
 chmod +x ./dockerfiles/python:3.12/build.sh
 bash ./dockerfiles/python:3.12/build.sh
 
+# Build the Node.js 20 language Docker image if not already built
+echo "Building Node.js 20 language image..."
+chmod +x ./dockerfiles/node:20/build.sh
+bash ./dockerfiles/node:20/build.sh
+
 # Build the C++ 23 language Docker image if not already built
 echo "Building C++ 23 language image..."
 chmod +x ./dockerfiles/cpp:23/build.sh
@@ -19,3 +24,18 @@ bash ./dockerfiles/cpp:23/build.sh
 echo "Building Java 15 language image..."
 chmod +x ./dockerfiles/java:15/build.sh
 bash ./dockerfiles/java:15/build.sh
+
+# Build the C 17 language Docker image if not already built
+echo "Building C 17 language image..."
+chmod +x ./dockerfiles/c:17/build.sh
+bash ./dockerfiles/c:17/build.sh
+
+# Build the Rust 1.83 language Docker image if not already built
+echo "Building Rust 1.83 language image..."
+chmod +x ./dockerfiles/rust:1.83/build.sh
+bash ./dockerfiles/rust:1.83/build.sh
+
+# Build the Go 1.23 language Docker image if not already built
+echo "Building Go 1.23 language image..."
+chmod +x ./dockerfiles/go:1.23/build.sh
+bash ./dockerfiles/go:1.23/build.sh
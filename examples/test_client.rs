@@ -19,6 +19,7 @@ for i in range(5):
         timeout_seconds: 30,
         memory_limit_mb: 512,
         input: Vec::new(),
+        ..Default::default()
     });
 
     let response = client.execute_code(request).await?;
@@ -38,6 +39,7 @@ while True:
         timeout_seconds: 5,
         memory_limit_mb: 512,
         input: Vec::new(),
+        ..Default::default()
     });
 
     let response = client.execute_code(request).await?;
@@ -47,13 +49,14 @@ while True:
     // 3. Test code that exceeds memory limit
     let request = Request::new(ExecuteRequest {
         code: r#"
-x = [0] * (1024 * 1024 * 1024)  # Try to allocate 1GB
+x = ["x" * (1024 * 1024) for _ in range(1024)]  # 1MB씩 1GB까지 사용
 "#.to_string(),
         language: "python".to_string(),
         version: "3.12".to_string(),
         timeout_seconds: 30,
         memory_limit_mb: 100,
         input: Vec::new(),
+        ..Default::default()
     });
 
     let response = client.execute_code(request).await?;
@@ -70,6 +73,7 @@ print(Hello
         timeout_seconds: 10,
         memory_limit_mb: 128,
         input: Vec::new(),
+        ..Default::default()
     });
 
     let response = client.execute_code(request).await?;
@@ -88,6 +92,7 @@ print(f"A: {a}, B: {b}")
         timeout_seconds: 10,
         memory_limit_mb: 128,
         input: vec!["hello".to_string(), "world".to_string()],
+        ..Default::default()
     });
 
     let response = client.execute_code(request).await?;
@@ -109,6 +114,7 @@ fn print_pretty_response(response: &tonic::Response<code_executor::proto::Execut
     println!("\nStdout:\n{}", message.stdout);
     println!("\nStderr:\n{}", message.stderr);
     println!("\nExecution time: {:.2}ms", message.execution_time_ms);
+    println!("CPU time: {:.2}ms user, {:.2}ms system", message.cpu_user_ms, message.cpu_system_ms);
     println!("Memory used: {} KB", message.memory_used_kb);
     println!("Error message: {}", message.error_message);
 } 
\ No newline at end of file
@@ -1,4 +1,7 @@
-use code_executor::container::{ContainerManager, ExecutionResult};
+use code_executor::container::{ContainerManager, DockerConfig, ImageSource};
+use code_executor::execution::ExecutionResult;
+use code_executor::language::LanguageRegistry;
+use std::path::Path;
 use std::error::Error;
 
 #[tokio::main(flavor = "current_thread")]
@@ -6,7 +9,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Initialize tracing for better error reporting
     tracing_subscriber::fmt::init();
 
-    let manager = ContainerManager::new().await?;
+    let manager = ContainerManager::new(DockerConfig::default()).await?;
+    let languages = LanguageRegistry::load(Path::new("dockerfiles"))?;
+    let manifest = languages.get("java", "15")?;
+    // 이미지가 없으면 기록된 digest가 없어 실행이 거부됨
+    manager.check_images(&[ImageSource { image: manifest.image.clone(), context: languages.build_context(&manifest) }]).await;
 
     // 1. 정상 실행 케이스
     let java_code = r#"
@@ -29,9 +36,8 @@ public class Main {
 "#;
     println!("\n[CASE 1] 정상 실행 케이스");
     let result = manager.execute_code(
+        &manifest,
         java_code,
-        "java",
-        "15",
         5,  // timeout in seconds
         128, // memory limit in MB
         &[],
@@ -51,9 +57,8 @@ public class Main {
 "#;
     println!("\n[CASE 2] 무한루프(타임아웃) 케이스");
     let result = manager.execute_code(
+        &manifest,
         infinite_loop_code,
-        "java",
-        "15",
         3,  // timeout in seconds
         128, // memory limit in MB
         &[],
@@ -73,9 +78,8 @@ public class Main {
 "#;
     println!("\n[CASE 3] 메모리 초과 케이스");
     let result = manager.execute_code(
+        &manifest,
         memory_exceed_code,
-        "java",
-        "15",
         5,  // timeout in seconds
         32, // memory limit in MB (작게 설정)
         &[],
@@ -92,9 +96,8 @@ public class Main {
 "#;
     println!("\n[CASE 4] 문법 에러 케이스");
     let result = manager.execute_code(
+        &manifest,
         syntax_error_code,
-        "java",
-        "15",
         5,  // timeout in seconds
         128, // memory limit in MB
         &[],
@@ -112,6 +115,6 @@ fn print_result(result: &ExecutionResult) {
     println!("{}", result.stdout);
     println!("\nStderr:");
     println!("{}", result.stderr);
-    println!("\nExecution time: {:.2}ms", result.execution_time);
-    println!("Memory used: {} bytes", result.memory_used);
+    println!("\nExecution time: {:.2}ms", result.execution_time_ms);
+    println!("Memory used: {} KB", result.memory_used_kb);
 } 
\ No newline at end of file
@@ -28,6 +28,7 @@ while True:
                 timeout_seconds: 5,
                 memory_limit_mb: 128,
                 input: Vec::new(),
+                ..Default::default()
             });
             let response = client.execute_code(request).await;
             (name, response)
@@ -0,0 +1,76 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tonic::Request;
+use hmac::{Hmac, Mac};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use code_executor::proto::code_executor_client::CodeExecutorClient;
+use code_executor::proto::{ExecuteRequest, StatusRequest};
+use code_executor::webhook::SIGNATURE_HEADER;
+
+/// Local stand-in for a grading service: submits an execution with a `callback_url`
+/// pointing at itself and verifies the signed result it receives.
+///
+/// Run the server with `CODE_EXECUTOR_WEBHOOK_ALLOWED_HOSTS=127.0.0.1`, and with the same
+/// `CODE_EXECUTOR_WEBHOOK_SECRET` to check signatures.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let secret = std::env::var("CODE_EXECUTOR_WEBHOOK_SECRET").ok();
+    let addr: SocketAddr = "127.0.0.1:8080".parse()?;
+    let (tx, mut rx) = mpsc::channel::<(Option<String>, Vec<u8>)>(1);
+
+    let make_service = make_service_fn(move |_| {
+        let tx = tx.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                let tx = tx.clone();
+                async move {
+                    let signature = request.headers()
+                        .get(SIGNATURE_HEADER)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string);
+                    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+                    let _ = tx.send((signature, body.to_vec())).await;
+                    Ok::<_, Infallible>(Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty()).unwrap())
+                }
+            }))
+        }
+    });
+    tokio::spawn(Server::bind(&addr).serve(make_service));
+    println!("Callback receiver listening on http://{}", addr);
+
+    let mut client = CodeExecutorClient::connect("http://[::1]:50051").await?;
+    let response = client.execute_code(Request::new(ExecuteRequest {
+        code: r#"print("Hello from a webhook!")"#.to_string(),
+        language: "python".to_string(),
+        version: "3.12".to_string(),
+        timeout_seconds: 5,
+        memory_limit_mb: 128,
+        callback_url: format!("http://{}/callback", addr),
+        ..Default::default()
+    })).await?.into_inner();
+    println!("Accepted execution {} (status {:?})", response.execution_id, response.status());
+
+    let (signature, body) = rx.recv().await.ok_or("Receiver stopped")?;
+    println!("\nCallback payload:\n{}", String::from_utf8_lossy(&body));
+
+    match (&secret, signature) {
+        (Some(secret), Some(signature)) => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+            mac.update(&body);
+            let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+            println!("Signature valid: {}", expected == signature);
+        }
+        (Some(_), None) => println!("Signature missing"),
+        (None, _) => println!("No secret configured, signature not checked"),
+    }
+
+    let status = client.get_status(Request::new(StatusRequest {
+        execution_id: response.execution_id,
+    })).await?.into_inner();
+    println!("GetStatus: {:?}", status.status());
+
+    Ok(())
+}
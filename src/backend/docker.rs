@@ -0,0 +1,23 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{BackendKind, ExecutionBackend};
+use crate::container::ContainerManager;
+use crate::execution::ExecutionResult;
+use crate::language::LanguageManifest;
+
+#[async_trait]
+impl ExecutionBackend for ContainerManager {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Docker
+    }
+
+    fn toolchain(&self, manifest: &LanguageManifest) -> Result<String> {
+        self.image_digest(&manifest.image)
+    }
+
+    async fn execute(&self, manifest: &LanguageManifest, code: &str, timeout_seconds: u32,
+                     memory_limit_mb: u32, input: &[String]) -> Result<ExecutionResult> {
+        self.execute_code(manifest, code, timeout_seconds, memory_limit_mb, input).await
+    }
+}
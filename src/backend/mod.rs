@@ -0,0 +1,210 @@
+mod docker;
+mod native;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::config::env_override;
+use crate::execution::ExecutionResult;
+use crate::language::{LanguageManifest, LanguageRegistry};
+
+pub use native::{NativeBackend, NativeConfig};
+
+/// Something that can run submitted code for a language.
+///
+/// Every backend reports the same `ExecutionResult` and status for the same program, so a
+/// language can be moved between backends without clients noticing.
+#[async_trait]
+pub trait ExecutionBackend: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    /// Identifier of the toolchain that runs `manifest`, e.g. the image id for Docker.
+    /// Fails when the language cannot run on this backend right now.
+    fn toolchain(&self, manifest: &LanguageManifest) -> Result<String>;
+
+    /// Run `code` with the given wall clock and memory limits, feeding `input` as stdin lines.
+    /// `timeout_seconds` is used as given; callers apply the manifest's time multiplier.
+    async fn execute(&self, manifest: &LanguageManifest, code: &str, timeout_seconds: u32,
+                     memory_limit_mb: u32, input: &[String]) -> Result<ExecutionResult>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// One container per execution from the language image
+    Docker,
+    /// Fork and exec on the server host under a cgroup or rlimits, isolated in namespaces where the host allows
+    Native,
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendKind::Docker => write!(f, "docker"),
+            BackendKind::Native => write!(f, "native"),
+        }
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "docker" => Ok(BackendKind::Docker),
+            "native" => Ok(BackendKind::Native),
+            _ => Err(anyhow!("unknown backend '{}', expected docker or native", value)),
+        }
+    }
+}
+
+/// Which backend runs each language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendConfig {
+    /// Used for languages without an entry in `languages`
+    pub default: BackendKind,
+    /// Per language override, keyed by language name (`python`) or `<language>:<version>`
+    pub languages: BTreeMap<String, BackendKind>,
+    pub native: NativeConfig,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            default: BackendKind::Docker,
+            languages: BTreeMap::new(),
+            native: NativeConfig::default(),
+        }
+    }
+}
+
+impl BackendConfig {
+    /// Apply `CODE_EXECUTOR_BACKEND` and `CODE_EXECUTOR_LANGUAGE_BACKENDS`
+    /// (e.g. `python=native,ruby=native`) on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_BACKEND", &mut self.default)?;
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_LANGUAGE_BACKENDS") {
+            self.languages.clear();
+            for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                let (language, backend) = entry.split_once('=')
+                    .ok_or_else(|| anyhow!("Invalid language backend '{}', expected <language>=<backend>", entry))?;
+                let backend = backend.parse()
+                    .map_err(|e| anyhow!("Invalid backend for language '{}': {}", language, e))?;
+                self.languages.insert(language.trim().to_string(), backend);
+            }
+        }
+        self.native.apply_env()
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        self.native.validate().into_iter().map(|error| format!("native.{}", error)).collect()
+    }
+
+    /// Backend for a manifest: an exact `<language>:<version>` entry wins over a language entry
+    pub fn kind_for(&self, manifest: &LanguageManifest) -> BackendKind {
+        self.languages.get(&manifest.key())
+            .or_else(|| self.languages.get(&manifest.language))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Describe entries that name no known language
+    pub fn validate_against(&self, languages: &LanguageRegistry) -> Vec<String> {
+        self.languages.keys()
+            .filter(|key| !languages.iter().any(|manifest| manifest.key() == **key || manifest.language == **key))
+            .map(|key| format!("backend.languages.{}: unknown language", key))
+            .collect()
+    }
+}
+
+/// The configured backends and the per-language choice between them
+pub struct Backends {
+    config: BackendConfig,
+    docker: Arc<dyn ExecutionBackend>,
+    native: Arc<dyn ExecutionBackend>,
+}
+
+impl Backends {
+    pub fn new(config: BackendConfig, docker: Arc<dyn ExecutionBackend>, native: Arc<dyn ExecutionBackend>) -> Self {
+        Self { config, docker, native }
+    }
+
+    pub fn for_language(&self, manifest: &LanguageManifest) -> &Arc<dyn ExecutionBackend> {
+        match self.config.kind_for(manifest) {
+            BackendKind::Docker => &self.docker,
+            BackendKind::Native => &self.native,
+        }
+    }
+
+    /// Whether any of the manifests runs on `kind`
+    pub fn uses(&self, kind: BackendKind, languages: &LanguageRegistry) -> bool {
+        languages.iter().any(|manifest| self.config.kind_for(manifest) == kind)
+    }
+}
+
+impl fmt::Debug for Backends {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backends").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> LanguageRegistry {
+        let manifests = ["python:3.11", "python:3.12", "ruby:3.2"].map(|key| {
+            let (language, version) = key.split_once(':').unwrap();
+            toml::from_str::<LanguageManifest>(&format!(r#"
+                language = "{}"
+                version = "{}"
+                image = "code-executor-{}-{}"
+                source_file = "main"
+                run = ["{}", "{{source}}"]
+            "#, language, version, language, version, language)).unwrap()
+        });
+        LanguageRegistry::from_manifests(manifests).unwrap()
+    }
+
+    #[test]
+    fn versions_override_languages_which_override_the_default() {
+        let config = BackendConfig {
+            default: BackendKind::Docker,
+            languages: BTreeMap::from([
+                ("python".to_string(), BackendKind::Native),
+                ("python:3.11".to_string(), BackendKind::Docker),
+            ]),
+            ..Default::default()
+        };
+        let registry = registry();
+        let kind = |language: &str, version: &str| config.kind_for(&registry.get(language, version).unwrap());
+
+        assert_eq!(kind("python", "3.12"), BackendKind::Native);
+        assert_eq!(kind("python", "3.11"), BackendKind::Docker);
+        assert_eq!(kind("ruby", "3.2"), BackendKind::Docker);
+    }
+
+    #[test]
+    fn reports_unknown_languages() {
+        let config = BackendConfig {
+            languages: BTreeMap::from([
+                ("ruby".to_string(), BackendKind::Native),
+                ("node".to_string(), BackendKind::Native),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(config.validate_against(&registry()), vec!["backend.languages.node: unknown language"]);
+    }
+
+    #[test]
+    fn parses_backend_names() {
+        assert_eq!("Native".parse::<BackendKind>().unwrap(), BackendKind::Native);
+        assert!("podman".parse::<BackendKind>().is_err());
+    }
+}
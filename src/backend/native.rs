@@ -0,0 +1,409 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{BackendKind, ExecutionBackend};
+use crate::config::env_override;
+use crate::execution::ExecutionResult;
+use crate::language::LanguageManifest;
+use crate::runner::{rootfs, CgroupLimits, CgroupParent, ExecutionConfig, Namespaces, Rootfs, Runner, SandboxIds};
+use crate::security::{Landlock, SeccompProfile};
+
+/// How native executions are confined
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NativeConfig {
+    /// Run each execution in its own cgroup v2 when the host delegates one to the server
+    pub cgroups: bool,
+    /// Cgroup to create execution cgroups under; the server's own cgroup when unset
+    pub cgroup_parent: Option<PathBuf>,
+    /// Share of one CPU each execution may use
+    pub cpu_quota_percent: u32,
+    /// Processes and threads each execution may have at once
+    pub max_processes: u32,
+    /// Isolate each execution in new user, PID, mount, network, IPC and UTS namespaces
+    pub namespaces: bool,
+    /// First host uid and gid executions run as when the server runs as root
+    pub sandbox_uid: u32,
+    pub sandbox_gid: u32,
+    /// Size of the uid and gid ranges; each concurrent execution gets a uid and gid of its own
+    pub sandbox_ids: u32,
+    /// Confine each execution with Landlock when the kernel supports it
+    pub landlock: bool,
+    /// Paths executions may read besides their interpreter's installation prefix
+    pub landlock_read_only: Vec<PathBuf>,
+    /// Pivot isolated executions into a minimal root filesystem instead of the host's
+    pub rootfs: bool,
+    /// Directory of exported language images, `<language>:<version>` like `dockerfiles/`;
+    /// languages without one get a root filesystem of `rootfs_host_paths`
+    pub rootfs_dir: Option<PathBuf>,
+    /// Host paths mounted read-only into root filesystems not made from an image; a `*` in the
+    /// last component matches any name
+    pub rootfs_host_paths: Vec<PathBuf>,
+    /// Size of the tmpfs mounted at `/workspace` and `/tmp` inside the root filesystem
+    pub workspace_size_mb: u32,
+    /// Bytes kept of each of stdout and stderr; the rest is read and discarded
+    pub max_output_bytes: usize,
+}
+
+impl Default for NativeConfig {
+    fn default() -> Self {
+        Self {
+            cgroups: true,
+            cgroup_parent: None,
+            cpu_quota_percent: 100,
+            max_processes: 64,
+            namespaces: true,
+            sandbox_uid: 200000,
+            sandbox_gid: 200000,
+            sandbox_ids: 1024,
+            landlock: true,
+            landlock_read_only: ["/usr", "/lib", "/lib64", "/bin", "/etc"].map(PathBuf::from).to_vec(),
+            rootfs: true,
+            rootfs_dir: None,
+            rootfs_host_paths: [
+                "/usr", "/lib", "/lib64", "/bin", "/sbin",
+                "/etc/alternatives", "/etc/ld.so.cache", "/etc/localtime", "/etc/ssl", "/etc/java-*",
+            ].map(PathBuf::from).to_vec(),
+            workspace_size_mb: 64,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl NativeConfig {
+    /// Apply `CODE_EXECUTOR_NATIVE_CGROUPS`, `CODE_EXECUTOR_NATIVE_CGROUP_PARENT`,
+    /// `CODE_EXECUTOR_NATIVE_CPU_QUOTA_PERCENT`, `CODE_EXECUTOR_NATIVE_MAX_PROCESSES`,
+    /// `CODE_EXECUTOR_NATIVE_NAMESPACES`, `CODE_EXECUTOR_NATIVE_SANDBOX_UID`,
+    /// `CODE_EXECUTOR_NATIVE_SANDBOX_GID`, `CODE_EXECUTOR_NATIVE_SANDBOX_IDS`, `CODE_EXECUTOR_NATIVE_LANDLOCK`,
+    /// `CODE_EXECUTOR_NATIVE_LANDLOCK_READ_ONLY`, `CODE_EXECUTOR_NATIVE_ROOTFS`,
+    /// `CODE_EXECUTOR_NATIVE_ROOTFS_DIR`, `CODE_EXECUTOR_NATIVE_ROOTFS_HOST_PATHS`,
+    /// `CODE_EXECUTOR_NATIVE_WORKSPACE_SIZE_MB` and `CODE_EXECUTOR_NATIVE_MAX_OUTPUT_BYTES`
+    /// on top of the current values; lists are `PATH`-style
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_NATIVE_CGROUPS", &mut self.cgroups)?;
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_NATIVE_CGROUP_PARENT") {
+            self.cgroup_parent = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        }
+        env_override("CODE_EXECUTOR_NATIVE_CPU_QUOTA_PERCENT", &mut self.cpu_quota_percent)?;
+        env_override("CODE_EXECUTOR_NATIVE_MAX_PROCESSES", &mut self.max_processes)?;
+        env_override("CODE_EXECUTOR_NATIVE_NAMESPACES", &mut self.namespaces)?;
+        env_override("CODE_EXECUTOR_NATIVE_SANDBOX_UID", &mut self.sandbox_uid)?;
+        env_override("CODE_EXECUTOR_NATIVE_SANDBOX_GID", &mut self.sandbox_gid)?;
+        env_override("CODE_EXECUTOR_NATIVE_SANDBOX_IDS", &mut self.sandbox_ids)?;
+        env_override("CODE_EXECUTOR_NATIVE_LANDLOCK", &mut self.landlock)?;
+        if let Some(value) = std::env::var_os("CODE_EXECUTOR_NATIVE_LANDLOCK_READ_ONLY") {
+            self.landlock_read_only = std::env::split_paths(&value).filter(|path| !path.as_os_str().is_empty()).collect();
+        }
+        env_override("CODE_EXECUTOR_NATIVE_ROOTFS", &mut self.rootfs)?;
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_NATIVE_ROOTFS_DIR") {
+            self.rootfs_dir = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        }
+        if let Some(value) = std::env::var_os("CODE_EXECUTOR_NATIVE_ROOTFS_HOST_PATHS") {
+            self.rootfs_host_paths = std::env::split_paths(&value).filter(|path| !path.as_os_str().is_empty()).collect();
+        }
+        env_override("CODE_EXECUTOR_NATIVE_WORKSPACE_SIZE_MB", &mut self.workspace_size_mb)?;
+        env_override("CODE_EXECUTOR_NATIVE_MAX_OUTPUT_BYTES", &mut self.max_output_bytes)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.cpu_quota_percent == 0 {
+            errors.push("cpu_quota_percent must be at least 1".to_string());
+        }
+        if self.max_processes == 0 {
+            errors.push("max_processes must be at least 1".to_string());
+        }
+        if self.sandbox_uid == 0 || self.sandbox_gid == 0 {
+            errors.push("sandbox_uid and sandbox_gid must not be root".to_string());
+        }
+        if self.sandbox_ids == 0 {
+            errors.push("sandbox_ids must be at least 1".to_string());
+        }
+        if self.sandbox_uid.checked_add(self.sandbox_ids).is_none() || self.sandbox_gid.checked_add(self.sandbox_ids).is_none() {
+            errors.push("sandbox_uid and sandbox_gid ranges must end below 2^32".to_string());
+        }
+        if let Some(path) = self.landlock_read_only.iter().find(|path| !path.is_absolute()) {
+            errors.push(format!("landlock_read_only paths must be absolute: {}", path.display()));
+        }
+        if let Some(path) = self.rootfs_host_paths.iter().find(|path| !path.is_absolute()) {
+            errors.push(format!("rootfs_host_paths must be absolute: {}", path.display()));
+        }
+        if self.workspace_size_mb == 0 {
+            errors.push("workspace_size_mb must be at least 1".to_string());
+        }
+        if self.max_output_bytes == 0 {
+            errors.push("max_output_bytes must be at least 1".to_string());
+        }
+        errors
+    }
+}
+
+/// Runs code directly on the server host with `Runner`: no daemon and no image.
+/// Each execution gets its own cgroup when the host delegates cgroup v2 to the server,
+/// otherwise only rlimits apply, its own namespaces and minimal root filesystem when the host
+/// allows user namespaces and Landlock rules when the kernel supports them.
+/// Without namespaces it is meant for trusted workloads only.
+#[derive(Debug)]
+pub struct NativeBackend {
+    cgroups: Option<Arc<CgroupParent>>,
+    sandbox_ids: Arc<SandboxIds>,
+    max_processes: u32,
+    namespaces: Option<Arc<Namespaces>>,
+    landlock: Option<Landlock>,
+    /// Root filesystem of languages without an exported image; `None` when executions see the host's
+    host_rootfs: Option<Arc<Rootfs>>,
+    rootfs_dir: Option<PathBuf>,
+    workspace_size_mb: u32,
+    max_output_bytes: usize,
+}
+
+impl NativeBackend {
+    /// Set up execution cgroups, namespaces, root filesystems and Landlock as configured,
+    /// doing without each one the host does not allow
+    pub fn new(config: &NativeConfig) -> Self {
+        let sandbox_ids = Arc::new(SandboxIds::new(config.sandbox_uid, config.sandbox_gid, config.sandbox_ids));
+        if !nix::unistd::Uid::effective().is_root() {
+            tracing::warn!("The server is not root, so native executions run as its own uid");
+        }
+        let namespaces = namespaces(config, &sandbox_ids).map(Arc::new);
+        let rootfs = config.rootfs && namespaces.is_some();
+        if config.rootfs && !rootfs {
+            tracing::warn!("Native executions see the host's filesystem, as a root filesystem needs namespaces");
+        }
+        Self {
+            cgroups: cgroups(config).map(Arc::new),
+            sandbox_ids,
+            max_processes: config.max_processes,
+            namespaces,
+            landlock: landlock(config),
+            host_rootfs: rootfs.then(|| Arc::new(Rootfs::host(&config.rootfs_host_paths, config.workspace_size_mb))),
+            rootfs_dir: config.rootfs_dir.clone().filter(|_| rootfs),
+            workspace_size_mb: config.workspace_size_mb,
+            max_output_bytes: config.max_output_bytes,
+        }
+    }
+
+    /// Exported image of the language under `rootfs_dir`, if there is one
+    fn image_dir(&self, manifest: &LanguageManifest) -> Option<PathBuf> {
+        let dir = self.rootfs_dir.as_ref()?.join(manifest.key());
+        dir.is_dir().then_some(dir)
+    }
+
+    /// Paths of the manifest's toolchain programs on this host: in the language's exported image
+    /// if it has one, so they run against the image's libraries, otherwise found in `PATH`
+    fn toolchain_paths(&self, manifest: &LanguageManifest) -> Result<Vec<PathBuf>> {
+        manifest.toolchain_programs().into_iter().map(|program| self.find(manifest, program)).collect()
+    }
+
+    fn find(&self, manifest: &LanguageManifest, program: &str) -> Result<PathBuf> {
+        let found = match self.image_dir(manifest) {
+            Some(image) => {
+                let program = program.strip_prefix('/').map_or_else(|| program.to_string(), |path| image.join(path).display().to_string());
+                let path = std::env::join_paths(std::env::split_paths(rootfs::SANDBOX_PATH)
+                    .map(|dir| image.join(dir.strip_prefix("/").unwrap_or(&dir))))?;
+                find_program(&program, Some(&path))
+            }
+            None => find_program(program, std::env::var_os("PATH").as_deref()),
+        };
+        found.ok_or_else(|| anyhow!("{} is not installed on this host ({} not found)", manifest.key(), program))
+    }
+
+    /// Root filesystem executions of the language pivot into, `None` when they see the host's
+    fn rootfs(&self, manifest: &LanguageManifest) -> Result<Option<Arc<Rootfs>>> {
+        match self.image_dir(manifest) {
+            Some(image) => Ok(Some(Arc::new(Rootfs::image(&image, self.workspace_size_mb)?))),
+            None => Ok(self.host_rootfs.clone()),
+        }
+    }
+}
+
+/// Without cgroups, namespaces, a root filesystem or Landlock; executions still get sandbox ids
+impl Default for NativeBackend {
+    fn default() -> Self {
+        let config = NativeConfig::default();
+        Self {
+            cgroups: None,
+            sandbox_ids: Arc::new(SandboxIds::new(config.sandbox_uid, config.sandbox_gid, config.sandbox_ids)),
+            max_processes: config.max_processes,
+            namespaces: None,
+            landlock: None,
+            host_rootfs: None,
+            rootfs_dir: None,
+            workspace_size_mb: config.workspace_size_mb,
+            max_output_bytes: config.max_output_bytes,
+        }
+    }
+}
+
+fn cgroups(config: &NativeConfig) -> Option<CgroupParent> {
+    if !config.cgroups {
+        tracing::info!("Native executions are limited with rlimits (cgroups disabled)");
+        return None;
+    }
+    let limits = CgroupLimits {
+        cpu_quota_percent: config.cpu_quota_percent,
+        max_processes: config.max_processes,
+    };
+    match CgroupParent::delegated(config.cgroup_parent.as_deref(), limits) {
+        Ok(parent) => {
+            tracing::info!("Native executions run in cgroups under {}", parent.path().display());
+            Some(parent)
+        }
+        Err(e) => {
+            tracing::warn!("Cgroup v2 is not usable, native executions fall back to rlimits: {:#}", e);
+            None
+        }
+    }
+}
+
+fn namespaces(config: &NativeConfig, sandbox_ids: &Arc<SandboxIds>) -> Option<Namespaces> {
+    if !config.namespaces {
+        tracing::warn!("Native executions are not isolated (namespaces disabled); run trusted code only");
+        return None;
+    }
+    match sandbox_ids.acquire().and_then(|identity| Namespaces.probe(&identity)) {
+        Ok(()) => {
+            tracing::info!("Native executions run in their own namespaces");
+            Some(Namespaces)
+        }
+        Err(e) => {
+            tracing::warn!("Namespaces are not usable, native executions are not isolated; run trusted code only: {:#}", e);
+            None
+        }
+    }
+}
+
+fn landlock(config: &NativeConfig) -> Option<Landlock> {
+    if !config.landlock {
+        tracing::info!("Native executions are not confined with Landlock (disabled)");
+        return None;
+    }
+    match Landlock::supported(config.landlock_read_only.clone()) {
+        Ok(landlock) => {
+            tracing::info!("Native executions are confined with Landlock");
+            Some(landlock)
+        }
+        Err(e) => {
+            tracing::warn!("Native executions are not confined with Landlock: {:#}", e);
+            None
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutionBackend for NativeBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Native
+    }
+
+    /// `native:<paths>` of the compiler and interpreter on this host, comma separated, which are
+    /// inside the exported image if the language has one
+    fn toolchain(&self, manifest: &LanguageManifest) -> Result<String> {
+        let paths = self.toolchain_paths(manifest)?;
+        Ok(format!("native:{}", paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(",")))
+    }
+
+    async fn execute(&self, manifest: &LanguageManifest, code: &str, timeout_seconds: u32,
+                     memory_limit_mb: u32, input: &[String]) -> Result<ExecutionResult> {
+        let toolchain = self.toolchain(manifest)?;
+        let landlock = match &self.landlock {
+            Some(landlock) => {
+                let mut landlock = landlock.clone();
+                for program in self.toolchain_paths(manifest)? {
+                    // 이미지 안의 컴파일러와 인터프리터는 새 루트 기준 경로로 허용
+                    let prefix = installation_prefix(&program);
+                    let prefix = match self.image_dir(manifest) {
+                        Some(image) => prefix.strip_prefix(std::fs::canonicalize(&image)?).ok().map(|path| Path::new("/").join(path)),
+                        None => Some(prefix),
+                    };
+                    if let Some(prefix) = prefix {
+                        landlock = landlock.allow_read(prefix);
+                    }
+                }
+                Some(Arc::new(landlock))
+            }
+            None => None,
+        };
+        let runner = Runner::new(ExecutionConfig {
+            timeout_seconds,
+            memory_limit_mb: memory_limit_mb as u64,
+            language: Arc::new(manifest.clone()),
+            code: code.to_string(),
+            input: input.to_vec(),
+            max_processes: self.max_processes,
+            max_output_bytes: self.max_output_bytes,
+            cgroups: self.cgroups.clone(),
+            identity: Some(self.sandbox_ids.acquire()?),
+            namespaces: self.namespaces.clone(),
+            rootfs: self.rootfs(manifest)?,
+            landlock,
+            seccomp: Some(Arc::new(SeccompProfile::for_language(manifest).filter())),
+            compile_seccomp: Some(Arc::new(SeccompProfile::for_compiler(manifest).filter())),
+        });
+
+        let mut result = runner.execute().await?;
+        result.image_digest = toolchain;
+        Ok(result)
+    }
+}
+
+/// Directory an interpreter is installed under, which holds its libraries too:
+/// `/opt/python/3.12` for `/opt/python/3.12/bin/python3`, after resolving symlinks
+fn installation_prefix(interpreter: &Path) -> PathBuf {
+    let interpreter = std::fs::canonicalize(interpreter).unwrap_or_else(|_| interpreter.to_path_buf());
+    let dir = interpreter.parent().unwrap_or(Path::new("/"));
+    match dir.file_name() {
+        Some(name) if name == "bin" || name == "sbin" => dir.parent().unwrap_or(dir).to_path_buf(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Resolve `program` the way `execvp` would: paths are used as given, bare names are searched in `PATH`
+fn find_program(program: &str, path: Option<&std::ffi::OsStr>) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Some(PathBuf::from(program)).filter(|path| is_executable(path));
+    }
+    std::env::split_paths(path?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_programs_on_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("python3");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+        let path = std::env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+
+        assert_eq!(find_program("python3", Some(&path)), None);
+        std::fs::set_permissions(&program, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        assert_eq!(find_program("python3", Some(&path)), Some(program.clone()));
+        assert_eq!(find_program(program.to_str().unwrap(), None), Some(program));
+        assert_eq!(find_program("ruby", Some(&path)), None);
+    }
+
+    #[test]
+    fn interpreters_are_readable_with_their_installation_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let prefix = dir.path().canonicalize().unwrap().join("python-3.12");
+        std::fs::create_dir_all(prefix.join("bin")).unwrap();
+        std::fs::write(prefix.join("bin/python3.12"), "").unwrap();
+        std::os::unix::fs::symlink(prefix.join("bin/python3.12"), dir.path().join("python3")).unwrap();
+
+        assert_eq!(installation_prefix(&dir.path().join("python3")), prefix);
+        assert_eq!(installation_prefix(&prefix.join("python3.12")), prefix);
+    }
+}
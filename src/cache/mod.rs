@@ -0,0 +1,3 @@
+mod result_cache;
+
+pub use result_cache::{CacheConfig, CacheKey, ResultCache};
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::env_override;
+use crate::proto::{ExecuteRequest, ExecuteResponse, ExecutionStatus};
+
+const DEFAULT_TTL_SECONDS: u64 = 600;
+const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Limits for the in-memory result cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// How long a stored result may be served
+    pub ttl_seconds: u64,
+    /// Upper bound on the total size of cached outputs; oldest entries are evicted first
+    pub max_bytes: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_seconds: DEFAULT_TTL_SECONDS,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Apply `CODE_EXECUTOR_CACHE_TTL_SECONDS` and `CODE_EXECUTOR_CACHE_MAX_BYTES` on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_CACHE_TTL_SECONDS", &mut self.ttl_seconds)?;
+        env_override("CODE_EXECUTOR_CACHE_MAX_BYTES", &mut self.max_bytes)?;
+        Ok(())
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_seconds)
+    }
+}
+
+/// Identifies an execution by everything that can influence its result
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    hash: String,
+    image: String,
+    image_digest: String,
+}
+
+impl CacheKey {
+    /// Hash the request's code, language, version, stdin and limits together with the image digest
+    pub fn new(request: &ExecuteRequest, image: &str, image_digest: &str) -> Self {
+        let mut hasher = Sha256::new();
+        // Length-prefix every field so that neighbouring values can't run into each other
+        let mut field = |bytes: &[u8]| {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        field(request.code.as_bytes());
+        field(request.language.as_bytes());
+        field(request.version.as_bytes());
+        field(&(request.input.len() as u64).to_le_bytes());
+        for line in &request.input {
+            field(line.as_bytes());
+        }
+        field(&request.timeout_seconds.to_le_bytes());
+        field(&request.memory_limit_mb.to_le_bytes());
+        field(image_digest.as_bytes());
+
+        Self {
+            hash: hex::encode(hasher.finalize()),
+            image: image.to_string(),
+            image_digest: image_digest.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: ExecuteResponse,
+    image: String,
+    stored_at: Instant,
+    size: usize,
+    sequence: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Insertion order for eviction; stale sequence numbers are skipped
+    order: VecDeque<(u64, String)>,
+    /// Last seen digest per image, used to drop results produced by a replaced image
+    image_digests: HashMap<String, String>,
+    total_bytes: usize,
+    next_sequence: u64,
+}
+
+/// Opt-in cache of `ExecuteResponse`s for deterministic programs.
+///
+/// Only results whose outcome doesn't depend on machine load are stored: timeouts and
+/// memory limit kills are always re-executed.
+#[derive(Debug)]
+pub struct ResultCache {
+    config: CacheConfig,
+    state: Mutex<State>,
+}
+
+impl ResultCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Look up a stored response, returning it with `cached` set
+    pub fn get(&self, key: &CacheKey) -> Option<ExecuteResponse> {
+        let mut state = self.state.lock().unwrap();
+        Self::observe_digest(&mut state, key);
+
+        let expired = match state.entries.get(&key.hash) {
+            Some(entry) => entry.stored_at.elapsed() > self.config.ttl(),
+            None => return None,
+        };
+        if expired {
+            Self::remove(&mut state, &key.hash);
+            return None;
+        }
+
+        state.entries.get(&key.hash).map(|entry| ExecuteResponse {
+            cached: true,
+            ..entry.response.clone()
+        })
+    }
+
+    /// Store a response if its status is deterministic and it fits in the cache
+    pub fn insert(&self, key: CacheKey, response: &ExecuteResponse) {
+        if !Self::is_cacheable(response) {
+            return;
+        }
+        let size = key.hash.len() + response.stdout.len() + response.stderr.len() + response.error_message.len();
+        if size > self.config.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        Self::observe_digest(&mut state, &key);
+        Self::remove(&mut state, &key.hash);
+
+        while state.total_bytes + size > self.config.max_bytes {
+            let Some((sequence, hash)) = state.order.pop_front() else {
+                break;
+            };
+            if state.entries.get(&hash).is_some_and(|entry| entry.sequence == sequence) {
+                Self::remove(&mut state, &hash);
+            }
+        }
+
+        // Forget order records of entries that were replaced or expired in the meantime
+        if state.order.len() > 2 * state.entries.len() + 16 {
+            let State { order, entries, .. } = &mut *state;
+            order.retain(|(sequence, hash)| entries.get(hash).is_some_and(|entry| entry.sequence == *sequence));
+        }
+
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.order.push_back((sequence, key.hash.clone()));
+        state.total_bytes += size;
+        state.entries.insert(key.hash, Entry {
+            response: response.clone(),
+            image: key.image,
+            stored_at: Instant::now(),
+            size,
+            sequence,
+        });
+    }
+
+    fn is_cacheable(response: &ExecuteResponse) -> bool {
+        matches!(
+            response.status(),
+            ExecutionStatus::Completed | ExecutionStatus::Failed | ExecutionStatus::RuntimeError
+                | ExecutionStatus::SecurityViolation | ExecutionStatus::CompileError
+        )
+    }
+
+    /// Drop every entry of an image once a different digest shows up for it
+    fn observe_digest(state: &mut State, key: &CacheKey) {
+        let previous = state.image_digests.insert(key.image.clone(), key.image_digest.clone());
+        if previous.is_some_and(|previous| previous != key.image_digest) {
+            let stale: Vec<String> = state.entries.iter()
+                .filter(|(_, entry)| entry.image == key.image)
+                .map(|(hash, _)| hash.clone())
+                .collect();
+            for hash in stale {
+                Self::remove(state, &hash);
+            }
+        }
+    }
+
+    fn remove(state: &mut State, hash: &str) {
+        if let Some(entry) = state.entries.remove(hash) {
+            state.total_bytes -= entry.size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:aaaa";
+
+    fn request() -> ExecuteRequest {
+        ExecuteRequest {
+            code: "print(input())".to_string(),
+            language: "python".to_string(),
+            version: "3.12".to_string(),
+            input: vec!["hello".to_string()],
+            timeout_seconds: 5,
+            memory_limit_mb: 128,
+            ..Default::default()
+        }
+    }
+
+    fn key(request: &ExecuteRequest) -> CacheKey {
+        CacheKey::new(request, "python:3.12", DIGEST)
+    }
+
+    fn response(stdout: &str) -> ExecuteResponse {
+        ExecuteResponse {
+            status: ExecutionStatus::Completed.into(),
+            stdout: stdout.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn cache(max_bytes: usize) -> ResultCache {
+        ResultCache::new(CacheConfig { ttl_seconds: 60, max_bytes })
+    }
+
+    #[test]
+    fn stored_results_are_served_as_cached() {
+        let cache = cache(DEFAULT_MAX_BYTES);
+        cache.insert(key(&request()), &response("hello\n"));
+
+        let cached = cache.get(&key(&request())).unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.stdout, "hello\n");
+
+        // 부하에 따라 달라지는 결과는 저장하지 않음
+        let other = ExecuteRequest { code: "while True: pass".to_string(), ..request() };
+        cache.insert(key(&other), &ExecuteResponse { status: ExecutionStatus::Timeout.into(), ..Default::default() });
+        assert!(cache.get(&key(&other)).is_none());
+    }
+
+    #[test]
+    fn results_expire_after_the_ttl() {
+        let cache = cache(DEFAULT_MAX_BYTES);
+        cache.insert(key(&request()), &response("hello\n"));
+        cache.state.lock().unwrap().entries.values_mut()
+            .for_each(|entry| entry.stored_at -= Duration::from_secs(61));
+
+        assert!(cache.get(&key(&request())).is_none());
+        let state = cache.state.lock().unwrap();
+        assert!(state.entries.is_empty());
+        assert_eq!(state.total_bytes, 0);
+    }
+
+    #[test]
+    fn oldest_results_are_evicted_beyond_max_bytes() {
+        let requests: Vec<ExecuteRequest> = (0..4)
+            .map(|i| ExecuteRequest { code: format!("print({})", i), ..request() })
+            .collect();
+        // 키 해시(64자)와 출력 10바이트짜리 항목 세 개가 들어가는 크기
+        let cache = cache(3 * (64 + 10));
+        for request in &requests {
+            cache.insert(key(request), &response("0123456789"));
+        }
+
+        assert!(cache.get(&key(&requests[0])).is_none());
+        assert!(requests[1..].iter().all(|request| cache.get(&key(request)).is_some()));
+        assert_eq!(cache.state.lock().unwrap().total_bytes, 3 * (64 + 10));
+
+        // 한도보다 큰 결과는 저장하지 않고 기존 항목도 유지
+        let large = ExecuteRequest { code: "print('x' * 1000)".to_string(), ..request() };
+        cache.insert(key(&large), &response(&"x".repeat(1000)));
+        assert!(cache.get(&key(&large)).is_none());
+        assert!(cache.get(&key(&requests[1])).is_some());
+    }
+
+    #[test]
+    fn a_new_image_digest_drops_the_image_results() {
+        let cache = cache(DEFAULT_MAX_BYTES);
+        let other = ExecuteRequest { code: "print(1)".to_string(), ..request() };
+        cache.insert(key(&request()), &response("hello\n"));
+        cache.insert(key(&other), &response("1\n"));
+        let node = ExecuteRequest { language: "node".to_string(), version: "20".to_string(), ..request() };
+        cache.insert(CacheKey::new(&node, "node:20", DIGEST), &response("hello\n"));
+
+        let rebuilt = CacheKey::new(&other, "python:3.12", "sha256:bbbb");
+        assert!(cache.get(&rebuilt).is_none());
+
+        assert!(cache.get(&key(&request())).is_none());
+        assert!(cache.get(&CacheKey::new(&node, "node:20", DIGEST)).is_some());
+        assert_eq!(cache.state.lock().unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn keys_change_with_everything_that_affects_the_result() {
+        let base = key(&request());
+        assert_eq!(key(&request()), base);
+
+        let variants = [
+            ExecuteRequest { code: "print(input() * 2)".to_string(), ..request() },
+            ExecuteRequest { language: "python3".to_string(), ..request() },
+            ExecuteRequest { version: "3.11".to_string(), ..request() },
+            ExecuteRequest { input: vec!["hell".to_string(), "o".to_string()], ..request() },
+            ExecuteRequest { input: Vec::new(), ..request() },
+            ExecuteRequest { timeout_seconds: 6, ..request() },
+            ExecuteRequest { memory_limit_mb: 256, ..request() },
+        ];
+        for variant in &variants {
+            assert_ne!(key(variant).hash, base.hash, "{:?}", variant);
+        }
+        assert_ne!(CacheKey::new(&request(), "python:3.12", "sha256:bbbb").hash, base.hash);
+    }
+}
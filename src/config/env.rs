@@ -0,0 +1,16 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+
+/// Replace `target` with the parsed value of the environment variable `name`, if it is set
+pub fn env_override<T>(name: &str, target: &mut T) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = std::env::var(name) {
+        *target = value.trim().parse()
+            .map_err(|e| anyhow!("Invalid {} '{}': {}", name, value, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,5 @@
+mod env;
+mod server;
+
+pub use env::env_override;
+pub use server::{LimitsConfig, ServerConfig};
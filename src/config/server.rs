@@ -0,0 +1,285 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use super::env_override;
+use crate::backend::BackendConfig;
+use crate::cache::CacheConfig;
+use crate::container::{DockerConfig, PoolConfig};
+use crate::history::HistoryConfig;
+use crate::language::LanguageLimits;
+use crate::scheduler::SchedulerConfig;
+use crate::webhook::WebhookConfig;
+
+/// Defaults and upper bounds for the limits a request may ask for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Used when a request leaves `timeout_seconds` at 0
+    pub default_timeout_seconds: u32,
+    pub max_timeout_seconds: u32,
+    /// Used when a request leaves `memory_limit_mb` at 0
+    pub default_memory_limit_mb: u32,
+    pub max_memory_limit_mb: u32,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout_seconds: 10,
+            max_timeout_seconds: 60,
+            default_memory_limit_mb: 256,
+            max_memory_limit_mb: 2048,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Apply `CODE_EXECUTOR_DEFAULT_TIMEOUT_SECONDS`, `CODE_EXECUTOR_MAX_TIMEOUT_SECONDS`,
+    /// `CODE_EXECUTOR_DEFAULT_MEMORY_LIMIT_MB` and `CODE_EXECUTOR_MAX_MEMORY_LIMIT_MB`
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_DEFAULT_TIMEOUT_SECONDS", &mut self.default_timeout_seconds)?;
+        env_override("CODE_EXECUTOR_MAX_TIMEOUT_SECONDS", &mut self.max_timeout_seconds)?;
+        env_override("CODE_EXECUTOR_DEFAULT_MEMORY_LIMIT_MB", &mut self.default_memory_limit_mb)?;
+        env_override("CODE_EXECUTOR_MAX_MEMORY_LIMIT_MB", &mut self.max_memory_limit_mb)?;
+        Ok(())
+    }
+
+    /// Describe every invalid setting
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.default_timeout_seconds == 0 || self.default_timeout_seconds > self.max_timeout_seconds {
+            errors.push(format!("default_timeout_seconds ({}) must be between 1 and max_timeout_seconds ({})",
+                                self.default_timeout_seconds, self.max_timeout_seconds));
+        }
+        if self.default_memory_limit_mb == 0 || self.default_memory_limit_mb > self.max_memory_limit_mb {
+            errors.push(format!("default_memory_limit_mb ({}) must be between 1 and max_memory_limit_mb ({})",
+                                self.default_memory_limit_mb, self.max_memory_limit_mb));
+        }
+        errors
+    }
+
+    /// Fill in defaults for unset (0) request limits and reject values above the maximum.
+    /// The language's own defaults take precedence over the server-wide ones.
+    pub fn resolve(&self, timeout_seconds: i32, memory_limit_mb: i32, language: &LanguageLimits) -> Result<(u32, u32)> {
+        let timeout_seconds = match timeout_seconds {
+            0 => language.timeout_seconds.unwrap_or(self.default_timeout_seconds),
+            value if value < 0 || value as u32 > self.max_timeout_seconds => {
+                return Err(anyhow!("timeout_seconds must be between 1 and {}", self.max_timeout_seconds));
+            }
+            value => value as u32,
+        };
+        let memory_limit_mb = match memory_limit_mb {
+            0 => language.memory_limit_mb.unwrap_or(self.default_memory_limit_mb),
+            value if value < 0 || value as u32 > self.max_memory_limit_mb => {
+                return Err(anyhow!("memory_limit_mb must be between 1 and {}", self.max_memory_limit_mb));
+            }
+            value => value as u32,
+        };
+        Ok((timeout_seconds, memory_limit_mb))
+    }
+}
+
+/// Everything that differs between deployments.
+///
+/// Values come from the built-in defaults, then the TOML file, then `CODE_EXECUTOR_*`
+/// environment variables, then the command line flags the binary passes to `load`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_address: String,
+    /// Directory holding one `<language>:<version>/language.toml` per supported language
+    pub languages_dir: PathBuf,
+    /// On SIGTERM, how long in-flight executions may keep running, and their callbacks be
+    /// delivered, before their containers are killed
+    pub drain_timeout_seconds: u64,
+    pub backend: BackendConfig,
+    pub docker: DockerConfig,
+    pub pool: PoolConfig,
+    pub limits: LimitsConfig,
+    pub scheduler: SchedulerConfig,
+    pub cache: CacheConfig,
+    pub webhook: WebhookConfig,
+    pub history: HistoryConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_address: "[::]:50051".to_string(),
+            languages_dir: PathBuf::from("dockerfiles"),
+            drain_timeout_seconds: 30,
+            backend: BackendConfig::default(),
+            docker: DockerConfig::default(),
+            pool: PoolConfig::default(),
+            limits: LimitsConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            cache: CacheConfig::default(),
+            webhook: WebhookConfig::default(),
+            history: HistoryConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Read the optional config file, apply environment overrides, then `overrides`
+    /// (the command line), so that each layer takes precedence over the ones before it
+    pub fn load(path: Option<&Path>, overrides: impl FnOnce(&mut Self)) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file {}", path.display()))?;
+                toml::from_str(&contents)
+                    .with_context(|| format!("Failed to parse config file {}", path.display()))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        overrides(&mut config);
+        Ok(config)
+    }
+
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_LISTEN_ADDRESS", &mut self.listen_address)?;
+        env_override("CODE_EXECUTOR_DRAIN_TIMEOUT_SECONDS", &mut self.drain_timeout_seconds)?;
+        env_override("CODE_EXECUTOR_LANGUAGES_DIR", &mut self.languages_dir)?;
+        self.backend.apply_env()?;
+        self.docker.apply_env()?;
+        self.pool.apply_env()?;
+        self.limits.apply_env()?;
+        self.scheduler.apply_env()?;
+        self.cache.apply_env()?;
+        self.webhook.apply_env()?;
+        self.history.apply_env()?;
+        Ok(())
+    }
+
+    /// Check every section, reporting all problems at once
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.listen_address.parse::<SocketAddr>() {
+            errors.push(format!("listen_address '{}' is not a socket address: {}", self.listen_address, e));
+        }
+        let sections = [
+            ("backend", self.backend.validate()),
+            ("docker", self.docker.validate()),
+            ("pool", self.pool.validate()),
+            ("limits", self.limits.validate()),
+            ("scheduler", self.scheduler.validate()),
+            ("webhook", self.webhook.validate()),
+        ];
+        for (section, section_errors) in sections {
+            errors.extend(section_errors.into_iter().map(|error| format!("{}.{}", section, error)));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid configuration:\n  - {}", errors.join("\n  - ")))
+        }
+    }
+
+    pub fn listen_address(&self) -> Result<SocketAddr> {
+        Ok(self.listen_address.parse()?)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_seconds)
+    }
+
+    /// The effective configuration as TOML, with secrets masked
+    pub fn to_redacted_toml(&self) -> Result<String> {
+        let mut redacted = self.clone();
+        if redacted.webhook.secret.is_some() {
+            redacted.webhook.secret = Some("<redacted>".to_string());
+        }
+        Ok(toml::to_string_pretty(&redacted)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn command_line_beats_environment_beats_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, r#"
+            drain_timeout_seconds = 5
+
+            [scheduler]
+            max_concurrent = 2
+            max_queue_length = 10
+        "#).unwrap();
+
+        // 이 테스트만 쓰는 변수들이라 병렬 테스트와 겹치지 않음
+        std::env::set_var("CODE_EXECUTOR_MAX_QUEUE_LENGTH", "20");
+        std::env::set_var("CODE_EXECUTOR_DRAIN_TIMEOUT_SECONDS", "7");
+        let config = ServerConfig::load(Some(file.path()), |config| config.drain_timeout_seconds = 9);
+        std::env::remove_var("CODE_EXECUTOR_MAX_QUEUE_LENGTH");
+        std::env::remove_var("CODE_EXECUTOR_DRAIN_TIMEOUT_SECONDS");
+        let config = config.unwrap();
+
+        assert_eq!(config.scheduler.max_concurrent, 2);
+        assert_eq!(config.scheduler.max_queue_length, 20);
+        assert_eq!(config.drain_timeout_seconds, 9);
+        assert_eq!(config.listen_address, ServerConfig::default().listen_address);
+    }
+
+    #[test]
+    fn broken_files_are_reported() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "[limits]\nmax_timeout = 60\n").unwrap();
+        let error = ServerConfig::load(Some(file.path()), |_| {}).unwrap_err();
+        assert!(format!("{:#}", error).contains("max_timeout"), "{:#}", error);
+
+        assert!(ServerConfig::load(Some(Path::new("/nonexistent/code-executor.toml")), |_| {}).is_err());
+    }
+
+    #[test]
+    fn validation_reports_every_problem_with_its_section() {
+        assert!(ServerConfig::default().validate().is_ok());
+
+        let mut config = ServerConfig { listen_address: "localhost".to_string(), ..Default::default() };
+        config.limits.default_timeout_seconds = 120;
+        config.limits.default_memory_limit_mb = 0;
+        config.scheduler.max_concurrent = 0;
+        let error = config.validate().unwrap_err().to_string();
+
+        for expected in [
+            "listen_address 'localhost'",
+            "limits.default_timeout_seconds (120) must be between 1 and max_timeout_seconds (60)",
+            "limits.default_memory_limit_mb (0)",
+            "scheduler.max_concurrent must be greater than 0",
+        ] {
+            assert!(error.contains(expected), "missing '{}' in:\n{}", expected, error);
+        }
+    }
+
+    #[test]
+    fn unset_request_limits_take_the_language_then_server_defaults() {
+        let limits = LimitsConfig::default();
+        let language = LanguageLimits { timeout_seconds: Some(20), memory_limit_mb: None };
+
+        assert_eq!(limits.resolve(0, 0, &LanguageLimits::default()).unwrap(), (10, 256));
+        assert_eq!(limits.resolve(0, 0, &language).unwrap(), (20, 256));
+        assert_eq!(limits.resolve(5, 512, &language).unwrap(), (5, 512));
+        assert_eq!(limits.resolve(60, 2048, &language).unwrap(), (60, 2048));
+    }
+
+    #[test]
+    fn request_limits_beyond_the_maximum_are_rejected() {
+        let limits = LimitsConfig::default();
+        let language = LanguageLimits::default();
+
+        let error = limits.resolve(61, 0, &language).unwrap_err();
+        assert_eq!(error.to_string(), "timeout_seconds must be between 1 and 60");
+        assert!(limits.resolve(-1, 0, &language).is_err());
+        let error = limits.resolve(0, 2049, &language).unwrap_err();
+        assert_eq!(error.to_string(), "memory_limit_mb must be between 1 and 2048");
+        assert!(limits.resolve(0, i32::MIN, &language).is_err());
+    }
+}
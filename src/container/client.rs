@@ -0,0 +1,137 @@
+use std::pin::Pin;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use bollard::Docker;
+use bollard::container::{
+    AttachContainerOptions, Config, CreateContainerOptions, KillContainerOptions, LogOutput, LogsOptions,
+    StartContainerOptions, UpdateContainerOptions, WaitContainerOptions,
+};
+use bollard::errors::Error as DockerError;
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use futures_util::{Stream, StreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use super::images::ImageApi;
+use super::reaper::ContainerApi;
+
+/// Container output as Docker delivers it, one chunk at a time
+pub type LogStream = Pin<Box<dyn Stream<Item = Result<LogOutput, DockerError>> + Send>>;
+
+/// A command started inside a running container
+pub struct ExecSession {
+    pub id: String,
+    pub output: LogStream,
+    pub input: Pin<Box<dyn AsyncWrite + Send>>,
+}
+
+/// Every Docker call `ContainerManager` makes, so its logic can run against a fake daemon
+#[async_trait]
+pub trait DockerClient: ContainerApi + ImageApi {
+    /// Create a container and return its id
+    async fn create_container(&self, name: &str, config: Config<String>) -> Result<String>;
+    async fn start_container(&self, id: &str) -> Result<()>;
+    /// Write `input` to the stdin of a started container and close it
+    async fn write_stdin(&self, id: &str, input: &[u8]) -> Result<()>;
+    /// Wait for the container to stop and return its exit code
+    async fn wait_container(&self, id: &str) -> Result<i64>;
+    /// Everything the container has written so far
+    async fn logs(&self, id: &str) -> Vec<Result<LogOutput, DockerError>>;
+    async fn kill_container(&self, id: &str) -> Result<()>;
+    /// Whether the kernel OOM killer stopped the container
+    async fn oom_killed(&self, id: &str) -> Result<bool>;
+    /// Change the memory limit (and swap limit, so no swap is used) of a running container
+    async fn update_memory(&self, id: &str, bytes: i64) -> Result<()>;
+    /// Run `cmd` inside a running container with stdout and stderr attached
+    async fn start_exec(&self, id: &str, cmd: Vec<String>, env: Vec<String>, attach_stdin: bool) -> Result<ExecSession>;
+    /// Exit code of a finished exec, `None` while it is still running
+    async fn exec_exit_code(&self, exec_id: &str) -> Result<Option<i64>>;
+}
+
+#[async_trait]
+impl DockerClient for Docker {
+    async fn create_container(&self, name: &str, config: Config<String>) -> Result<String> {
+        let options = CreateContainerOptions { name, platform: None };
+        Ok(Docker::create_container(self, Some(options), config).await?.id)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<()> {
+        Docker::start_container(self, id, None::<StartContainerOptions<String>>).await?;
+        Ok(())
+    }
+
+    async fn write_stdin(&self, id: &str, input: &[u8]) -> Result<()> {
+        let attach = self.attach_container::<String>(id, Some(AttachContainerOptions {
+            stream: Some(true),
+            stdin: Some(true),
+            stdout: Some(false),
+            stderr: Some(false),
+            logs: Some(false),
+            detach_keys: None,
+        })).await?;
+
+        let mut stdin = attach.input;
+        stdin.write_all(input).await?;
+        stdin.shutdown().await?;
+        Ok(())
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64> {
+        let mut wait = Docker::wait_container(self, id, None::<WaitContainerOptions<String>>);
+        match wait.next().await {
+            Some(Ok(exit)) => Ok(exit.status_code),
+            // bollard은 0이 아닌 종료 코드를 에러로 돌려줌
+            Some(Err(DockerError::DockerContainerWaitError { code, .. })) => Ok(code),
+            Some(Err(e)) => Err(e.into()),
+            None => Err(anyhow!("Container {} did not return an exit status", id)),
+        }
+    }
+
+    async fn logs(&self, id: &str) -> Vec<Result<LogOutput, DockerError>> {
+        Docker::logs(self, id, Some(LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        })).collect().await
+    }
+
+    async fn kill_container(&self, id: &str) -> Result<()> {
+        Docker::kill_container(self, id, None::<KillContainerOptions<String>>).await?;
+        Ok(())
+    }
+
+    async fn oom_killed(&self, id: &str) -> Result<bool> {
+        let inspect = self.inspect_container(id, None).await?;
+        Ok(inspect.state.and_then(|state| state.oom_killed).unwrap_or(false))
+    }
+
+    async fn update_memory(&self, id: &str, bytes: i64) -> Result<()> {
+        self.update_container(id, UpdateContainerOptions::<String> {
+            memory: Some(bytes),
+            memory_swap: Some(bytes),
+            ..Default::default()
+        }).await?;
+        Ok(())
+    }
+
+    async fn start_exec(&self, id: &str, cmd: Vec<String>, env: Vec<String>, attach_stdin: bool) -> Result<ExecSession> {
+        let exec = self.create_exec(id, CreateExecOptions {
+            cmd: Some(cmd),
+            env: Some(env),
+            working_dir: Some("/workspace".to_string()),
+            user: Some(super::manager::RUN_SCRIPT_USER.to_string()),
+            attach_stdin: Some(attach_stdin),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            ..Default::default()
+        }).await?;
+
+        match Docker::start_exec(self, &exec.id, None).await? {
+            StartExecResults::Attached { output, input } => Ok(ExecSession { id: exec.id, output, input }),
+            StartExecResults::Detached => Err(anyhow!("Exec {} started detached", exec.id)),
+        }
+    }
+
+    async fn exec_exit_code(&self, exec_id: &str) -> Result<Option<i64>> {
+        Ok(self.inspect_exec(exec_id).await?.exit_code)
+    }
+}
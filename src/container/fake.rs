@@ -0,0 +1,361 @@
+//! In-memory `DockerClient` for tests. Nothing runs: each container or exec plays back the
+//! next scripted run, and calls can be made to fail to exercise error paths.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use bollard::container::{Config, LogOutput};
+use bollard::errors::Error as DockerError;
+use futures_util::StreamExt;
+use tokio::io::AsyncWrite;
+use tokio::sync::Notify;
+
+use super::client::{DockerClient, ExecSession};
+use super::images::ImageApi;
+use super::reaper::{ContainerApi, LabeledContainer};
+
+/// Exit code reported for killed containers, as Docker does for SIGKILL
+const KILLED_EXIT_CODE: i64 = 137;
+
+/// What the program in a container or exec does
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptedRun {
+    /// `None` keeps running until the container is killed
+    pub exit_code: Option<i64>,
+    pub stdout: String,
+    pub stderr: String,
+    pub oom_killed: bool,
+}
+
+impl ScriptedRun {
+    pub fn exits(exit_code: i64) -> Self {
+        Self { exit_code: Some(exit_code), ..Default::default() }
+    }
+
+    pub fn hangs() -> Self {
+        Self::default()
+    }
+
+    pub fn stdout(mut self, stdout: &str) -> Self {
+        self.stdout = stdout.to_string();
+        self
+    }
+
+    pub fn stderr(mut self, stderr: &str) -> Self {
+        self.stderr = stderr.to_string();
+        self
+    }
+
+    pub fn oom_killed(mut self) -> Self {
+        self.oom_killed = true;
+        self
+    }
+}
+
+/// Everything done to one container
+#[derive(Debug, Clone)]
+pub struct FakeContainer {
+    pub id: String,
+    pub name: String,
+    pub config: Config<String>,
+    pub started: bool,
+    pub killed: bool,
+    pub removed: bool,
+    pub stdin: Vec<u8>,
+    pub memory: Option<i64>,
+    pub execs: Vec<Vec<String>>,
+    /// Assigned from the script when the container's program is first looked at
+    run: Option<ScriptedRun>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    images: HashMap<String, String>,
+    runs: VecDeque<ScriptedRun>,
+    containers: BTreeMap<String, FakeContainer>,
+    /// Exec id to the container it runs in
+    execs: HashMap<String, String>,
+    failing: HashSet<&'static str>,
+    next_id: usize,
+}
+
+impl State {
+    fn container(&mut self, id: &str) -> Result<&mut FakeContainer> {
+        self.containers.get_mut(id)
+            .filter(|container| !container.removed)
+            .ok_or_else(|| anyhow!("No such container: {}", id))
+    }
+
+    fn check(&self, call: &'static str) -> Result<()> {
+        if self.failing.contains(call) {
+            return Err(anyhow!("{} failed", call));
+        }
+        Ok(())
+    }
+
+    /// The run of a container, taking the next scripted one (or a clean exit) the first time
+    fn run(&mut self, id: &str) -> Result<ScriptedRun> {
+        let next = self.runs.front().cloned();
+        let container = self.container(id)?;
+        if container.run.is_none() {
+            container.run = Some(next.clone().unwrap_or_else(|| ScriptedRun::exits(0)));
+            if next.is_some() {
+                self.runs.pop_front();
+            }
+        }
+        Ok(self.containers[id].run.clone().unwrap())
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{}", prefix, self.next_id)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeDocker {
+    state: Arc<Mutex<State>>,
+    killed: Arc<Notify>,
+}
+
+impl FakeDocker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_image(&self, image: &str, digest: &str) {
+        self.state.lock().unwrap().images.insert(image.to_string(), digest.to_string());
+    }
+
+    /// Queue what the next container or exec to run will do
+    pub fn script(&self, run: ScriptedRun) {
+        self.state.lock().unwrap().runs.push_back(run);
+    }
+
+    /// Make every call named `call` (e.g. `"start_container"`) fail from now on
+    pub fn fail(&self, call: &'static str) {
+        self.state.lock().unwrap().failing.insert(call);
+    }
+
+    pub fn containers(&self) -> Vec<FakeContainer> {
+        self.state.lock().unwrap().containers.values().cloned().collect()
+    }
+
+    /// Containers created and not removed yet
+    pub fn live_containers(&self) -> Vec<FakeContainer> {
+        self.containers().into_iter().filter(|container| !container.removed).collect()
+    }
+
+    async fn until_killed(&self, id: &str) {
+        loop {
+            let notified = self.killed.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let killed = self.state.lock().unwrap().containers.get(id)
+                .is_none_or(|container| container.killed || container.removed);
+            if killed {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+fn output(run: &ScriptedRun) -> Vec<Result<LogOutput, DockerError>> {
+    let mut output = Vec::new();
+    if !run.stdout.is_empty() {
+        output.push(Ok(LogOutput::StdOut { message: run.stdout.clone().into() }));
+    }
+    if !run.stderr.is_empty() {
+        output.push(Ok(LogOutput::StdErr { message: run.stderr.clone().into() }));
+    }
+    output
+}
+
+#[async_trait]
+impl ContainerApi for FakeDocker {
+    async fn list_labeled(&self, label: &str) -> Result<Vec<LabeledContainer>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.containers.values()
+            .filter(|container| !container.removed)
+            .filter(|container| container.config.labels.as_ref().is_some_and(|labels| labels.contains_key(label)))
+            .map(|container| LabeledContainer {
+                id: container.id.clone(),
+                labels: container.config.labels.clone().unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn force_remove(&self, id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("force_remove")?;
+        let container = state.container(id)?;
+        container.killed = true;
+        container.removed = true;
+        self.killed.notify_waiters();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ImageApi for FakeDocker {
+    async fn image_id(&self, image: &str) -> Result<Option<String>> {
+        let state = self.state.lock().unwrap();
+        state.check("image_id")?;
+        Ok(state.images.get(image).cloned())
+    }
+
+    async fn build_image(&self, image: &str, _context: Vec<u8>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("build_image")?;
+        state.images.insert(image.to_string(), format!("sha256:{}", image));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DockerClient for FakeDocker {
+    async fn create_container(&self, name: &str, config: Config<String>) -> Result<String> {
+        let mut state = self.state.lock().unwrap();
+        state.check("create_container")?;
+        let image = config.image.clone().unwrap_or_default();
+        if !state.images.values().any(|digest| *digest == image) && !state.images.contains_key(&image) {
+            return Err(anyhow!("No such image: {}", image));
+        }
+        let id = state.next_id("container");
+        state.containers.insert(id.clone(), FakeContainer {
+            id: id.clone(),
+            name: name.to_string(),
+            config,
+            started: false,
+            killed: false,
+            removed: false,
+            stdin: Vec::new(),
+            memory: None,
+            execs: Vec::new(),
+            run: None,
+        });
+        Ok(id)
+    }
+
+    async fn start_container(&self, id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("start_container")?;
+        state.container(id)?.started = true;
+        Ok(())
+    }
+
+    async fn write_stdin(&self, id: &str, input: &[u8]) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("write_stdin")?;
+        state.container(id)?.stdin.extend_from_slice(input);
+        Ok(())
+    }
+
+    async fn wait_container(&self, id: &str) -> Result<i64> {
+        let run = {
+            let mut state = self.state.lock().unwrap();
+            state.check("wait_container")?;
+            state.run(id)?
+        };
+        match run.exit_code {
+            Some(exit_code) => Ok(exit_code),
+            None => {
+                self.until_killed(id).await;
+                Ok(KILLED_EXIT_CODE)
+            }
+        }
+    }
+
+    async fn logs(&self, id: &str) -> Vec<Result<LogOutput, DockerError>> {
+        let mut state = self.state.lock().unwrap();
+        state.run(id).map(|run| output(&run)).unwrap_or_default()
+    }
+
+    async fn kill_container(&self, id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("kill_container")?;
+        state.container(id)?.killed = true;
+        self.killed.notify_waiters();
+        Ok(())
+    }
+
+    async fn oom_killed(&self, id: &str) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        state.check("oom_killed")?;
+        Ok(state.container(id)?.run.as_ref().is_some_and(|run| run.oom_killed))
+    }
+
+    async fn update_memory(&self, id: &str, bytes: i64) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.check("update_memory")?;
+        state.container(id)?.memory = Some(bytes);
+        Ok(())
+    }
+
+    async fn start_exec(&self, id: &str, cmd: Vec<String>, _env: Vec<String>, _attach_stdin: bool) -> Result<ExecSession> {
+        let (exec_id, run) = {
+            let mut state = self.state.lock().unwrap();
+            state.check("start_exec")?;
+            let container = state.container(id)?;
+            if !container.started {
+                return Err(anyhow!("Container {} is not running", id));
+            }
+            container.execs.push(cmd);
+            let exec_id = state.next_id("exec");
+            state.execs.insert(exec_id.clone(), id.to_string());
+            (exec_id, state.run(id)?)
+        };
+
+        let chunks = futures_util::stream::iter(output(&run));
+        let output = if run.exit_code.is_some() {
+            chunks.boxed()
+        } else {
+            // 종료 코드가 없으면 컨테이너가 kill 될 때까지 출력 스트림을 열어 둠
+            let fake = self.clone();
+            let id = id.to_string();
+            let killed = futures_util::stream::once(async move { fake.until_killed(&id).await })
+                .filter_map(|()| async { None });
+            chunks.chain(killed).boxed()
+        };
+        let input = Box::pin(StdinRecorder { state: self.state.clone(), container_id: id.to_string() });
+        Ok(ExecSession { id: exec_id, output, input })
+    }
+
+    async fn exec_exit_code(&self, exec_id: &str) -> Result<Option<i64>> {
+        let mut state = self.state.lock().unwrap();
+        state.check("exec_exit_code")?;
+        let container_id = state.execs.get(exec_id).cloned().ok_or_else(|| anyhow!("No such exec: {}", exec_id))?;
+        Ok(state.run(&container_id)?.exit_code)
+    }
+}
+
+/// Appends everything written to an exec's stdin to its container's record
+struct StdinRecorder {
+    state: Arc<Mutex<State>>,
+    container_id: String,
+}
+
+impl AsyncWrite for StdinRecorder {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        let mut state = self.state.lock().unwrap();
+        match state.container(&self.container_id) {
+            Ok(container) => {
+                container.stdin.extend_from_slice(buf);
+                Poll::Ready(Ok(buf.len()))
+            }
+            Err(e) => Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, e.to_string()))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
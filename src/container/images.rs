@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use bollard::Docker;
+use bollard::errors::Error as DockerError;
+use bollard::image::BuildImageOptions;
+use futures_util::StreamExt;
+
+/// Size of a tar header and of the blocks file contents are padded to
+const TAR_BLOCK: usize = 512;
+/// ustar stores the path in a 100 byte field; longer paths would need the prefix field
+const TAR_NAME_LENGTH: usize = 100;
+
+/// An image a language needs and the directory it can be built from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSource {
+    pub image: String,
+    /// Directory with a `Dockerfile`; `None` when the image can only be pulled or built by hand
+    pub context: Option<PathBuf>,
+}
+
+/// Outcome of checking every image at startup
+#[derive(Debug, Default, PartialEq)]
+pub struct ImageReport {
+    /// Image id (`sha256:...`) per available image
+    pub digests: BTreeMap<String, String>,
+    /// Why each unavailable image could not be used
+    pub missing: BTreeMap<String, String>,
+    /// Images built during the check
+    pub built: Vec<String>,
+}
+
+/// The part of the Docker API needed to check and build images
+#[async_trait]
+pub trait ImageApi: Send + Sync {
+    /// Id of the local image, or `None` when it does not exist
+    async fn image_id(&self, image: &str) -> Result<Option<String>>;
+    /// Build `image` from an uncompressed tar of the build context
+    async fn build_image(&self, image: &str, context: Vec<u8>) -> Result<()>;
+}
+
+#[async_trait]
+impl ImageApi for Docker {
+    async fn image_id(&self, image: &str) -> Result<Option<String>> {
+        match self.inspect_image(image).await {
+            Ok(inspect) => inspect.id.map(Some).ok_or_else(|| anyhow!("Image {} has no id", image)),
+            Err(DockerError::DockerResponseServerError { status_code: 404, .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn build_image(&self, image: &str, context: Vec<u8>) -> Result<()> {
+        let options = BuildImageOptions {
+            dockerfile: "Dockerfile",
+            t: image,
+            rm: true,
+            forcerm: true,
+            ..Default::default()
+        };
+        let mut output = Docker::build_image(self, options, None, Some(context.into()));
+        while let Some(info) = output.next().await {
+            let info = info?;
+            if let Some(error) = info.error {
+                return Err(anyhow!("Building {} failed: {}", image, error.trim()));
+            }
+            if let Some(line) = info.stream.as_deref().map(str::trim).filter(|line| !line.is_empty()) {
+                tracing::debug!("[build {}] {}", image, line);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Look up every image and, when `build_missing` is set, build the missing ones from their
+/// context directory. Problems are collected in the report instead of failing the check,
+/// so one broken image does not take the other languages down.
+pub async fn check_images(api: &dyn ImageApi, sources: &[ImageSource], build_missing: bool) -> ImageReport {
+    // 같은 이미지를 여러 언어가 쓰면 빌드 컨텍스트가 있는 쪽을 사용
+    let mut contexts: BTreeMap<&str, Option<&Path>> = BTreeMap::new();
+    for source in sources {
+        let context = contexts.entry(&source.image).or_default();
+        if context.is_none() {
+            *context = source.context.as_deref();
+        }
+    }
+
+    let mut report = ImageReport::default();
+    for (image, context) in contexts {
+        match api.image_id(image).await {
+            Ok(Some(digest)) => {
+                report.digests.insert(image.to_string(), digest);
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                report.missing.insert(image.to_string(), format!("inspect failed: {}", e));
+                continue;
+            }
+        }
+
+        let reason = match (build_missing, context) {
+            (false, _) => "not found locally".to_string(),
+            (true, None) => "not found locally and there is no Dockerfile to build it from".to_string(),
+            (true, Some(context)) => {
+                tracing::info!("Building missing image {} from {}", image, context.display());
+                match build(api, image, context).await {
+                    Ok(digest) => {
+                        report.digests.insert(image.to_string(), digest);
+                        report.built.push(image.to_string());
+                        continue;
+                    }
+                    Err(e) => format!("build failed: {}", e),
+                }
+            }
+        };
+        report.missing.insert(image.to_string(), reason);
+    }
+    report
+}
+
+async fn build(api: &dyn ImageApi, image: &str, context: &Path) -> Result<String> {
+    api.build_image(image, build_context(context)?).await?;
+    api.image_id(image).await?.ok_or_else(|| anyhow!("Image {} is still missing after the build", image))
+}
+
+/// Pack a directory into an uncompressed ustar archive for the Docker build API
+pub fn build_context(dir: &Path) -> Result<Vec<u8>> {
+    let mut archive = Vec::new();
+    append_directory(&mut archive, dir, Path::new(""))?;
+    // 아카이브 끝은 빈 블록 두 개
+    archive.resize(archive.len() + 2 * TAR_BLOCK, 0);
+    Ok(archive)
+}
+
+fn append_directory(archive: &mut Vec<u8>, root: &Path, relative: &Path) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(root.join(relative))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        let name = relative.join(path.file_name().unwrap_or_default());
+        let metadata = std::fs::metadata(&path)?;
+        if metadata.is_dir() {
+            append_entry(archive, &format!("{}/", name.display()), b'5', 0o755, &[])?;
+            append_directory(archive, root, &name)?;
+        } else if metadata.is_file() {
+            let mode = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o7777;
+            append_entry(archive, &name.display().to_string(), b'0', mode, &std::fs::read(&path)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn append_entry(archive: &mut Vec<u8>, name: &str, kind: u8, mode: u32, contents: &[u8]) -> Result<()> {
+    if name.len() > TAR_NAME_LENGTH {
+        return Err(anyhow!("Path {} in the build context is too long", name));
+    }
+
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], mode as u64);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], contents.len() as u64);
+    write_octal(&mut header[136..148], 0);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // 체크섬은 체크섬 필드를 공백으로 채운 상태에서 계산
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+    write_octal(&mut header[148..155], checksum);
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(contents);
+    archive.resize(archive.len().next_multiple_of(TAR_BLOCK), 0);
+    Ok(())
+}
+
+/// Zero padded octal followed by a NUL, filling the field
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockApi {
+        images: Mutex<HashMap<String, String>>,
+        built: Mutex<Vec<(String, usize)>>,
+        broken_builds: Vec<String>,
+    }
+
+    #[async_trait]
+    impl ImageApi for MockApi {
+        async fn image_id(&self, image: &str) -> Result<Option<String>> {
+            Ok(self.images.lock().unwrap().get(image).cloned())
+        }
+
+        async fn build_image(&self, image: &str, context: Vec<u8>) -> Result<()> {
+            if self.broken_builds.iter().any(|broken| broken == image) {
+                anyhow::bail!("RUN apt-get install failed");
+            }
+            self.built.lock().unwrap().push((image.to_string(), context.len()));
+            self.images.lock().unwrap().insert(image.to_string(), format!("sha256:{}", image));
+            Ok(())
+        }
+    }
+
+    fn source(image: &str, context: Option<&Path>) -> ImageSource {
+        ImageSource { image: image.to_string(), context: context.map(Path::to_path_buf) }
+    }
+
+    fn context_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Dockerfile"), "FROM scratch\n").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn reports_missing_images_without_building() {
+        let api = MockApi::default();
+        api.images.lock().unwrap().insert("python".to_string(), "sha256:abc".to_string());
+        let context = context_dir();
+
+        let report = check_images(&api, &[source("python", None), source("ruby", Some(context.path()))], false).await;
+        assert_eq!(report.digests, BTreeMap::from([("python".to_string(), "sha256:abc".to_string())]));
+        assert_eq!(report.missing["ruby"], "not found locally");
+        assert!(api.built.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn builds_missing_images_from_their_context() {
+        let api = MockApi { broken_builds: vec!["java".to_string()], ..Default::default() };
+        let context = context_dir();
+        let sources = [
+            source("cpp", None),
+            source("cpp", Some(context.path())),
+            source("java", Some(context.path())),
+            source("ruby", None),
+        ];
+
+        let report = check_images(&api, &sources, true).await;
+        assert_eq!(report.built, vec!["cpp"]);
+        assert_eq!(report.digests["cpp"], "sha256:cpp");
+        assert!(report.missing["java"].contains("RUN apt-get install failed"));
+        assert!(report.missing["ruby"].contains("no Dockerfile"));
+        assert_eq!(api.built.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn build_context_is_a_readable_tar() {
+        let dir = context_dir();
+        std::fs::create_dir(dir.path().join("scripts")).unwrap();
+        std::fs::write(dir.path().join("scripts").join("setup.sh"), "echo setup\n").unwrap();
+
+        let archive = build_context(dir.path()).unwrap();
+        assert_eq!(archive.len() % TAR_BLOCK, 0);
+
+        let path = dir.path().join("context.tar");
+        std::fs::write(&path, &archive).unwrap();
+        let listing = std::process::Command::new("tar").arg("-tf").arg(&path).output().unwrap();
+        assert!(listing.status.success(), "{}", String::from_utf8_lossy(&listing.stderr));
+        assert_eq!(String::from_utf8_lossy(&listing.stdout), "Dockerfile\nscripts/\nscripts/setup.sh\n");
+    }
+
+    #[test]
+    fn rejects_paths_too_long_for_ustar() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("x".repeat(120)), "").unwrap();
+        assert!(build_context(dir.path()).is_err());
+    }
+}
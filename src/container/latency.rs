@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets in milliseconds; the last bucket is unbounded
+const BUCKET_BOUNDS_MS: [f64; 12] = [10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0, 60000.0];
+
+/// How the container for an execution was obtained
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StartKind {
+    /// Taken from the warm pool
+    Warm,
+    /// Created and started for this execution
+    Cold,
+}
+
+impl fmt::Display for StartKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartKind::Warm => write!(f, "warm"),
+            StartKind::Cold => write!(f, "cold"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    counts: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    total: u64,
+    sum_ms: f64,
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed_ms: f64) {
+        let bucket = BUCKET_BOUNDS_MS.iter().position(|bound| elapsed_ms <= *bound).unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.sum_ms += elapsed_ms;
+    }
+
+    /// Upper bound of the bucket holding the given quantile; `f64::INFINITY` for the overflow bucket
+    fn quantile(&self, quantile: f64) -> f64 {
+        let rank = ((self.total as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_BOUNDS_MS.get(bucket).copied().unwrap_or(f64::INFINITY);
+            }
+        }
+        f64::INFINITY
+    }
+}
+
+/// Summary of one language and start kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySummary {
+    pub language: String,
+    pub kind: StartKind,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: n={} mean={:.1}ms p50{} p95{} p99{}",
+               self.language, self.kind, self.count, self.mean_ms,
+               bound(self.p50_ms), bound(self.p95_ms), bound(self.p99_ms))
+    }
+}
+
+/// `<=100ms` for a bucket bound, `>60000ms` for the overflow bucket
+fn bound(ms: f64) -> String {
+    if ms.is_finite() {
+        format!("<={}ms", ms)
+    } else {
+        format!(">{}ms", BUCKET_BOUNDS_MS[BUCKET_BOUNDS_MS.len() - 1])
+    }
+}
+
+/// End-to-end execution latency per language, split by warm and cold starts
+#[derive(Debug, Default)]
+pub struct LatencyHistograms {
+    histograms: Mutex<BTreeMap<(String, StartKind), Histogram>>,
+}
+
+impl LatencyHistograms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, language: &str, kind: StartKind, elapsed: Duration) {
+        self.histograms.lock().unwrap()
+            .entry((language.to_string(), kind))
+            .or_default()
+            .record(elapsed.as_secs_f64() * 1000.0);
+    }
+
+    /// Total number of recorded executions
+    pub fn total(&self) -> u64 {
+        self.histograms.lock().unwrap().values().map(|histogram| histogram.total).sum()
+    }
+
+    pub fn summaries(&self) -> Vec<LatencySummary> {
+        self.histograms.lock().unwrap().iter()
+            .map(|((language, kind), histogram)| LatencySummary {
+                language: language.clone(),
+                kind: *kind,
+                count: histogram.total,
+                mean_ms: histogram.sum_ms / histogram.total as f64,
+                p50_ms: histogram.quantile(0.50),
+                p95_ms: histogram.quantile(0.95),
+                p99_ms: histogram.quantile(0.99),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_report_bucket_upper_bounds() {
+        let histograms = LatencyHistograms::new();
+        for _ in 0..90 {
+            histograms.record("python", StartKind::Warm, Duration::from_millis(40));
+        }
+        for _ in 0..10 {
+            histograms.record("python", StartKind::Warm, Duration::from_millis(700));
+        }
+
+        let summary = &histograms.summaries()[0];
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p95_ms, 1000.0);
+        assert!((summary.mean_ms - 106.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn warm_and_cold_starts_are_kept_apart() {
+        let histograms = LatencyHistograms::new();
+        histograms.record("python", StartKind::Cold, Duration::from_millis(900));
+        histograms.record("python", StartKind::Warm, Duration::from_millis(60));
+        histograms.record("ruby", StartKind::Cold, Duration::from_secs(120));
+
+        let summaries = histograms.summaries();
+        let kinds: Vec<_> = summaries.iter().map(|summary| (summary.language.as_str(), summary.kind)).collect();
+        assert_eq!(kinds, vec![("python", StartKind::Warm), ("python", StartKind::Cold), ("ruby", StartKind::Cold)]);
+        assert_eq!(summaries[0].p99_ms, 100.0);
+        assert_eq!(summaries[1].p99_ms, 1000.0);
+        assert_eq!(summaries[2].p50_ms, f64::INFINITY);
+        assert_eq!(histograms.total(), 3);
+    }
+}
@@ -1,90 +1,278 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use bollard::Docker;
-use bollard::container::{Config, CreateContainerOptions, StartContainerOptions, RemoveContainerOptions, StatsOptions, AttachContainerOptions};
-use bollard::exec::{CreateExecOptions, StartExecResults};
+use std::time::{Duration, Instant, SystemTime};
 use anyhow::{Result, anyhow};
-use uuid::Uuid;
+use bollard::Docker;
+use bollard::container::{Config, LogOutput};
 use futures_util::StreamExt;
-use std::time::{Duration, Instant};
-use tokio::time::timeout;
-use futures_util::stream::TryStreamExt;
+use serde::{Deserialize, Serialize};
 use tokio::io::AsyncWriteExt;
+use tokio::time::timeout;
+use uuid::Uuid;
 
-const EXECUTION_TIMEOUT: u64 = 10; // Default timeout in seconds
+use super::client::{DockerClient, ExecSession};
+use super::images::{check_images, ImageApi, ImageReport, ImageSource};
+use super::latency::{LatencyHistograms, StartKind};
+use super::pool::{PoolConfig, WarmPool};
+use super::reaper::{InstanceIdentity, Reaper};
+use crate::config::env_override;
+use crate::execution::{ExecutionResult, ExecutionStatus};
+use crate::language::LanguageManifest;
+use crate::security::SeccompProfile;
 
-#[derive(Debug)]
-pub struct ContainerManager {
-    docker: Docker,
+const CPU_PERIOD: i64 = 100000;
+/// Memory limit of idle pooled containers; raised to the request's limit when handed out
+const IDLE_MEMORY_LIMIT_MB: u32 = 64;
+/// Execution label of pooled containers that have not been handed out
+const IDLE_EXECUTION_LABEL: &str = "idle";
+/// Entrypoint of every execution: writes the code, compiles it and runs it as the manifest says
+const RUN_SCRIPT: &str = include_str!("run_user_code.sh");
+/// The run script keeps its time report out of the program's reach as root and drops to the
+/// image's uid 1000 user for compiling and running
+pub(crate) const RUN_SCRIPT_USER: &str = "root";
+/// Lines around the `/usr/bin/time -v` report the run script writes last to stderr
+const TIME_BEGIN_MARKER: &str = "===CODE_EXEC_TIME_BEGIN===";
+const TIME_END_MARKER: &str = "===CODE_EXEC_TIME_END===";
+/// Exit code of the run script when the compile command fails, in which case it writes no time report
+const COMPILE_FAILED_EXIT_CODE: i32 = 2;
+/// Images whose pooled containers failed to start are skipped for this long
+const POOL_FAILURE_BACKOFF: Duration = Duration::from_secs(30);
+/// What every container run is confined by: its cgroup, namespaces and seccomp profile
+const CONTAINER_PROTECTIONS: [&str; 3] = ["cgroup", "namespaces", "seccomp"];
+
+/// How to reach the Docker daemon and how execution containers are limited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DockerConfig {
+    /// `unix:///path/to/docker.sock` or `tcp://host:port`; empty uses the local defaults (`DOCKER_HOST`)
+    pub host: String,
+    /// Timeout for Docker API calls
+    pub api_timeout_seconds: u64,
+    /// CPU share of a single core each container may use
+    pub cpu_quota_percent: u32,
+    /// How often to look for orphaned containers
+    pub reaper_interval_seconds: u64,
+    /// Extra time past an execution's timeout before its container counts as abandoned
+    pub deadline_grace_seconds: u64,
+    /// Build language images that are missing at startup from their manifest directory
+    pub build_missing_images: bool,
+}
+
+impl Default for DockerConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            api_timeout_seconds: 120,
+            cpu_quota_percent: 50,
+            reaper_interval_seconds: 60,
+            deadline_grace_seconds: 60,
+            build_missing_images: false,
+        }
+    }
+}
+
+impl DockerConfig {
+    /// Apply `CODE_EXECUTOR_DOCKER_HOST`, `CODE_EXECUTOR_CPU_QUOTA_PERCENT` and
+    /// `CODE_EXECUTOR_BUILD_MISSING_IMAGES` on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_DOCKER_HOST", &mut self.host)?;
+        env_override("CODE_EXECUTOR_CPU_QUOTA_PERCENT", &mut self.cpu_quota_percent)?;
+        env_override("CODE_EXECUTOR_BUILD_MISSING_IMAGES", &mut self.build_missing_images)?;
+        Ok(())
+    }
+
+    /// Describe every invalid setting
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !self.host.is_empty() && !["unix://", "tcp://", "http://"].iter().any(|scheme| self.host.starts_with(scheme)) {
+            errors.push(format!("host '{}' must start with unix://, tcp:// or http://", self.host));
+        }
+        if self.cpu_quota_percent == 0 {
+            errors.push("cpu_quota_percent must be greater than 0".to_string());
+        }
+        if self.reaper_interval_seconds == 0 {
+            errors.push("reaper_interval_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
 }
 
-#[derive(Debug)]
-pub struct ExecutionResult {
-    pub stdout: String,
-    pub stderr: String,
-    pub status: ExecutionStatus,
-    pub execution_time: f64,
-    pub memory_used: u32,
+pub struct ContainerManager {
+    docker: Arc<dyn DockerClient>,
+    config: DockerConfig,
+    /// Containers created by this instance that have not been removed yet
+    active: Arc<std::sync::Mutex<HashSet<String>>>,
+    identity: InstanceIdentity,
+    /// Image id per available image, recorded at startup; containers are created from these ids
+    image_digests: std::sync::RwLock<HashMap<String, String>>,
+    pool: Option<WarmPool>,
+    /// How long a pooled container may exist before the reaper considers it abandoned
+    pool_container_lifetime: Duration,
+    /// Seccomp profile per image; images without one get the default profile
+    seccomp_profiles: HashMap<String, SeccompProfile>,
+    latency: LatencyHistograms,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-#[repr(i32)]
-pub enum ExecutionStatus {
-    Pending = 0,
-    Running = 1,
-    Completed = 2,
-    Failed = 3,
-    Timeout = 4,
-    MemoryLimitExceeded = 5,
-    RuntimeError = 6,
+impl fmt::Debug for ContainerManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContainerManager")
+            .field("config", &self.config)
+            .field("identity", &self.identity)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ContainerManager {
-    pub async fn new() -> Result<Self> {
-        let docker = Docker::connect_with_local_defaults()?;
-        Ok(Self { docker })
+    pub async fn new(config: DockerConfig) -> Result<Self> {
+        let docker = if let Some(path) = config.host.strip_prefix("unix://") {
+            Docker::connect_with_unix(path, config.api_timeout_seconds, bollard::API_DEFAULT_VERSION)?
+        } else if !config.host.is_empty() {
+            Docker::connect_with_http(&config.host, config.api_timeout_seconds, bollard::API_DEFAULT_VERSION)?
+        } else {
+            Docker::connect_with_local_defaults()?.with_timeout(Duration::from_secs(config.api_timeout_seconds))
+        };
+        Ok(Self::with_client(config, Arc::new(docker)))
+    }
+
+    /// Use `docker` for every Docker call instead of connecting to a daemon
+    pub fn with_client(config: DockerConfig, docker: Arc<dyn DockerClient>) -> Self {
+        Self {
+            docker,
+            config,
+            active: Default::default(),
+            identity: InstanceIdentity::current(),
+            image_digests: Default::default(),
+            pool: None,
+            pool_container_lifetime: Duration::ZERO,
+            seccomp_profiles: HashMap::new(),
+            latency: LatencyHistograms::new(),
+        }
+    }
+
+    /// Keep pre-started containers for each of `images`.
+    /// `max_timeout_seconds` bounds how long a pooled container can run once handed out.
+    pub fn with_pool(mut self, config: PoolConfig, max_timeout_seconds: u32,
+                     images: impl IntoIterator<Item = String>) -> Self {
+        if config.enabled {
+            self.pool_container_lifetime = config.max_idle()
+                + Duration::from_secs(max_timeout_seconds as u64 + self.config.deadline_grace_seconds);
+            self.pool = Some(WarmPool::new(config, images));
+        }
+        self
+    }
+
+    /// Apply each language's seccomp profile to the containers of its image
+    pub fn with_seccomp_profiles<'a>(mut self, manifests: impl IntoIterator<Item = &'a LanguageManifest>) -> Self {
+        self.seccomp_profiles.extend(manifests.into_iter()
+            .map(|manifest| (manifest.image.clone(), SeccompProfile::for_language(manifest))));
+        self
+    }
+
+    /// End-to-end execution latency, split by warm and cold starts
+    pub fn latency(&self) -> &LatencyHistograms {
+        &self.latency
+    }
+
+    pub fn identity(&self) -> &InstanceIdentity {
+        &self.identity
     }
 
-    pub async fn execute_code(&self, code: &str, language: &str, version: &str,
+    /// Start removing orphaned containers now and every `reaper_interval_seconds`
+    pub fn spawn_reaper(&self) -> tokio::task::JoinHandle<()> {
+        Reaper::new(self.docker.clone(), self.identity.clone())
+            .spawn(Duration::from_secs(self.config.reaper_interval_seconds))
+    }
+
+    /// Inspect every image, building missing ones when `build_missing_images` is set, and
+    /// record the digests of those that are available. Only recorded images can be executed.
+    pub async fn check_images(&self, sources: &[ImageSource]) -> ImageReport {
+        let api: &dyn ImageApi = self.docker.as_ref();
+        let report = check_images(api, sources, self.config.build_missing_images).await;
+        self.image_digests.write().unwrap().extend(report.digests.clone());
+        report
+    }
+
+    /// Content-addressed id recorded for `image` at startup. Executions are pinned to it,
+    /// so rebuilding an image takes effect after a restart.
+    pub fn image_digest(&self, image: &str) -> Result<String> {
+        self.image_digests.read().unwrap()
+            .get(image)
+            .cloned()
+            .ok_or_else(|| anyhow!("Image {} is not available on this server", image))
+    }
+
+    /// Run `code` in the manifest's image. `timeout_seconds` is the wall clock limit as given;
+    /// callers apply the manifest's time multiplier.
+    pub async fn execute_code(&self, manifest: &LanguageManifest, code: &str,
                               timeout_seconds: u32, memory_limit_mb: u32, input: &[String]) -> Result<ExecutionResult> {
-        // Generate unique container name and execution_id
+        let started = Instant::now();
+        let language = manifest.language.as_str();
+        let image = manifest.image.as_str();
+        let digest = self.image_digest(image)?;
+
+        // Generate unique execution_id
         let execution_id = Uuid::new_v4().to_string();
-        let container_name = format!("code-exec-{}-{}", language, &execution_id);
-
-        // Create container configuration
-        let mut env = vec![
-            format!("MEMORY_LIMIT={}", memory_limit_mb),
-            format!("TIMEOUT={}", timeout_seconds),
-            format!("USER_CODE={}", code),
-            format!("EXECUTION_ID={}", &execution_id),
-        ];
-
-        // Determine image name based on language and version
-        let image = match (language, version) {
-            ("cpp", "23") => "code-executor-cpp-23",
-            ("python", "3.12") => "code-executor-python-3.12",
-            ("ruby", "3.2") => "code-executor-ruby-3.2",
-            ("java", "15") => "code-executor-java-15",
-            // ... add more as needed ...
-            _ => return Err(anyhow!("Unsupported language or version: {} {}", language, version)),
-        };
+        let env = execution_env(manifest, code, &execution_id, timeout_seconds, memory_limit_mb);
+
+        // 풀에서 꺼낸 컨테이너는 한 번만 사용하고 실행 후 삭제
+        if let Some(container_id) = self.pool.as_ref().and_then(|pool| pool.acquire(image, started)) {
+            // 실행이 취소되어 future가 버려져도 가드가 컨테이너를 삭제
+            let container = self.guard(container_id.clone());
+            match self.start_pooled(&container_id, env.clone(), memory_limit_mb, !input.is_empty()).await {
+                Ok(exec) => {
+                    let result = self.finish_pooled(&container_id, exec, timeout_seconds, input).await;
+                    drop(container);
+                    let mut result = result?;
+                    result.image_digest = digest;
+                    result.protections = CONTAINER_PROTECTIONS.map(str::to_string).to_vec();
+                    self.latency.record(language, StartKind::Warm, started.elapsed());
+                    return Ok(result);
+                }
+                Err(e) => {
+                    tracing::warn!("Pooled container {} could not be used, starting a new one: {}", container_id, e);
+                    drop(container);
+                }
+            }
+        }
 
-        let host_config = bollard::models::HostConfig {
+        let mut result = self.run_in_new_container(&execution_id, manifest, &digest, env, timeout_seconds, memory_limit_mb, input).await?;
+        result.image_digest = digest;
+        result.protections = CONTAINER_PROTECTIONS.map(str::to_string).to_vec();
+        self.latency.record(language, StartKind::Cold, started.elapsed());
+        Ok(result)
+    }
+
+    /// Resource limits and the seccomp profile shared by fresh and pooled containers of `image`
+    fn host_config(&self, image: &str, memory_limit_mb: u32) -> bollard::models::HostConfig {
+        let seccomp = self.seccomp_profiles.get(image).cloned().unwrap_or_default();
+        bollard::models::HostConfig {
             memory: Some((memory_limit_mb as i64) * 1024 * 1024),
             memory_swap: Some((memory_limit_mb as i64) * 1024 * 1024), // Disable swap
-            cpu_period: Some(100000),
-            cpu_quota: Some(50000), // 50% CPU limit
-            security_opt: Some(vec!["no-new-privileges".to_string()]),
+            cpu_period: Some(CPU_PERIOD),
+            cpu_quota: Some(CPU_PERIOD * self.config.cpu_quota_percent as i64 / 100),
+            security_opt: Some(vec!["no-new-privileges".to_string(), seccomp.docker_security_opt()]),
             ..Default::default()
-        };
+        }
+    }
+
+    /// Create, run and remove a container dedicated to one execution
+    #[allow(clippy::too_many_arguments)]
+    async fn run_in_new_container(&self, execution_id: &str, manifest: &LanguageManifest, digest: &str, env: Vec<String>,
+                                  timeout_seconds: u32, memory_limit_mb: u32, input: &[String]) -> Result<ExecutionResult> {
+        let container_name = format!("code-exec-{}-{}", manifest.language, execution_id);
 
-        // 언어별로 실행 옵션 결정 및 래퍼 제거, cmd는 빈 벡터
-        let cmd = vec![];
+        // 서버가 비정상 종료되어도 reaper가 정리할 수 있도록 소유자와 기한을 라벨로 남김
+        let deadline = SystemTime::now()
+            + Duration::from_secs(timeout_seconds as u64 + self.config.deadline_grace_seconds);
 
         let config = Config {
-            image: Some(image.to_string()),
-            cmd: Some(cmd),
-            host_config: Some(host_config),
+            image: Some(digest.to_string()),
+            labels: Some(self.identity.labels(execution_id, deadline)),
+            entrypoint: Some(run_script_command()),
+            cmd: Some(vec![]),
+            user: Some(RUN_SCRIPT_USER.to_string()),
+            host_config: Some(self.host_config(&manifest.image, memory_limit_mb)),
             working_dir: Some("/workspace".to_string()),
             env: Some(env),
             network_disabled: Some(true),
@@ -92,196 +280,843 @@ impl ContainerManager {
             ..Default::default()
         };
 
-        // Create and start container
-        let container = self.docker.create_container(
-            Some(CreateContainerOptions {
-                name: container_name.as_str(),
-                platform: None,
-            }),
-            config,
-        ).await?;
+        let id = self.docker.create_container(&container_name, config).await?;
+        self.active.lock().unwrap().insert(id.clone());
 
-        self.docker.start_container(&container.id, None::<StartContainerOptions<String>>).await?;
+        // Cleanup: 실행이 실패하거나 취소되어도 컨테이너는 항상 삭제
+        let container = self.guard(id.clone());
+        let result = self.run_container(&id, timeout_seconds, input).await;
+        container.remove().await;
+        result
+    }
+
+    /// Start a created container, feed stdin and wait for it within the timeout
+    async fn run_container(&self, id: &str, timeout_seconds: u32, input: &[String]) -> Result<ExecutionResult> {
+        self.docker.start_container(id).await?;
 
         // input 전달: attach 후 stdin에 write
         if !input.is_empty() {
-            let mut attach = self.docker.attach_container::<String>(
-                &container.id,
-                Some(AttachContainerOptions {
-                    stream: Some(true),
-                    stdin: Some(true),
-                    stdout: Some(false),
-                    stderr: Some(false),
-                    logs: Some(false),
-                    detach_keys: None,
-                }),
-            ).await?;
-
-            let mut stdin = attach.input;
-            for line in input {
-                stdin.write_all(format!("{}\n", line).as_bytes()).await?;
+            self.docker.write_stdin(id, &stdin_bytes(input)).await?;
+        }
+
+        let wait_result = timeout(Duration::from_secs(timeout_seconds as u64), self.docker.wait_container(id)).await;
+
+        let mut result = ExecutionResult::default();
+        for log in self.docker.logs(id).await {
+            push_output(&mut result, log);
+        }
+
+        let timed_out = match wait_result {
+            Ok(Ok(exit_code)) => {
+                exited(&mut result, exit_code);
+                false
+            }
+            Ok(Err(e)) => {
+                result.status = ExecutionStatus::RuntimeError;
+                result.stderr.push_str(&format!("Container error: {}", e));
+                false
             }
+            Err(_) => true,
+        };
+
+        // 타임아웃 발생 시 컨테이너 강제 종료
+        if timed_out {
+            let _ = self.docker.kill_container(id).await;
+        }
+
+        // 컨테이너 상태 조회로 OOMKilled(메모리 초과) 확인
+        let oom_killed = self.docker.oom_killed(id).await?;
+        let compile_failed = compile_failed(&result);
+        let signal = apply_time_report(&mut result);
+        let seccomp_killed = killed_by_seccomp(&result, signal);
+        classify(&mut result, timed_out, oom_killed, compile_failed, seccomp_killed);
+        Ok(result)
+    }
+
+    /// Point a pooled container at one execution: apply the requested memory limit and
+    /// start the run script with the execution's environment
+    async fn start_pooled(&self, container_id: &str, env: Vec<String>,
+                          memory_limit_mb: u32, attach_stdin: bool) -> Result<ExecSession> {
+        self.docker.update_memory(container_id, (memory_limit_mb as i64) * 1024 * 1024).await?;
+        self.docker.start_exec(container_id, run_script_command(), env, attach_stdin).await
+    }
+
+    /// Feed stdin, collect output until the exec ends or times out, and classify the result
+    async fn finish_pooled(&self, container_id: &str, exec: ExecSession, timeout_seconds: u32,
+                           input: &[String]) -> Result<ExecutionResult> {
+        let ExecSession { id, mut output, input: mut stdin } = exec;
+        if !input.is_empty() {
+            stdin.write_all(&stdin_bytes(input)).await?;
             stdin.shutdown().await?;
         }
+        drop(stdin);
 
-        // 실행 시간 및 메모리 사용량 측정 준비
-        let start = Instant::now();
-        let max_mem = Arc::new(tokio::sync::Mutex::new(0u64));
-        let max_mem_clone = max_mem.clone();
-        let docker_stats = self.docker.clone();
-        let container_id_stats = container.id.clone();
-
-        // stats 폴링 task 시작
-        let stats_handle = tokio::spawn(async move {
-            let mut stats_stream = docker_stats.stats(&container_id_stats, Some(StatsOptions { stream: true, ..Default::default() }));
-            while let Some(Ok(stats)) = stats_stream.next().await {
-                if let Some(usage) = stats.memory_stats.usage {
-                    let mut max_mem = max_mem_clone.lock().await;
-                    if usage > *max_mem {
-                        *max_mem = usage;
-                    }
-                }
+        let mut result = ExecutionResult::default();
+        let collect = async {
+            while let Some(log) = output.next().await {
+                push_output(&mut result, log);
             }
-        });
+        };
+        let timed_out = timeout(Duration::from_secs(timeout_seconds as u64), collect).await.is_err();
 
-        // Wait for container with timeout
-        let timeout_duration = Duration::from_secs(timeout_seconds as u64);
-        let mut wait_stream = self.docker.wait_container::<String>(&container.id, None);
-        let wait_result = timeout(timeout_duration, wait_stream.next()).await;
+        if timed_out {
+            let _ = self.docker.kill_container(container_id).await;
+        } else {
+            match self.docker.exec_exit_code(&id).await? {
+                Some(exit_code) => exited(&mut result, exit_code),
+                None => result.status = ExecutionStatus::Failed,
+            }
+        }
 
-        let mut timed_out = false;
+        let oom_killed = self.docker.oom_killed(container_id).await?;
+        let compile_failed = compile_failed(&result);
+        let signal = apply_time_report(&mut result);
+        let seccomp_killed = killed_by_seccomp(&result, signal);
+        classify(&mut result, timed_out, oom_killed, compile_failed, seccomp_killed);
+        Ok(result)
+    }
 
-        // Get container logs
-        let logs = self.docker.logs::<String>(&container.id, Some(bollard::container::LogsOptions::<String> {
-            stdout: true,
-            stderr: true,
+    /// Create and start an idle container for the pool
+    async fn create_idle_container(&self, image: &str) -> Result<String> {
+        let container_name = format!("code-exec-warm-{}", Uuid::new_v4());
+        let deadline = SystemTime::now() + self.pool_container_lifetime;
+        let config = Config {
+            image: Some(self.image_digest(image)?),
+            entrypoint: Some(vec!["sleep".to_string(), "infinity".to_string()]),
+            cmd: Some(vec![]),
+            labels: Some(self.identity.labels(IDLE_EXECUTION_LABEL, deadline)),
+            host_config: Some(self.host_config(image, IDLE_MEMORY_LIMIT_MB)),
+            working_dir: Some("/workspace".to_string()),
+            network_disabled: Some(true),
             ..Default::default()
-        })).collect::<Vec<_>>().await;
-
-        // Process results
-        let mut result = ExecutionResult {
-            stdout: String::new(),
-            stderr: String::new(),
-            status: ExecutionStatus::Pending,
-            execution_time: 0.0,
-            memory_used: 0,
         };
 
-        // Process logs
-        for log in logs {
-            match log {
-                Ok(log) => {
-                    match log {
-                        bollard::container::LogOutput::StdOut { message } => {
-                            result.stdout.push_str(&String::from_utf8_lossy(&message));
-                        }
-                        bollard::container::LogOutput::StdErr { message } => {
-                            result.stderr.push_str(&String::from_utf8_lossy(&message));
-                        }
-                        _ => {}
+        let id = self.docker.create_container(&container_name, config).await?;
+        self.active.lock().unwrap().insert(id.clone());
+
+        if let Err(e) = self.docker.start_container(&id).await {
+            self.remove_container(&id).await;
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Retire expired idle containers and create what the pool is missing
+    async fn refill_pool(&self, pool: &WarmPool, failing: &mut HashMap<String, Instant>) {
+        let plan = pool.plan(Instant::now());
+        for id in plan.retire {
+            self.remove_container(&id).await;
+        }
+
+        failing.retain(|_, failed_at| failed_at.elapsed() < POOL_FAILURE_BACKOFF);
+        let creations = plan.create.iter()
+            .filter(|(image, _)| !failing.contains_key(image))
+            .flat_map(|(image, count)| std::iter::repeat_n(image, *count))
+            .map(|image| async move { (image, self.create_idle_container(image).await) });
+
+        for (image, created) in futures_util::future::join_all(creations).await {
+            match created {
+                Ok(id) => {
+                    if !pool.add(image, id.clone(), Instant::now()) {
+                        self.remove_container(&id).await;
                     }
                 }
                 Err(e) => {
-                    result.stderr.push_str(&format!("Error reading logs: {}", e));
+                    if failing.insert(image.clone(), Instant::now()).is_none() {
+                        tracing::warn!("Failed to start a pooled container for {}: {}", image, e);
+                    }
                 }
             }
         }
+    }
 
-        // Check execution status
-        match wait_result {
-            Ok(Some(Ok(exit))) => {
-                if exit.status_code != 0i64 {
-                    result.status = ExecutionStatus::Failed;
-                } else {
-                    result.status = ExecutionStatus::Completed;
-                }
-            }
-            Ok(Some(Err(e))) => {
-                result.status = ExecutionStatus::RuntimeError;
-                result.stderr.push_str(&format!("Container error: {}", e));
-            }
-            Ok(None) => {
-                result.status = ExecutionStatus::Failed;
-                result.stderr.push_str("Container did not return an exit status.\n");
+    /// Keep the warm pool topped up in the background; does nothing when the pool is disabled
+    pub fn spawn_pool_refill(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.pool.as_ref()?.config().refill_interval();
+        let manager = self.clone();
+        Some(tokio::spawn(async move {
+            let Some(pool) = &manager.pool else {
+                return;
+            };
+            let mut failing = HashMap::new();
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                manager.refill_pool(pool, &mut failing).await;
             }
-            Err(_) => {
-                result.status = ExecutionStatus::Timeout;
-                timed_out = true;
+        }))
+    }
+
+    /// Log latency summaries every `interval` while new executions are being recorded
+    pub fn spawn_latency_reporter(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut reported = 0;
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let total = manager.latency.total();
+                if total == reported {
+                    continue;
+                }
+                reported = total;
+                for summary in manager.latency.summaries() {
+                    tracing::info!("Execution latency {}", summary);
+                }
+                if let Some(pool) = &manager.pool {
+                    tracing::info!("Warm pool idle containers: {:?}", pool.idle_counts());
+                }
             }
-        }
+        })
+    }
 
-        // 타임아웃 발생 시 컨테이너 강제 종료
-        if timed_out {
-            let _ = self.docker.kill_container(&container.id, None::<bollard::container::KillContainerOptions<String>>).await;
+    /// Kill and remove every container still running for this instance.
+    /// Called on shutdown once the drain deadline has passed.
+    pub async fn remove_active_containers(&self) -> usize {
+        if let Some(pool) = &self.pool {
+            pool.close();
         }
+        let containers: Vec<String> = self.active.lock().unwrap().iter().cloned().collect();
+        for id in &containers {
+            tracing::warn!("Removing leftover container {}", id);
+            self.remove_container(id).await;
+        }
+        containers.len()
+    }
 
-        // 컨테이너 상태 조회로 OOMKilled(메모리 초과) 확인
-        let inspect = self.docker.inspect_container(&container.id, None).await?;
-        let mut is_oom_killed = false;
-        if let Some(state) = inspect.state {
-            if state.oom_killed.unwrap_or(false) {
-                is_oom_killed = true;
-                result.stderr.push_str("Memory limit exceeded (OOMKilled)\n");
-            }
+    /// Force-remove a container, ignoring errors (it may already be gone)
+    async fn remove_container(&self, id: &str) {
+        remove_container(self.docker.as_ref(), &self.active, id).await;
+    }
+
+    /// Tie the removal of a container of this instance to the returned guard
+    fn guard(&self, id: String) -> ContainerGuard {
+        ContainerGuard { docker: self.docker.clone(), active: self.active.clone(), id: Some(id) }
+    }
+}
+
+/// Removes a container when dropped, so an execution whose future is dropped midway
+/// (e.g. because the client went away) doesn't leave its container running
+struct ContainerGuard {
+    docker: Arc<dyn DockerClient>,
+    active: Arc<std::sync::Mutex<HashSet<String>>>,
+    id: Option<String>,
+}
+
+impl ContainerGuard {
+    /// Remove the container now and wait for Docker
+    async fn remove(mut self) {
+        if let Some(id) = self.id.take() {
+            remove_container(self.docker.as_ref(), &self.active, &id).await;
         }
+    }
+}
 
-        // 최종 status 결정 (우선순위: Timeout > MemoryLimitExceeded > Failed > Completed)
-        if timed_out {
-            result.status = ExecutionStatus::Timeout;
-        } else if is_oom_killed {
-            result.status = ExecutionStatus::MemoryLimitExceeded;
+impl Drop for ContainerGuard {
+    /// Remove the container without making the caller wait for Docker
+    fn drop(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        let docker = self.docker.clone();
+        let active = self.active.clone();
+        tokio::spawn(async move { remove_container(docker.as_ref(), &active, &id).await });
+    }
+}
+
+/// `bash -c <run script>`; the trailing argument becomes `$0` for error messages
+fn run_script_command() -> Vec<String> {
+    vec!["bash".to_string(), "-c".to_string(), RUN_SCRIPT.to_string(), "run_user_code".to_string()]
+}
+
+/// Environment read by the run script
+fn execution_env(manifest: &LanguageManifest, code: &str, execution_id: &str,
+                 timeout_seconds: u32, memory_limit_mb: u32) -> Vec<String> {
+    let workdir = std::path::PathBuf::from(format!("/tmp/{}", execution_id));
+    let commands = manifest.commands(&workdir);
+    let mut env = vec![
+        format!("MEMORY_LIMIT={}", memory_limit_mb),
+        format!("TIMEOUT={}", timeout_seconds),
+        format!("USER_CODE={}", code),
+        format!("EXECUTION_ID={}", execution_id),
+        format!("WORK_DIR={}", workdir.display()),
+        format!("SOURCE_FILE={}", manifest.source_file),
+        format!("RUN_COMMAND={}", shell_join(&commands.run)),
+    ];
+    if let Some(compile) = &commands.compile {
+        env.push(format!("COMPILE_COMMAND={}", shell_join(compile)));
+    }
+    env
+}
+
+/// Quote every argument for `eval` in the run script
+fn shell_join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', "'\\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+async fn remove_container(docker: &dyn DockerClient, active: &std::sync::Mutex<HashSet<String>>, id: &str) {
+    let _ = docker.force_remove(id).await;
+    active.lock().unwrap().remove(id);
+}
+
+/// Input lines as written to stdin, each terminated by a newline
+fn stdin_bytes(input: &[String]) -> Vec<u8> {
+    input.iter().flat_map(|line| line.bytes().chain(std::iter::once(b'\n'))).collect()
+}
+
+/// Record the exit code of the run script; a non-zero exit, including a fatal signal the
+/// script reports as 128 + the signal number, is a failed run
+fn exited(result: &mut ExecutionResult, exit_code: i64) {
+    let exit_code = i32::try_from(exit_code).unwrap_or(i32::MAX);
+    result.exit_code = Some(exit_code);
+    result.status = ExecutionStatus::from_exit_code(exit_code);
+}
+
+/// 최종 status 결정 (우선순위: Timeout > MemoryLimitExceeded > CompileError > SecurityViolation > 종료 코드)
+fn classify(result: &mut ExecutionResult, timed_out: bool, oom_killed: bool, compile_failed: bool, seccomp_killed: bool) {
+    if oom_killed {
+        result.stderr.push_str("Memory limit exceeded (OOMKilled)\n");
+    } else if seccomp_killed {
+        result.stderr.push_str("Process killed for a forbidden system call\n");
+    }
+    if timed_out {
+        result.status = ExecutionStatus::Timeout;
+    } else if oom_killed {
+        result.status = ExecutionStatus::MemoryLimitExceeded;
+    } else if compile_failed {
+        result.status = ExecutionStatus::CompileError;
+    } else if seccomp_killed {
+        result.status = ExecutionStatus::SecurityViolation;
+    }
+}
+
+/// Append a chunk of container output to the result
+fn push_output(result: &mut ExecutionResult, log: Result<LogOutput, bollard::errors::Error>) {
+    match log {
+        Ok(LogOutput::StdOut { message }) => {
+            result.stdout.push_str(&String::from_utf8_lossy(&message));
+        }
+        Ok(LogOutput::StdErr { message }) => {
+            result.stderr.push_str(&String::from_utf8_lossy(&message));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            result.stderr.push_str(&format!("Error reading logs: {}", e));
         }
+    }
+}
 
-        let mut time_output = String::new();
-        let mut in_time_block = false;
-        let mut filtered_stderr = String::new();
-        for line in result.stderr.lines() {
-            if line.trim() == "===CODE_EXEC_TIME_BEGIN===" {
-                in_time_block = true;
-                continue;
-            }
-            if line.trim() == "===CODE_EXEC_TIME_END===" {
-                in_time_block = false;
-                continue;
-            }
-            if in_time_block {
-                time_output.push_str(line);
-                time_output.push('\n');
-            } else {
-                filtered_stderr.push_str(line);
-                filtered_stderr.push('\n');
-            }
+/// The run script ended with its compile failure exit code before running anything: a run always
+/// ends with the script's time report, which the program cannot remove
+fn compile_failed(result: &ExecutionResult) -> bool {
+    result.exit_code == Some(COMPILE_FAILED_EXIT_CODE) && split_time_report(&result.stderr).is_none()
+}
+
+/// Take the `/usr/bin/time -v` report that the run script appends to stderr and
+/// fill in execution time, peak memory, CPU time and context switches from it.
+/// Returns the signal that terminated the program, if any.
+///
+/// Only a report that ends stderr counts: the script writes its report after the program and
+/// everything it started are gone, so a block the program printed itself stays in its output.
+fn apply_time_report(result: &mut ExecutionResult) -> Option<i32> {
+    let (stderr, time_output) = split_time_report(&result.stderr)
+        .map(|(stderr, report)| (stderr.to_string(), report.to_string()))?;
+    // time_output에서 시간/메모리 정보 추출
+    // time -v는 각 줄을 탭으로 들여쓰고 "Elapsed (wall clock) time (h:mm:ss or m:ss): 0:01.23" 형식으로 출력
+    let mut signal = None;
+    for line in time_output.lines().map(str::trim) {
+        // 시그널로 종료되면 보고서 첫 줄이 "Command terminated by signal 31" 형식
+        if let Some(number) = line.strip_prefix("Command terminated by signal ") {
+            signal = number.trim().parse().ok();
         }
-        // time_output에서 시간/메모리 정보 추출
-        for line in time_output.lines() {
-            if let Some(time_str) = line.strip_prefix("Elapsed (wall clock) time:") {
-                let time_str = time_str.trim();
-                let ms = if let Some((min, sec)) = time_str.split_once(":") {
-                    let min: f64 = min.parse().unwrap_or(0.0);
-                    let sec: f64 = sec.parse().unwrap_or(0.0);
-                    (min * 60.0 + sec) * 1000.0
-                } else {
-                    time_str.parse::<f64>().unwrap_or(0.0) * 1000.0
-                };
-                result.execution_time = ms;
-            }
-            if let Some(mem_str) = line.strip_prefix("Maximum resident set size (kbytes):") {
-                let kb = mem_str.trim().parse::<u32>().unwrap_or(0);
-                result.memory_used = kb;
-            }
+        if line.starts_with("Elapsed (wall clock) time") {
+            let time_str = line.rsplit_once(' ').map_or(line, |(_, value)| value);
+            let seconds = time_str.split(':')
+                .fold(0.0, |total, part| total * 60.0 + part.parse::<f64>().unwrap_or(0.0));
+            result.execution_time_ms = seconds * 1000.0;
+        }
+        if let Some(mem_str) = line.strip_prefix("Maximum resident set size (kbytes):") {
+            let kb = mem_str.trim().parse::<u64>().unwrap_or(0);
+            result.memory_used_kb = kb;
+        }
+        if let Some(seconds) = line.strip_prefix("User time (seconds):") {
+            result.cpu_user_ms = seconds.trim().parse::<f64>().unwrap_or(0.0) * 1000.0;
+        }
+        if let Some(seconds) = line.strip_prefix("System time (seconds):") {
+            result.cpu_system_ms = seconds.trim().parse::<f64>().unwrap_or(0.0) * 1000.0;
+        }
+        if let Some(count) = line.strip_prefix("Voluntary context switches:") {
+            result.voluntary_context_switches = count.trim().parse().unwrap_or(0);
         }
-        // ===CODE_EXEC_TIME_BEGIN=== ~ ===CODE_EXEC_TIME_END=== 블록을 제거한 stderr로 대체
-        result.stderr = filtered_stderr;
-
-        // Cleanup: remove container (항상 실행, 에러 무시)
-        let _ = self.docker.remove_container(
-            &container.id,
-            Some(RemoveContainerOptions {
-                force: true,
-                ..Default::default()
-            }),
-        ).await;
+        if let Some(count) = line.strip_prefix("Involuntary context switches:") {
+            result.involuntary_context_switches = count.trim().parse().unwrap_or(0);
+        }
+    }
+    // 보고서 블록을 제거한 stderr로 대체
+    result.stderr = stderr;
+    signal
+}
 
-        Ok(result)
+/// Split stderr into the program's output and the report between the time markers at its end
+fn split_time_report(stderr: &str) -> Option<(&str, &str)> {
+    let body = stderr.trim_end_matches('\n').strip_suffix(TIME_END_MARKER)?;
+    let (begin, _) = body.rmatch_indices(TIME_BEGIN_MARKER)
+        .find(|(begin, _)| body[..*begin].is_empty() || body[..*begin].ends_with('\n'))?;
+    Some((&body[..begin], &body[begin + TIME_BEGIN_MARKER.len()..]))
+}
+
+/// The report says the program was killed by SIGSYS and the script exited the way `time` does
+/// for such a program (128 + the signal number)
+fn killed_by_seccomp(result: &ExecutionResult, signal: Option<i32>) -> bool {
+    signal == Some(libc::SIGSYS) && result.exit_code == Some(128 + libc::SIGSYS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::fake::{FakeDocker, ScriptedRun};
+    use crate::container::reaper::{EXECUTION_LABEL, INSTANCE_LABEL};
+
+    const IMAGE: &str = "code-executor-cpp-23";
+    const DIGEST: &str = "sha256:cpp23";
+
+    fn manifest() -> LanguageManifest {
+        toml::from_str(r#"
+            language = "cpp"
+            version = "23"
+            image = "code-executor-cpp-23"
+            source_file = "main.cpp"
+            compile = ["g++", "-o", "{workdir}/main", "{source}"]
+            run = ["{workdir}/main"]
+        "#).unwrap()
+    }
+
+    #[test]
+    fn shell_join_survives_eval() {
+        let args: Vec<String> = ["printf", "%s|", "it's", "$HOME", "two words", "\"q\""]
+            .iter().map(|arg| arg.to_string()).collect();
+        let output = std::process::Command::new("bash")
+            .args(["-c", "eval \"$COMMAND\""])
+            .env("COMMAND", shell_join(&args))
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's|$HOME|two words|\"q\"|");
+    }
+
+    #[test]
+    fn programs_cannot_replace_the_time_report() {
+        use std::os::unix::fs::PermissionsExt;
+        if !nix::unistd::Uid::effective().is_root() {
+            eprintln!("Not root, skipping");
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+        // GNU time처럼 보고서를 먼저 열고 그 fd를 명령에 물려주는 대역
+        let time = dir.path().join("time");
+        std::fs::write(&time, concat!(
+            "#!/bin/bash\nout=$3; shift 3\nexec 3>\"$out\"\n\"$@\"; status=$?\n",
+            "echo \"Command exited with non-zero status $status\" >&3\n",
+            "printf '\\tMaximum resident set size (kbytes): 1234\\n' >&3\nexit $status\n",
+        )).unwrap();
+        std::fs::set_permissions(&time, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // 작업 디렉터리의 옛 보고서 자리, 물려받은 fd, 자기 프로세스의 fd에 모두 가짜 보고서를 씀
+        let forge = "rm -f .time; printf 'Command terminated by signal 31\\n' > .time; \
+                     report='\\tCommand terminated by signal 31\\n\\tMaximum resident set size (kbytes): 1\\n'; \
+                     for fd in 3 4 5 6 7 8 9; do printf \"$report\" >&$fd; done 2>/dev/null; \
+                     for file in /proc/[0-9]*/fd/*; do printf \"$report\" >> \"$file\"; done 2>/dev/null; \
+                     exit 159";
+        let run: Vec<String> = ["bash", "-c", forge].iter().map(|arg| arg.to_string()).collect();
+
+        let output = std::process::Command::new("bash")
+            .args(["-c", RUN_SCRIPT, "run_user_code"])
+            .env("TIME_COMMAND", &time)
+            .env("WORK_DIR", dir.path().join("work"))
+            .env("SOURCE_FILE", "main.sh")
+            .env("USER_CODE", "")
+            .env("RUN_COMMAND", shell_join(&run))
+            .output()
+            .unwrap();
+
+        let mut result = ExecutionResult {
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code(),
+            ..Default::default()
+        };
+        let signal = apply_time_report(&mut result);
+        assert_eq!(result.exit_code, Some(159));
+        assert_eq!(signal, None);
+        assert_eq!(result.memory_used_kb, 1234);
+        assert!(!killed_by_seccomp(&result, signal));
+    }
+
+    #[test]
+    fn env_points_the_run_script_at_the_manifest_commands() {
+        let env = execution_env(&manifest(), "int main() {}", "exec-1", 5, 128);
+
+        assert!(env.contains(&"WORK_DIR=/tmp/exec-1".to_string()));
+        assert!(env.contains(&"SOURCE_FILE=main.cpp".to_string()));
+        assert!(env.contains(&"COMPILE_COMMAND='g++' '-o' '/tmp/exec-1/main' '/tmp/exec-1/main.cpp'".to_string()));
+        assert!(env.contains(&"RUN_COMMAND='/tmp/exec-1/main'".to_string()));
+        assert!(env.contains(&"USER_CODE=int main() {}".to_string()));
+    }
+
+    #[test]
+    fn interpreted_languages_have_no_compile_step() {
+        let mut manifest = manifest();
+        manifest.compile = None;
+        let env = execution_env(&manifest, "", "exec-1", 5, 128);
+
+        assert!(!env.iter().any(|var| var.starts_with("COMPILE_COMMAND=")));
     }
-}
\ No newline at end of file
+
+    async fn manager(fake: &FakeDocker) -> ContainerManager {
+        fake.add_image(IMAGE, DIGEST);
+        let manager = ContainerManager::with_client(DockerConfig::default(), Arc::new(fake.clone()));
+        manager.check_images(&[ImageSource { image: IMAGE.to_string(), context: None }]).await;
+        manager
+    }
+
+    async fn run(manager: &ContainerManager, input: &[&str]) -> Result<ExecutionResult> {
+        let input: Vec<String> = input.iter().map(|line| line.to_string()).collect();
+        manager.execute_code(&manifest(), "int main() {}", 2, 128, &input).await
+    }
+
+    fn time_report(elapsed: &str, max_rss_kb: u32) -> String {
+        format!("===CODE_EXEC_TIME_BEGIN===\n\tCommand being timed: \"./main\"\n\
+                 \tUser time (seconds): 1.25\n\tSystem time (seconds): 0.04\n\
+                 \tElapsed (wall clock) time (h:mm:ss or m:ss): {}\n\
+                 \tMaximum resident set size (kbytes): {}\n\
+                 \tVoluntary context switches: 12\n\tInvoluntary context switches: 7\n\
+                 ===CODE_EXEC_TIME_END===\n", elapsed, max_rss_kb)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn completed_runs_report_output_time_and_memory() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::exits(0).stdout("hello\n").stderr(&format!("warning\n{}", time_report("0:01.50", 3456))));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.stdout, "hello\n");
+        assert_eq!(result.stderr, "warning\n");
+        assert_eq!(result.execution_time_ms, 1500.0);
+        assert_eq!(result.memory_used_kb, 3456);
+        assert_eq!(result.cpu_user_ms, 1250.0);
+        assert_eq!(result.cpu_system_ms, 40.0);
+        assert_eq!((result.voluntary_context_switches, result.involuntary_context_switches), (12, 7));
+        assert_eq!(result.image_digest, DIGEST);
+        assert_eq!(result.protections, ["cgroup", "namespaces", "seccomp"]);
+    }
+
+    #[test]
+    fn time_report_accepts_hours() {
+        let mut result = ExecutionResult { stderr: time_report("1:02:03", 10), ..Default::default() };
+        apply_time_report(&mut result);
+
+        assert_eq!(result.execution_time_ms, 3_723_000.0);
+        assert_eq!(result.stderr, "");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_zero_exits_fail() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::exits(2).stderr(&format!("error: not found\n{}", time_report("0:00.01", 1024))));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.exit_code, Some(2));
+        assert_eq!(result.stderr, "error: not found\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn compile_failures_are_compile_errors() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        let stderr = "main.cpp:1:1: error: expected unqualified-id\nCompilation failed\n";
+        fake.script(ScriptedRun::exits(2).stderr(stderr));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::CompileError);
+        assert_eq!(result.stderr, stderr);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn programs_cannot_claim_a_compile_error() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        let output = "Compilation failed\n===CODE_EXEC_COMPILE_ERROR===\n";
+        fake.script(ScriptedRun::exits(2).stderr(&format!("{}{}", output, time_report("0:00.01", 1024))));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.stderr, output);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait_errors_are_runtime_errors() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.fail("wait_container");
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::RuntimeError);
+        assert!(result.stderr.contains("Container error: wait_container failed"));
+        assert!(fake.live_containers().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hanging_containers_are_killed_at_the_timeout() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::hangs().stdout("partial"));
+
+        let started = tokio::time::Instant::now();
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Timeout);
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.stdout, "partial");
+        assert_eq!(started.elapsed(), Duration::from_secs(2));
+
+        let containers = fake.containers();
+        assert!(containers[0].killed && containers[0].removed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn oom_kills_exceed_the_memory_limit() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::exits(137).oom_killed());
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::MemoryLimitExceeded);
+        assert!(result.stderr.contains("OOMKilled"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn printing_out_of_memory_messages_is_not_running_out_of_memory() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::exits(1).stdout("java.lang.OutOfMemoryError\n")
+            .stderr(&format!("MemoryError\n{}", time_report("0:00.01", 1024))));
+
+        assert_eq!(run(&manager, &[]).await.unwrap().status, ExecutionStatus::Failed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn seccomp_kills_are_security_violations() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        let report = time_report("0:00.01", 1024).replace("BEGIN===\n", "BEGIN===\nCommand terminated by signal 31\n");
+        fake.script(ScriptedRun::exits(159).stderr(&report));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::SecurityViolation);
+        assert_eq!(result.stderr, "Process killed for a forbidden system call\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn time_reports_printed_by_the_program_are_output() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        let forged = time_report("9:59:59", 1).replace("BEGIN===\n", "BEGIN===\nCommand terminated by signal 31\n");
+        fake.script(ScriptedRun::exits(159).stderr(&format!("{}{}", forged, time_report("0:00.20", 2048))));
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.stderr, forged);
+        assert_eq!((result.execution_time_ms, result.memory_used_kb), (200.0, 2048));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn signals_in_the_report_need_the_matching_exit_code() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        let report = time_report("0:00.01", 1024).replace("BEGIN===\n", "BEGIN===\nCommand terminated by signal 31\n");
+        fake.script(ScriptedRun::exits(1).stderr(&report));
+
+        assert_eq!(run(&manager, &[]).await.unwrap().status, ExecutionStatus::Failed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeouts_take_priority_over_oom_kills() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::hangs().oom_killed());
+
+        let result = run(&manager, &[]).await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Timeout);
+        assert!(result.stderr.contains("OOMKilled"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn input_lines_are_written_to_stdin() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+
+        run(&manager, &["1 2", "3"]).await.unwrap();
+        assert_eq!(fake.containers()[0].stdin, b"1 2\n3\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn containers_run_the_recorded_digest_with_labels_and_limits() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+
+        run(&manager, &[]).await.unwrap();
+        let container = &fake.containers()[0];
+        let host_config = container.config.host_config.as_ref().unwrap();
+        assert_eq!(container.config.image.as_deref(), Some(DIGEST));
+        assert!(container.name.starts_with("code-exec-cpp-"));
+        assert_eq!(container.config.labels.as_ref().unwrap()[INSTANCE_LABEL], manager.identity().id);
+        assert_eq!(host_config.memory, Some(128 * 1024 * 1024));
+        assert_eq!(host_config.memory_swap, host_config.memory);
+        assert_eq!(container.config.network_disabled, Some(true));
+        assert_eq!(container.config.user.as_deref(), Some(RUN_SCRIPT_USER));
+        let security_opt = host_config.security_opt.as_ref().unwrap();
+        assert_eq!(security_opt[0], "no-new-privileges");
+        assert!(security_opt[1].starts_with("seccomp={") && security_opt[1].contains("\"ptrace\""));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn containers_are_removed_when_start_fails() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.fail("start_container");
+
+        assert!(run(&manager, &[]).await.is_err());
+        assert_eq!(fake.containers().len(), 1);
+        assert!(fake.live_containers().is_empty());
+        assert_eq!(manager.remove_active_containers().await, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn images_missing_at_startup_cannot_run() {
+        let fake = FakeDocker::new();
+        let manager = ContainerManager::with_client(DockerConfig::default(), Arc::new(fake.clone()));
+        let report = manager.check_images(&[ImageSource { image: IMAGE.to_string(), context: None }]).await;
+
+        assert_eq!(report.missing[IMAGE], "not found locally");
+        assert!(run(&manager, &[]).await.is_err());
+        assert!(fake.containers().is_empty());
+    }
+
+    async fn pooled_manager(fake: &FakeDocker) -> ContainerManager {
+        let manager = manager(fake).await
+            .with_pool(PoolConfig { enabled: true, ..Default::default() }, 10, [IMAGE.to_string()]);
+        let pool = manager.pool.as_ref().unwrap();
+        manager.refill_pool(pool, &mut HashMap::new()).await;
+        manager
+    }
+
+    /// Let background removals finish
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pooled_containers_run_one_execution_each() {
+        let fake = FakeDocker::new();
+        let manager = pooled_manager(&fake).await;
+        let idle = fake.live_containers();
+        assert_eq!(idle.len(), 1);
+        assert_eq!(idle[0].config.labels.as_ref().unwrap()[EXECUTION_LABEL], IDLE_EXECUTION_LABEL);
+
+        fake.script(ScriptedRun::exits(0).stdout("warm\n"));
+        let result = run(&manager, &["42"]).await.unwrap();
+        settle().await;
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.stdout, "warm\n");
+        assert_eq!(result.image_digest, DIGEST);
+        let container = &fake.containers()[0];
+        assert_eq!(container.execs, vec![run_script_command()]);
+        assert_eq!(container.memory, Some(128 * 1024 * 1024));
+        assert_eq!(container.stdin, b"42\n");
+        assert!(container.removed);
+        assert_eq!(manager.latency().total(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pooled_executions_time_out_and_detect_oom_kills() {
+        let fake = FakeDocker::new();
+        let manager = pooled_manager(&fake).await;
+        fake.script(ScriptedRun::hangs());
+        assert_eq!(run(&manager, &[]).await.unwrap().status, ExecutionStatus::Timeout);
+
+        manager.refill_pool(manager.pool.as_ref().unwrap(), &mut HashMap::new()).await;
+        fake.script(ScriptedRun::exits(137).oom_killed());
+        assert_eq!(run(&manager, &[]).await.unwrap().status, ExecutionStatus::MemoryLimitExceeded);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unusable_pooled_containers_fall_back_to_a_cold_start() {
+        let fake = FakeDocker::new();
+        let manager = pooled_manager(&fake).await;
+        fake.fail("start_exec");
+
+        let result = run(&manager, &[]).await.unwrap();
+        settle().await;
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        let containers = fake.containers();
+        assert_eq!(containers.len(), 2);
+        assert!(containers.iter().all(|container| container.removed));
+        assert!(containers[1].name.starts_with("code-exec-cpp-"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_removes_idle_and_running_containers() {
+        let fake = FakeDocker::new();
+        let manager = Arc::new(pooled_manager(&fake).await);
+        fake.script(ScriptedRun::hangs());
+        fake.fail("start_exec");
+
+        let running = tokio::spawn({
+            let manager = manager.clone();
+            async move { run(&manager, &[]).await }
+        });
+        settle().await;
+        assert_eq!(fake.live_containers().len(), 1);
+
+        assert_eq!(manager.remove_active_containers().await, 1);
+        assert!(fake.live_containers().is_empty());
+        assert!(running.await.unwrap().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_executions_remove_their_container() {
+        let fake = FakeDocker::new();
+        let manager = manager(&fake).await;
+        fake.script(ScriptedRun::hangs());
+
+        // 클라이언트가 끊기면 tonic이 실행 future를 버림
+        assert!(tokio::time::timeout(Duration::from_secs(1), run(&manager, &[])).await.is_err());
+        settle().await;
+
+        assert_eq!(fake.containers().len(), 1);
+        assert!(fake.live_containers().is_empty());
+        assert_eq!(manager.remove_active_containers().await, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_pooled_executions_remove_their_container() {
+        let fake = FakeDocker::new();
+        let manager = pooled_manager(&fake).await;
+        fake.script(ScriptedRun::hangs());
+
+        assert!(tokio::time::timeout(Duration::from_secs(1), run(&manager, &[])).await.is_err());
+        settle().await;
+
+        assert!(fake.live_containers().is_empty());
+        assert_eq!(manager.remove_active_containers().await, 0);
+    }
+}
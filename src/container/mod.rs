@@ -1,3 +1,15 @@
+pub mod client;
+#[cfg(test)]
+mod fake;
+pub mod images;
+pub mod latency;
 pub mod manager;
+pub mod pool;
+pub mod reaper;
 
-pub use manager::{ContainerManager, ExecutionResult, ExecutionStatus};
\ No newline at end of file
+pub use client::{DockerClient, ExecSession};
+pub use images::{ImageApi, ImageReport, ImageSource};
+pub use latency::{LatencyHistograms, LatencySummary, StartKind};
+pub use manager::{ContainerManager, DockerConfig};
+pub use pool::{PoolConfig, WarmPool};
+pub use reaper::{ContainerApi, InstanceIdentity, Reaper};
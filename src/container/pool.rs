@@ -0,0 +1,318 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::env_override;
+
+/// Settings for the warm container pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoolConfig {
+    pub enabled: bool,
+    /// Idle containers kept per image even without demand
+    pub min_idle_per_image: usize,
+    /// Upper bound on idle containers per image
+    pub max_idle_per_image: usize,
+    /// Executions within this window decide how many idle containers an image gets
+    pub demand_window_seconds: u64,
+    /// How often the pool is topped up
+    pub refill_interval_ms: u64,
+    /// Idle containers older than this are replaced
+    pub max_idle_seconds: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_idle_per_image: 1,
+            max_idle_per_image: 4,
+            demand_window_seconds: 60,
+            refill_interval_ms: 500,
+            max_idle_seconds: 300,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Apply `CODE_EXECUTOR_POOL_ENABLED`, `CODE_EXECUTOR_POOL_MIN_IDLE` and
+    /// `CODE_EXECUTOR_POOL_MAX_IDLE` on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_POOL_ENABLED", &mut self.enabled)?;
+        env_override("CODE_EXECUTOR_POOL_MIN_IDLE", &mut self.min_idle_per_image)?;
+        env_override("CODE_EXECUTOR_POOL_MAX_IDLE", &mut self.max_idle_per_image)?;
+        Ok(())
+    }
+
+    /// Describe every invalid setting
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.min_idle_per_image > self.max_idle_per_image {
+            errors.push(format!("min_idle_per_image ({}) must not exceed max_idle_per_image ({})",
+                                self.min_idle_per_image, self.max_idle_per_image));
+        }
+        if self.enabled && self.max_idle_per_image == 0 {
+            errors.push("max_idle_per_image must be greater than 0 when the pool is enabled".to_string());
+        }
+        if self.refill_interval_ms == 0 {
+            errors.push("refill_interval_ms must be greater than 0".to_string());
+        }
+        if self.max_idle_seconds == 0 {
+            errors.push("max_idle_seconds must be greater than 0".to_string());
+        }
+        errors
+    }
+
+    pub fn refill_interval(&self) -> Duration {
+        Duration::from_millis(self.refill_interval_ms)
+    }
+
+    pub fn max_idle(&self) -> Duration {
+        Duration::from_secs(self.max_idle_seconds)
+    }
+}
+
+#[derive(Debug)]
+struct IdleContainer {
+    id: String,
+    created_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ImagePool {
+    idle: VecDeque<IdleContainer>,
+    /// When executions asked for this image, oldest first
+    demand: VecDeque<Instant>,
+}
+
+/// What the refill task should do next
+#[derive(Debug, Default, PartialEq)]
+pub struct RefillPlan {
+    /// Number of containers to create per image
+    pub create: Vec<(String, usize)>,
+    /// Idle containers that are too old and must be removed
+    pub retire: Vec<String>,
+}
+
+/// Bookkeeping for pre-started containers, one queue per image.
+///
+/// Containers are handed out exactly once: `acquire` removes them from the pool and the
+/// caller destroys them after the execution, so nothing ever runs twice in the same container.
+/// Docker calls are left to `ContainerManager`.
+#[derive(Debug)]
+pub struct WarmPool {
+    config: PoolConfig,
+    state: Mutex<PoolState>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    images: HashMap<String, ImagePool>,
+    closed: bool,
+}
+
+impl WarmPool {
+    pub fn new(config: PoolConfig, images: impl IntoIterator<Item = String>) -> Self {
+        let images = images.into_iter().map(|image| (image, ImagePool::default())).collect();
+        Self {
+            config,
+            state: Mutex::new(PoolState { images, closed: false }),
+        }
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Take an idle container for `image`, recording the demand either way
+    pub fn acquire(&self, image: &str, now: Instant) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return None;
+        }
+        let pool = state.images.get_mut(image)?;
+        pool.demand.push_back(now);
+        pool.idle.pop_front().map(|container| container.id)
+    }
+
+    /// Idle containers wanted for `image`: the executions seen in the demand window,
+    /// kept between the configured minimum and maximum
+    fn target(&self, pool: &ImagePool) -> usize {
+        pool.demand.len().clamp(self.config.min_idle_per_image, self.config.max_idle_per_image)
+    }
+
+    /// Forget old demand, pull out expired idle containers and work out what to create
+    pub fn plan(&self, now: Instant) -> RefillPlan {
+        let window = Duration::from_secs(self.config.demand_window_seconds);
+        let max_idle = self.config.max_idle();
+        let mut state = self.state.lock().unwrap();
+        let mut plan = RefillPlan::default();
+        if state.closed {
+            return plan;
+        }
+
+        let mut images: Vec<_> = state.images.iter_mut().collect();
+        images.sort_by(|a, b| a.0.cmp(b.0));
+        for (image, pool) in images {
+            while pool.demand.front().is_some_and(|at| now.duration_since(*at) > window) {
+                pool.demand.pop_front();
+            }
+            while pool.idle.front().is_some_and(|container| now.duration_since(container.created_at) > max_idle) {
+                plan.retire.push(pool.idle.pop_front().unwrap().id);
+            }
+
+            let target = self.target(pool);
+            if target > pool.idle.len() {
+                plan.create.push((image.clone(), target - pool.idle.len()));
+            }
+        }
+        plan
+    }
+
+    /// Hand a freshly started container to the pool. Returns false when the pool has been
+    /// closed or is already full, in which case the caller must remove the container.
+    pub fn add(&self, image: &str, id: String, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return false;
+        }
+        let Some(pool) = state.images.get(image) else {
+            return false;
+        };
+        if pool.idle.len() >= self.target(pool) {
+            return false;
+        }
+        state.images.get_mut(image).unwrap().idle.push_back(IdleContainer { id, created_at: now });
+        true
+    }
+
+    /// Stop handing out and accepting containers, returning the idle ones for removal
+    pub fn close(&self) -> Vec<String> {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.images.values_mut()
+            .flat_map(|pool| pool.idle.drain(..).map(|container| container.id))
+            .collect()
+    }
+
+    /// Number of idle containers per image
+    pub fn idle_counts(&self) -> Vec<(String, usize)> {
+        let state = self.state.lock().unwrap();
+        let mut counts: Vec<_> = state.images.iter().map(|(image, pool)| (image.clone(), pool.idle.len())).collect();
+        counts.sort();
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(min: usize, max: usize) -> WarmPool {
+        let config = PoolConfig {
+            enabled: true,
+            min_idle_per_image: min,
+            max_idle_per_image: max,
+            demand_window_seconds: 60,
+            max_idle_seconds: 300,
+            ..Default::default()
+        };
+        WarmPool::new(config, ["python".to_string(), "ruby".to_string()])
+    }
+
+    fn fill(pool: &WarmPool, now: Instant) {
+        for (image, count) in pool.plan(now).create {
+            for n in 0..count {
+                assert!(pool.add(&image, format!("{}-{}", image, n), now));
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_the_minimum_without_demand() {
+        let pool = pool(1, 4);
+        let plan = pool.plan(Instant::now());
+        assert_eq!(plan.create, vec![("python".to_string(), 1), ("ruby".to_string(), 1)]);
+        assert!(plan.retire.is_empty());
+    }
+
+    #[test]
+    fn grows_with_demand_up_to_the_maximum() {
+        let pool = pool(1, 4);
+        let now = Instant::now();
+        for _ in 0..3 {
+            pool.acquire("python", now);
+        }
+        assert_eq!(pool.plan(now).create, vec![("python".to_string(), 3), ("ruby".to_string(), 1)]);
+
+        for _ in 0..10 {
+            pool.acquire("python", now);
+        }
+        assert_eq!(pool.plan(now).create[0], ("python".to_string(), 4));
+    }
+
+    #[test]
+    fn demand_outside_the_window_is_forgotten() {
+        let pool = pool(0, 4);
+        let start = Instant::now();
+        pool.acquire("python", start);
+        pool.acquire("python", start);
+
+        assert_eq!(pool.plan(start + Duration::from_secs(61)), RefillPlan::default());
+    }
+
+    #[test]
+    fn containers_are_handed_out_once() {
+        let pool = pool(2, 2);
+        let now = Instant::now();
+        fill(&pool, now);
+
+        let first = pool.acquire("python", now).unwrap();
+        let second = pool.acquire("python", now).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(pool.acquire("python", now), None);
+        assert_eq!(pool.idle_counts(), vec![("python".to_string(), 0), ("ruby".to_string(), 2)]);
+    }
+
+    #[test]
+    fn unknown_images_are_never_pooled() {
+        let pool = pool(1, 4);
+        assert_eq!(pool.acquire("java", Instant::now()), None);
+        assert!(!pool.add("java", "java-0".to_string(), Instant::now()));
+    }
+
+    #[test]
+    fn rejects_containers_beyond_the_target() {
+        let pool = pool(1, 4);
+        let now = Instant::now();
+        assert!(pool.add("python", "python-0".to_string(), now));
+        assert!(!pool.add("python", "python-1".to_string(), now));
+    }
+
+    #[test]
+    fn retires_expired_idle_containers() {
+        let pool = pool(1, 1);
+        let start = Instant::now();
+        fill(&pool, start);
+
+        let plan = pool.plan(start + Duration::from_secs(301));
+        assert_eq!(plan.retire, vec!["python-0".to_string(), "ruby-0".to_string()]);
+        assert_eq!(plan.create, vec![("python".to_string(), 1), ("ruby".to_string(), 1)]);
+    }
+
+    #[test]
+    fn closing_drains_and_stops_the_pool() {
+        let pool = pool(1, 1);
+        let now = Instant::now();
+        fill(&pool, now);
+
+        let mut drained = pool.close();
+        drained.sort();
+        assert_eq!(drained, vec!["python-0".to_string(), "ruby-0".to_string()]);
+        assert_eq!(pool.acquire("python", now), None);
+        assert!(!pool.add("python", "python-1".to_string(), now));
+        assert_eq!(pool.plan(now), RefillPlan::default());
+    }
+}
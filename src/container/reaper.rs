@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use async_trait::async_trait;
+use bollard::Docker;
+use bollard::container::{ListContainersOptions, RemoveContainerOptions};
+use nix::errno::Errno;
+use nix::sys::signal::kill;
+use nix::unistd::Pid;
+
+/// Label keys set on every container created by `ContainerManager`
+pub const INSTANCE_LABEL: &str = "code-executor.instance";
+pub const EXECUTION_LABEL: &str = "code-executor.execution";
+/// Unix seconds after which the container is considered abandoned
+pub const DEADLINE_LABEL: &str = "code-executor.deadline";
+pub const HOST_LABEL: &str = "code-executor.host";
+pub const PID_LABEL: &str = "code-executor.pid";
+
+/// Identifies the server process that owns a container
+#[derive(Debug, Clone)]
+pub struct InstanceIdentity {
+    pub id: String,
+    pub host: String,
+    pub pid: u32,
+}
+
+impl InstanceIdentity {
+    /// Identity of the running process; the id is new on every start
+    pub fn current() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            host: nix::unistd::gethostname()
+                .map(|host| host.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            pid: std::process::id(),
+        }
+    }
+
+    /// Ownership labels for a container running `execution_id` until `deadline`
+    pub fn labels(&self, execution_id: &str, deadline: SystemTime) -> HashMap<String, String> {
+        HashMap::from([
+            (INSTANCE_LABEL.to_string(), self.id.clone()),
+            (EXECUTION_LABEL.to_string(), execution_id.to_string()),
+            (DEADLINE_LABEL.to_string(), unix_seconds(deadline).to_string()),
+            (HOST_LABEL.to_string(), self.host.clone()),
+            (PID_LABEL.to_string(), self.pid.to_string()),
+        ])
+    }
+}
+
+/// A container carrying the ownership labels
+#[derive(Debug, Clone)]
+pub struct LabeledContainer {
+    pub id: String,
+    pub labels: HashMap<String, String>,
+}
+
+/// The part of the Docker API the reaper needs, so it can be tested without a daemon
+#[async_trait]
+pub trait ContainerApi: Send + Sync {
+    /// Every container, running or not, that has `label` set
+    async fn list_labeled(&self, label: &str) -> Result<Vec<LabeledContainer>>;
+    /// Kill and remove a container
+    async fn force_remove(&self, id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl ContainerApi for Docker {
+    async fn list_labeled(&self, label: &str) -> Result<Vec<LabeledContainer>> {
+        let containers = self.list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            filters: HashMap::from([("label".to_string(), vec![label.to_string()])]),
+            ..Default::default()
+        })).await?;
+
+        Ok(containers.into_iter()
+            .filter_map(|container| Some(LabeledContainer {
+                id: container.id?,
+                labels: container.labels.unwrap_or_default(),
+            }))
+            .collect())
+    }
+
+    async fn force_remove(&self, id: &str) -> Result<()> {
+        self.remove_container(id, Some(RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        })).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: ContainerApi + ?Sized> ContainerApi for Arc<T> {
+    async fn list_labeled(&self, label: &str) -> Result<Vec<LabeledContainer>> {
+        (**self).list_labeled(label).await
+    }
+
+    async fn force_remove(&self, id: &str) -> Result<()> {
+        (**self).force_remove(id).await
+    }
+}
+
+/// Why a container is being reaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReapReason {
+    /// The execution should have finished long ago
+    PastDeadline,
+    /// The server process that created it on this host is gone
+    OwnerDead,
+}
+
+/// Removes labeled containers that are past their deadline or whose owning server has died.
+///
+/// Containers of other live instances are left alone until their deadline, so several
+/// servers can share one Docker daemon.
+pub struct Reaper<A> {
+    api: A,
+    identity: InstanceIdentity,
+    is_process_alive: fn(u32) -> bool,
+}
+
+impl<A: ContainerApi> Reaper<A> {
+    pub fn new(api: A, identity: InstanceIdentity) -> Self {
+        Self { api, identity, is_process_alive: process_alive }
+    }
+
+    /// Replace the liveness check for owner processes on this host
+    pub fn with_process_check(mut self, is_process_alive: fn(u32) -> bool) -> Self {
+        self.is_process_alive = is_process_alive;
+        self
+    }
+
+    /// Decide whether a container should be removed at `now`
+    pub fn reap_reason(&self, container: &LabeledContainer, now: SystemTime) -> Option<ReapReason> {
+        let labels = &container.labels;
+        let deadline = labels.get(DEADLINE_LABEL).and_then(|value| value.parse::<u64>().ok());
+        if deadline.is_some_and(|deadline| deadline < unix_seconds(now)) {
+            return Some(ReapReason::PastDeadline);
+        }
+
+        // 같은 호스트의 다른 인스턴스만 프로세스 생존 여부를 확인할 수 있음
+        let instance = labels.get(INSTANCE_LABEL)?;
+        let same_host = labels.get(HOST_LABEL).is_some_and(|host| *host == self.identity.host);
+        if *instance == self.identity.id || !same_host {
+            return None;
+        }
+        match labels.get(PID_LABEL).and_then(|pid| pid.parse::<u32>().ok()) {
+            Some(pid) if (self.is_process_alive)(pid) => None,
+            _ => Some(ReapReason::OwnerDead),
+        }
+    }
+
+    /// One sweep over all labeled containers, returning how many were removed
+    pub async fn reap_once(&self, now: SystemTime) -> Result<usize> {
+        let mut removed = 0;
+        for container in self.api.list_labeled(INSTANCE_LABEL).await? {
+            let Some(reason) = self.reap_reason(&container, now) else {
+                continue;
+            };
+            match self.api.force_remove(&container.id).await {
+                Ok(()) => {
+                    tracing::warn!(
+                        "Reaped container {} (execution {}): {:?}",
+                        container.id,
+                        container.labels.get(EXECUTION_LABEL).map(String::as_str).unwrap_or("?"),
+                        reason
+                    );
+                    removed += 1;
+                }
+                Err(e) => tracing::error!("Failed to reap container {}: {}", container.id, e),
+            }
+        }
+        Ok(removed)
+    }
+}
+
+impl<A: ContainerApi + 'static> Reaper<A> {
+    /// Sweep now and then every `interval`
+    pub fn spawn(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let reaper = Arc::new(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = reaper.reap_once(SystemTime::now()).await {
+                    tracing::error!("Container reaper sweep failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Signal 0 checks for existence without delivering anything; EPERM still means it exists
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    !matches!(kill(Pid::from_raw(pid), None), Err(Errno::ESRCH))
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockApi {
+        containers: Mutex<Vec<LabeledContainer>>,
+        fail_removal: Vec<String>,
+    }
+
+    impl MockApi {
+        fn with(containers: Vec<LabeledContainer>) -> Self {
+            Self { containers: Mutex::new(containers), ..Default::default() }
+        }
+
+        fn remaining(&self) -> Vec<String> {
+            self.containers.lock().unwrap().iter().map(|container| container.id.clone()).collect()
+        }
+    }
+
+    #[async_trait]
+    impl ContainerApi for MockApi {
+        async fn list_labeled(&self, label: &str) -> Result<Vec<LabeledContainer>> {
+            Ok(self.containers.lock().unwrap().iter()
+                .filter(|container| container.labels.contains_key(label))
+                .cloned()
+                .collect())
+        }
+
+        async fn force_remove(&self, id: &str) -> Result<()> {
+            if self.fail_removal.iter().any(|failing| failing == id) {
+                anyhow::bail!("removal of {} failed", id);
+            }
+            self.containers.lock().unwrap().retain(|container| container.id != id);
+            Ok(())
+        }
+    }
+
+    const LIVE_PID: u32 = 100;
+    const DEAD_PID: u32 = 200;
+
+    fn identity(id: &str, host: &str, pid: u32) -> InstanceIdentity {
+        InstanceIdentity { id: id.to_string(), host: host.to_string(), pid }
+    }
+
+    fn container(id: &str, owner: &InstanceIdentity, deadline: SystemTime) -> LabeledContainer {
+        LabeledContainer { id: id.to_string(), labels: owner.labels(id, deadline) }
+    }
+
+    fn reaper(api: MockApi) -> Reaper<MockApi> {
+        Reaper::new(api, identity("self", "node-a", LIVE_PID)).with_process_check(|pid| pid == LIVE_PID)
+    }
+
+    fn later() -> SystemTime {
+        SystemTime::now() + Duration::from_secs(600)
+    }
+
+    fn earlier() -> SystemTime {
+        SystemTime::now() - Duration::from_secs(600)
+    }
+
+    #[tokio::test]
+    async fn removes_containers_past_their_deadline() {
+        let me = identity("self", "node-a", LIVE_PID);
+        let reaper = reaper(MockApi::with(vec![
+            container("expired", &me, earlier()),
+            container("running", &me, later()),
+        ]));
+
+        assert_eq!(reaper.reap_once(SystemTime::now()).await.unwrap(), 1);
+        assert_eq!(reaper.api.remaining(), vec!["running"]);
+    }
+
+    #[tokio::test]
+    async fn removes_containers_of_dead_instances_on_this_host() {
+        let dead = identity("crashed", "node-a", DEAD_PID);
+        let alive = identity("neighbour", "node-a", LIVE_PID);
+        let reaper = reaper(MockApi::with(vec![
+            container("orphan", &dead, later()),
+            container("neighbours", &alive, later()),
+        ]));
+
+        assert_eq!(reaper.reap_once(SystemTime::now()).await.unwrap(), 1);
+        assert_eq!(reaper.api.remaining(), vec!["neighbours"]);
+    }
+
+    #[tokio::test]
+    async fn leaves_other_hosts_alone_until_their_deadline() {
+        let remote = identity("remote", "node-b", DEAD_PID);
+        let reaper = reaper(MockApi::with(vec![
+            container("remote-running", &remote, later()),
+            container("remote-expired", &remote, earlier()),
+        ]));
+
+        assert_eq!(reaper.reap_once(SystemTime::now()).await.unwrap(), 1);
+        assert_eq!(reaper.api.remaining(), vec!["remote-running"]);
+    }
+
+    #[test]
+    fn missing_pid_on_this_host_counts_as_dead_owner() {
+        let reaper = reaper(MockApi::default());
+        let mut orphan = container("orphan", &identity("old", "node-a", DEAD_PID), later());
+        orphan.labels.remove(PID_LABEL);
+
+        assert_eq!(reaper.reap_reason(&orphan, SystemTime::now()), Some(ReapReason::OwnerDead));
+    }
+
+    #[tokio::test]
+    async fn keeps_sweeping_after_a_failed_removal() {
+        let me = identity("self", "node-a", LIVE_PID);
+        let mut api = MockApi::with(vec![
+            container("stuck", &me, earlier()),
+            container("expired", &me, earlier()),
+        ]);
+        api.fail_removal.push("stuck".to_string());
+        let reaper = reaper(api);
+
+        assert_eq!(reaper.reap_once(SystemTime::now()).await.unwrap(), 1);
+        assert_eq!(reaper.api.remaining(), vec!["stuck"]);
+    }
+
+    #[test]
+    fn labels_carry_owner_and_deadline() {
+        let labels = identity("self", "node-a", 42).labels("exec-1", UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+        assert_eq!(labels[INSTANCE_LABEL], "self");
+        assert_eq!(labels[EXECUTION_LABEL], "exec-1");
+        assert_eq!(labels[DEADLINE_LABEL], "1700000000");
+        assert_eq!(labels[HOST_LABEL], "node-a");
+        assert_eq!(labels[PID_LABEL], "42");
+    }
+
+    #[test]
+    fn current_process_is_alive() {
+        assert!(process_alive(std::process::id()));
+    }
+}
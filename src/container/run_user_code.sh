@@ -0,0 +1,45 @@
+#!/bin/bash
+# Generic entrypoint for every language image, passed to `bash -c` by ContainerManager.
+# Environment: USER_CODE, WORK_DIR, SOURCE_FILE, RUN_COMMAND and optionally COMPILE_COMMAND,
+# with both commands already shell-quoted from the language manifest. TIME_COMMAND replaces
+# /usr/bin/time in tests.
+#
+# The script itself runs as root; compilation and the program run as the image's uid 1000
+# user, so nothing the program does can reach the time report.
+
+SANDBOX_UID=1000
+SANDBOX=(setpriv --reuid="$SANDBOX_UID" --regid="$SANDBOX_UID" --clear-groups --inh-caps=-all --)
+# 사용자 프로그램에는 표준 입출력만 넘김 (time이 열어 둔 보고서 파일 등 나머지 fd는 닫고 실행)
+CLOSE_FDS='for fd in /proc/$$/fd/*; do fd=${fd##*/}; [ "$fd" -gt 2 ] && eval "exec $fd>&-"; done; exec "$@"'
+export HOME
+HOME=$(getent passwd "$SANDBOX_UID" | cut -d: -f6)
+HOME=${HOME:-$WORK_DIR}
+
+mkdir -p "$WORK_DIR" && cd "$WORK_DIR" || exit 1
+printf '%s\n' "$USER_CODE" > "$SOURCE_FILE"
+chown -R "$SANDBOX_UID:$SANDBOX_UID" "$WORK_DIR" || exit 1
+
+if [ -n "$COMPILE_COMMAND" ]; then
+  if ! eval "\"\${SANDBOX[@]}\" $COMPILE_COMMAND"; then
+    echo "Compilation failed" >&2
+    # 시간 보고서 없이 이 종료 코드로 끝나면 서버가 COMPILE_ERROR로 보고함
+    # (실행 단계는 항상 보고서를 덧붙이므로 사용자 프로그램은 이 조합을 만들 수 없음)
+    exit 2
+  fi
+fi
+
+# 실행 시간/메모리 측정 결과는 stderr 끝에 블록으로 덧붙임
+# 보고서는 root만 들어갈 수 있는 디렉터리에 기록 (time도 root로 실행되므로 /proc/<pid>/fd로도 열 수 없음)
+REPORT_DIR=$(mktemp -d) || exit 1
+TIME_FILE="$REPORT_DIR/time"
+eval "\"\${TIME_COMMAND:-/usr/bin/time}\" -v -o \"\$TIME_FILE\" \"\${SANDBOX[@]}\" bash -c \"\$CLOSE_FDS\" run_user_code $RUN_COMMAND"
+STATUS=$?
+# 백그라운드에 남은 사용자 프로세스를 모두 종료해 보고서가 stderr의 마지막 출력이 되게 함
+# (서버는 stderr 끝의 보고서만 읽음; PID 1과 kill을 보내는 셸 자신은 kill -1의 대상이 아님)
+"${SANDBOX[@]}" bash -c 'kill -KILL -1' 2>/dev/null
+
+echo "===CODE_EXEC_TIME_BEGIN===" 1>&2
+cat "$TIME_FILE" 1>&2
+echo "===CODE_EXEC_TIME_END===" 1>&2
+rm -rf "$REPORT_DIR"
+exit $STATUS
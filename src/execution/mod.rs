@@ -0,0 +1,3 @@
+mod result;
+
+pub use result::{CompileResult, ExecutionResult, ExecutionStatus};
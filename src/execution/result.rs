@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+
+use crate::proto;
+
+/// How an execution ended; every backend reports the same status for the same program.
+/// Serialized with the API's names, e.g. `MEMORY_LIMIT_EXCEEDED`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionStatus {
+    #[default]
+    Pending,
+    Running,
+    /// Exited with status 0
+    Completed,
+    /// Exited with a non-zero status or was killed by a signal
+    Failed,
+    Timeout,
+    MemoryLimitExceeded,
+    /// The backend lost track of the program, e.g. its container could not be waited for
+    RuntimeError,
+    /// Killed by the seccomp profile for a denied system call
+    SecurityViolation,
+    /// The code did not compile, so it never ran
+    CompileError,
+}
+
+impl ExecutionStatus {
+    /// Status of a program that exited on its own with `exit_code`
+    pub fn from_exit_code(exit_code: i32) -> Self {
+        if exit_code == 0 {
+            Self::Completed
+        } else {
+            Self::Failed
+        }
+    }
+}
+
+impl From<ExecutionStatus> for proto::ExecutionStatus {
+    fn from(status: ExecutionStatus) -> Self {
+        match status {
+            ExecutionStatus::Pending => Self::Pending,
+            ExecutionStatus::Running => Self::Running,
+            ExecutionStatus::Completed => Self::Completed,
+            ExecutionStatus::Failed => Self::Failed,
+            ExecutionStatus::Timeout => Self::Timeout,
+            ExecutionStatus::MemoryLimitExceeded => Self::MemoryLimitExceeded,
+            ExecutionStatus::RuntimeError => Self::RuntimeError,
+            ExecutionStatus::SecurityViolation => Self::SecurityViolation,
+            ExecutionStatus::CompileError => Self::CompileError,
+        }
+    }
+}
+
+impl From<proto::ExecutionStatus> for ExecutionStatus {
+    fn from(status: proto::ExecutionStatus) -> Self {
+        match status {
+            proto::ExecutionStatus::Pending => Self::Pending,
+            proto::ExecutionStatus::Running => Self::Running,
+            proto::ExecutionStatus::Completed => Self::Completed,
+            proto::ExecutionStatus::Failed => Self::Failed,
+            proto::ExecutionStatus::Timeout => Self::Timeout,
+            proto::ExecutionStatus::MemoryLimitExceeded => Self::MemoryLimitExceeded,
+            proto::ExecutionStatus::RuntimeError => Self::RuntimeError,
+            proto::ExecutionStatus::SecurityViolation => Self::SecurityViolation,
+            proto::ExecutionStatus::CompileError => Self::CompileError,
+        }
+    }
+}
+
+/// Output and resource usage of one execution, the same for every backend.
+/// Field names and units match `ExecuteResponse`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionResult {
+    pub status: ExecutionStatus,
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit status of the program, 128 + the signal number when a signal killed it;
+    /// `None` when it was killed for its time limit or its exit could not be observed
+    pub exit_code: Option<i32>,
+    /// Wall clock time
+    pub execution_time_ms: f64,
+    /// Peak resident set size of the program and its descendants
+    pub memory_used_kb: u64,
+    /// CPU time spent in user mode, including descendants
+    pub cpu_user_ms: f64,
+    /// CPU time spent in the kernel, including descendants
+    pub cpu_system_ms: f64,
+    pub voluntary_context_switches: u64,
+    pub involuntary_context_switches: u64,
+    /// Toolchain the code ran with: the language image id (sha256:...) for Docker,
+    /// `native:<paths>` for the native backend
+    pub image_digest: String,
+    /// Confinement that was in effect for the run, e.g. `cgroup`, `namespaces`, `landlock`, `seccomp`
+    pub protections: Vec<String>,
+    /// Stdout or stderr went over the backend's output limit, or was still open after the program
+    /// ended, and the rest was discarded; only the native backend limits output
+    pub output_truncated: bool,
+    /// Compile step captured apart from the run; `None` for interpreted languages and in
+    /// containers, where the run script compiles and runs in one go
+    pub compile: Option<CompileResult>,
+}
+
+/// Output and usage of a compile step, which runs under the manifest's compile limits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompileResult {
+    /// `Completed` when the program was built
+    pub status: ExecutionStatus,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub execution_time_ms: f64,
+    pub memory_used_kb: u64,
+    pub output_truncated: bool,
+}
+
+impl From<ExecutionResult> for CompileResult {
+    fn from(result: ExecutionResult) -> Self {
+        Self {
+            status: result.status,
+            stdout: result.stdout,
+            stderr: result.stderr,
+            exit_code: result.exit_code,
+            execution_time_ms: result.execution_time_ms,
+            memory_used_kb: result.memory_used_kb,
+            output_truncated: result.output_truncated,
+        }
+    }
+}
+
+/// The response fields describing the run; the execution id, queueing and language
+/// resolution are left for the caller to fill in
+impl From<ExecutionResult> for proto::ExecuteResponse {
+    fn from(result: ExecutionResult) -> Self {
+        Self {
+            status: proto::ExecutionStatus::from(result.status).into(),
+            stdout: result.stdout,
+            stderr: result.stderr,
+            exit_code: result.exit_code,
+            execution_time_ms: result.execution_time_ms,
+            memory_used_kb: result.memory_used_kb,
+            cpu_user_ms: result.cpu_user_ms,
+            cpu_system_ms: result.cpu_system_ms,
+            voluntary_context_switches: result.voluntary_context_switches,
+            involuntary_context_switches: result.involuntary_context_switches,
+            image_digest: result.image_digest,
+            protections: result.protections,
+            output_truncated: result.output_truncated,
+            compile: result.compile.map(Into::into),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<proto::ExecuteResponse> for ExecutionResult {
+    fn from(response: proto::ExecuteResponse) -> Self {
+        Self {
+            status: response.status().into(),
+            stdout: response.stdout,
+            stderr: response.stderr,
+            exit_code: response.exit_code,
+            execution_time_ms: response.execution_time_ms,
+            memory_used_kb: response.memory_used_kb,
+            cpu_user_ms: response.cpu_user_ms,
+            cpu_system_ms: response.cpu_system_ms,
+            voluntary_context_switches: response.voluntary_context_switches,
+            involuntary_context_switches: response.involuntary_context_switches,
+            image_digest: response.image_digest,
+            protections: response.protections,
+            output_truncated: response.output_truncated,
+            compile: response.compile.map(Into::into),
+        }
+    }
+}
+
+impl From<CompileResult> for proto::CompileResult {
+    fn from(compile: CompileResult) -> Self {
+        Self {
+            status: proto::ExecutionStatus::from(compile.status).into(),
+            stdout: compile.stdout,
+            stderr: compile.stderr,
+            exit_code: compile.exit_code,
+            execution_time_ms: compile.execution_time_ms,
+            memory_used_kb: compile.memory_used_kb,
+            output_truncated: compile.output_truncated,
+        }
+    }
+}
+
+impl From<proto::CompileResult> for CompileResult {
+    fn from(compile: proto::CompileResult) -> Self {
+        Self {
+            status: compile.status().into(),
+            stdout: compile.stdout,
+            stderr: compile.stderr,
+            exit_code: compile.exit_code,
+            execution_time_ms: compile.execution_time_ms,
+            memory_used_kb: compile.memory_used_kb,
+            output_truncated: compile.output_truncated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> ExecutionResult {
+        ExecutionResult {
+            status: ExecutionStatus::CompileError,
+            stdout: "out".to_string(),
+            stderr: "main.c:1: error\n".to_string(),
+            exit_code: Some(1),
+            execution_time_ms: 12.5,
+            memory_used_kb: u64::MAX,
+            cpu_user_ms: 8.0,
+            cpu_system_ms: 2.0,
+            voluntary_context_switches: u64::MAX,
+            involuntary_context_switches: 1,
+            image_digest: "native:/usr/bin/gcc".to_string(),
+            protections: vec!["rlimits".to_string(), "seccomp".to_string()],
+            output_truncated: true,
+            compile: Some(CompileResult {
+                status: ExecutionStatus::Failed,
+                stdout: String::new(),
+                stderr: "main.c:1: error\n".to_string(),
+                exit_code: Some(1),
+                execution_time_ms: 10.0,
+                memory_used_kb: 4096,
+                output_truncated: false,
+            }),
+        }
+    }
+
+    #[test]
+    fn responses_carry_results_without_loss() {
+        let response = proto::ExecuteResponse::from(result());
+        assert_eq!(response.status(), proto::ExecutionStatus::CompileError);
+        assert_eq!(response.memory_used_kb, u64::MAX);
+        assert_eq!(ExecutionResult::from(response), result());
+
+        let timed_out = ExecutionResult { status: ExecutionStatus::Timeout, exit_code: None, ..result() };
+        assert_eq!(ExecutionResult::from(proto::ExecuteResponse::from(timed_out.clone())), timed_out);
+    }
+
+    #[test]
+    fn statuses_keep_their_api_names() {
+        for value in 0..=8 {
+            let status = proto::ExecutionStatus::try_from(value).unwrap();
+            let json = serde_json::to_string(&ExecutionStatus::from(status)).unwrap();
+            assert_eq!(json, format!("\"{}\"", status.as_str_name()));
+            assert_eq!(proto::ExecutionStatus::from(ExecutionStatus::from(status)), status);
+        }
+    }
+
+    #[test]
+    fn results_round_trip_through_json() {
+        let json = serde_json::to_value(result()).unwrap();
+        assert_eq!(json["status"], "COMPILE_ERROR");
+        assert_eq!(json["compile"]["exit_code"], 1);
+        assert_eq!(serde_json::from_value::<ExecutionResult>(json).unwrap(), result());
+    }
+}
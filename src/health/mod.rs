@@ -0,0 +1,7 @@
+mod service;
+
+pub mod proto {
+    tonic::include_proto!("grpc.health.v1");
+}
+
+pub use service::{HealthReporter, HealthService};
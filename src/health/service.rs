@@ -0,0 +1,89 @@
+use std::pin::Pin;
+use futures::Stream;
+use tokio::sync::watch;
+use tonic::{Request, Response, Status};
+
+use super::proto::health_check_response::ServingStatus;
+use super::proto::health_server::Health;
+use super::proto::{HealthCheckRequest, HealthCheckResponse};
+
+/// Fully qualified name of the executor service, as load balancers ask for it
+const EXECUTOR_SERVICE_NAME: &str = "code_executor.CodeExecutor";
+
+/// Shared serving status, flipped to NOT_SERVING when the server starts shutting down
+#[derive(Debug, Clone)]
+pub struct HealthReporter {
+    status: watch::Sender<ServingStatus>,
+}
+
+impl HealthReporter {
+    pub fn new() -> Self {
+        let (status, _) = watch::channel(ServingStatus::Serving);
+        Self { status }
+    }
+
+    pub fn set_not_serving(&self) {
+        self.status.send_replace(ServingStatus::NotServing);
+    }
+
+    pub fn is_serving(&self) -> bool {
+        *self.status.borrow() == ServingStatus::Serving
+    }
+
+    /// `grpc.health.v1.Health` implementation reporting this status
+    pub fn service(&self) -> HealthService {
+        HealthService { reporter: self.clone() }
+    }
+}
+
+impl Default for HealthReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Answers health checks for the whole server (empty service name) and for the executor service
+#[derive(Debug)]
+pub struct HealthService {
+    reporter: HealthReporter,
+}
+
+// 핸들러가 돌려주는 Status를 그대로 돌려줌
+#[allow(clippy::result_large_err)]
+fn ensure_known_service(request: Request<HealthCheckRequest>) -> Result<(), Status> {
+    match request.into_inner().service.as_str() {
+        "" | EXECUTOR_SERVICE_NAME => Ok(()),
+        service => Err(Status::not_found(format!("Unknown service: {}", service))),
+    }
+}
+
+#[tonic::async_trait]
+impl Health for HealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        ensure_known_service(request)?;
+        let status = *self.reporter.status.borrow();
+        Ok(Response::new(HealthCheckResponse { status: status as i32 }))
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        ensure_known_service(request)?;
+
+        // 현재 상태를 먼저 보내고, 이후에는 상태가 바뀔 때마다 전송
+        let mut receiver = self.reporter.status.subscribe();
+        receiver.mark_changed();
+        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.changed().await.ok()?;
+            let status = *receiver.borrow_and_update();
+            Some((Ok(HealthCheckResponse { status: status as i32 }), receiver))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,413 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use rusqlite::{params, Connection, ToSql};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::env_override;
+use crate::proto::{ExecuteRequest, ExecuteResponse, ExecutionRecord, ExecutionStatus, ListExecutionsRequest};
+
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 4096;
+const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS executions (
+        execution_id       TEXT PRIMARY KEY,
+        tenant_id          TEXT NOT NULL,
+        language           TEXT NOT NULL,
+        version            TEXT NOT NULL,
+        code_sha256        TEXT NOT NULL,
+        timeout_seconds    INTEGER NOT NULL,
+        memory_limit_mb    INTEGER NOT NULL,
+        status             TEXT NOT NULL,
+        execution_time_ms  REAL NOT NULL,
+        memory_used_kb     INTEGER NOT NULL,
+        stdout             TEXT NOT NULL,
+        stderr             TEXT NOT NULL,
+        error_message      TEXT NOT NULL,
+        cached             INTEGER NOT NULL,
+        created_at_unix_ms INTEGER NOT NULL,
+        image_digest       TEXT NOT NULL DEFAULT '',
+        cpu_user_ms        REAL NOT NULL DEFAULT 0,
+        cpu_system_ms      REAL NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS executions_tenant_created ON executions (tenant_id, created_at_unix_ms);
+    CREATE INDEX IF NOT EXISTS executions_created ON executions (created_at_unix_ms);
+";
+
+/// Columns added after the first release and how to add them to older databases
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("image_digest", "TEXT NOT NULL DEFAULT ''"),
+    ("cpu_user_ms", "REAL NOT NULL DEFAULT 0"),
+    ("cpu_system_ms", "REAL NOT NULL DEFAULT 0"),
+];
+
+/// Settings for the optional execution history database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// SQLite database file; history is disabled when unset
+    pub path: Option<PathBuf>,
+    /// Executions older than this many days are pruned
+    pub retention_days: u64,
+    /// Stored stdout/stderr are truncated to this many bytes each
+    pub max_output_bytes: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            retention_days: DEFAULT_RETENTION_DAYS,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Apply `CODE_EXECUTOR_HISTORY_DB`, `CODE_EXECUTOR_HISTORY_RETENTION_DAYS` and
+    /// `CODE_EXECUTOR_HISTORY_MAX_OUTPUT_BYTES` on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_HISTORY_DB") {
+            self.path = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        }
+        env_override("CODE_EXECUTOR_HISTORY_RETENTION_DAYS", &mut self.retention_days)?;
+        env_override("CODE_EXECUTOR_HISTORY_MAX_OUTPUT_BYTES", &mut self.max_output_bytes)?;
+        Ok(())
+    }
+
+    fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_days * 24 * 60 * 60)
+    }
+}
+
+/// Execution history persisted to a local SQLite database.
+///
+/// All methods block on SQLite; call them from `spawn_blocking`.
+#[derive(Debug)]
+pub struct HistoryStore {
+    config: HistoryConfig,
+    connection: Mutex<Connection>,
+}
+
+impl HistoryStore {
+    /// Open the configured database, or return `None` when history is disabled
+    pub fn open(config: HistoryConfig) -> Result<Option<Self>> {
+        let Some(path) = &config.path else {
+            return Ok(None);
+        };
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA journal_mode = WAL;")?;
+        connection.execute_batch(SCHEMA)?;
+        add_missing_columns(&connection)?;
+
+        Ok(Some(Self {
+            config,
+            connection: Mutex::new(connection),
+        }))
+    }
+
+    /// Store the outcome of an execution; outputs are truncated to `max_output_bytes`
+    pub fn record(&self, request: &ExecuteRequest, response: &ExecuteResponse, submitted_at: SystemTime) -> Result<()> {
+        let code_sha256 = hex::encode(Sha256::digest(request.code.as_bytes()));
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT OR REPLACE INTO executions (
+                execution_id, tenant_id, language, version, code_sha256, timeout_seconds, memory_limit_mb,
+                status, execution_time_ms, memory_used_kb, stdout, stderr, error_message, cached, created_at_unix_ms,
+                image_digest, cpu_user_ms, cpu_system_ms
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                response.execution_id,
+                request.tenant_id,
+                request.language,
+                request.version,
+                code_sha256,
+                request.timeout_seconds,
+                request.memory_limit_mb,
+                response.status().as_str_name(),
+                response.execution_time_ms,
+                response.memory_used_kb,
+                truncate(&response.stdout, self.config.max_output_bytes),
+                truncate(&response.stderr, self.config.max_output_bytes),
+                response.error_message,
+                response.cached,
+                unix_ms(submitted_at),
+                response.image_digest,
+                response.cpu_user_ms,
+                response.cpu_system_ms,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent executions matching the filter, newest first
+    pub fn list(&self, filter: &ListExecutionsRequest) -> Result<Vec<ExecutionRecord>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if !filter.tenant_id.is_empty() {
+            conditions.push("tenant_id = ?");
+            values.push(Box::new(filter.tenant_id.clone()));
+        }
+        if filter.status.is_some() {
+            conditions.push("status = ?");
+            values.push(Box::new(filter.status().as_str_name()));
+        }
+        if !filter.language.is_empty() {
+            conditions.push("language = ?");
+            values.push(Box::new(filter.language.clone()));
+        }
+        if filter.since_unix_ms > 0 {
+            conditions.push("created_at_unix_ms >= ?");
+            values.push(Box::new(filter.since_unix_ms));
+        }
+        if filter.until_unix_ms > 0 {
+            conditions.push("created_at_unix_ms < ?");
+            values.push(Box::new(filter.until_unix_ms));
+        }
+        let limit = match i64::from(filter.limit) {
+            limit if limit <= 0 => DEFAULT_LIST_LIMIT,
+            limit => limit.min(MAX_LIST_LIMIT),
+        };
+        values.push(Box::new(limit));
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT execution_id, tenant_id, language, version, code_sha256, timeout_seconds, memory_limit_mb,
+                    status, execution_time_ms, memory_used_kb, stdout, stderr, error_message, cached, created_at_unix_ms,
+                    image_digest, cpu_user_ms, cpu_system_ms
+             FROM executions {} ORDER BY created_at_unix_ms DESC LIMIT ?",
+            where_clause
+        );
+
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            let status: String = row.get(7)?;
+            Ok(ExecutionRecord {
+                execution_id: row.get(0)?,
+                tenant_id: row.get(1)?,
+                language: row.get(2)?,
+                version: row.get(3)?,
+                code_sha256: row.get(4)?,
+                timeout_seconds: row.get(5)?,
+                memory_limit_mb: row.get(6)?,
+                status: ExecutionStatus::from_str_name(&status).unwrap_or(ExecutionStatus::Failed) as i32,
+                execution_time_ms: row.get(8)?,
+                memory_used_kb: row.get(9)?,
+                stdout: row.get(10)?,
+                stderr: row.get(11)?,
+                error_message: row.get(12)?,
+                cached: row.get(13)?,
+                created_at_unix_ms: row.get(14)?,
+                image_digest: row.get(15)?,
+                cpu_user_ms: row.get(16)?,
+                cpu_system_ms: row.get(17)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Delete executions older than the retention period, returning how many were removed
+    pub fn prune(&self) -> Result<usize> {
+        let cutoff = SystemTime::now().checked_sub(self.config.retention()).unwrap_or(UNIX_EPOCH);
+        let connection = self.connection.lock().unwrap();
+        let removed = connection.execute(
+            "DELETE FROM executions WHERE created_at_unix_ms < ?1",
+            params![unix_ms(cutoff)],
+        )?;
+        Ok(removed)
+    }
+
+    /// Prune expired executions now and then every hour
+    pub fn spawn_pruner(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let pruning = store.clone();
+                match tokio::task::spawn_blocking(move || pruning.prune()).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(removed)) => tracing::info!("Pruned {} executions from history", removed),
+                    Ok(Err(e)) => tracing::error!("Failed to prune execution history: {}", e),
+                    Err(e) => tracing::error!("History pruning task failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Bring databases created by older versions up to the current schema
+fn add_missing_columns(connection: &Connection) -> Result<()> {
+    let columns = connection
+        .prepare("SELECT name FROM pragma_table_info('executions')")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (name, definition) in ADDED_COLUMNS {
+        if !columns.iter().any(|column| column == name) {
+            connection.execute_batch(&format!("ALTER TABLE executions ADD COLUMN {} {}", name, definition))?;
+        }
+    }
+    Ok(())
+}
+
+fn unix_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as i64).unwrap_or(0)
+}
+
+/// Cut `text` to at most `max_bytes` without splitting a UTF-8 character
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_databases_without_added_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        // 첫 버전의 테이블: 추가된 열을 모두 뺀 스키마
+        let (columns, _) = SCHEMA.split_once(",\n        image_digest").unwrap();
+        Connection::open(&path).unwrap()
+            .execute_batch(&format!("{}\n    );", columns))
+            .unwrap();
+
+        let store = HistoryStore::open(HistoryConfig { path: Some(path), ..Default::default() }).unwrap().unwrap();
+        let response = ExecuteResponse {
+            execution_id: "exec-1".to_string(),
+            image_digest: "sha256:abc".to_string(),
+            cpu_user_ms: 12.5,
+            ..Default::default()
+        };
+        store.record(&ExecuteRequest::default(), &response, SystemTime::now()).unwrap();
+
+        let executions = store.list(&ListExecutionsRequest::default()).unwrap();
+        assert_eq!(executions[0].image_digest, "sha256:abc");
+        assert_eq!(executions[0].cpu_user_ms, 12.5);
+    }
+
+    fn memory_store(config: HistoryConfig) -> HistoryStore {
+        HistoryStore::open(HistoryConfig { path: Some(PathBuf::from(":memory:")), ..config }).unwrap().unwrap()
+    }
+
+    fn record(store: &HistoryStore, id: &str, tenant: &str, language: &str, status: ExecutionStatus, at_unix_ms: u64) {
+        let request = ExecuteRequest {
+            tenant_id: tenant.to_string(),
+            language: language.to_string(),
+            ..Default::default()
+        };
+        let response = ExecuteResponse {
+            execution_id: id.to_string(),
+            status: status as i32,
+            ..Default::default()
+        };
+        store.record(&request, &response, UNIX_EPOCH + Duration::from_millis(at_unix_ms)).unwrap();
+    }
+
+    fn ids(store: &HistoryStore, filter: ListExecutionsRequest) -> Vec<String> {
+        store.list(&filter).unwrap().into_iter().map(|record| record.execution_id).collect()
+    }
+
+    #[test]
+    fn lists_filter_by_tenant_status_language_and_time() {
+        let store = memory_store(HistoryConfig::default());
+        record(&store, "a-py-ok", "a", "python", ExecutionStatus::Completed, 1000);
+        record(&store, "a-cpp-fail", "a", "cpp", ExecutionStatus::Failed, 2000);
+        record(&store, "b-py-ok", "b", "python", ExecutionStatus::Completed, 3000);
+        record(&store, "a-py-timeout", "a", "python", ExecutionStatus::Timeout, 4000);
+
+        assert_eq!(ids(&store, ListExecutionsRequest::default()), ["a-py-timeout", "b-py-ok", "a-cpp-fail", "a-py-ok"]);
+        assert_eq!(
+            ids(&store, ListExecutionsRequest { tenant_id: "a".to_string(), ..Default::default() }),
+            ["a-py-timeout", "a-cpp-fail", "a-py-ok"]
+        );
+        assert_eq!(
+            ids(&store, ListExecutionsRequest { status: Some(ExecutionStatus::Completed as i32), ..Default::default() }),
+            ["b-py-ok", "a-py-ok"]
+        );
+        assert_eq!(
+            ids(&store, ListExecutionsRequest { language: "cpp".to_string(), ..Default::default() }),
+            ["a-cpp-fail"]
+        );
+        // since는 포함, until은 제외
+        assert_eq!(
+            ids(&store, ListExecutionsRequest { since_unix_ms: 2000, until_unix_ms: 4000, ..Default::default() }),
+            ["b-py-ok", "a-cpp-fail"]
+        );
+        assert_eq!(
+            ids(&store, ListExecutionsRequest {
+                tenant_id: "a".to_string(),
+                language: "python".to_string(),
+                limit: 1,
+                ..Default::default()
+            }),
+            ["a-py-timeout"]
+        );
+    }
+
+    #[test]
+    fn pruning_removes_executions_past_the_retention() {
+        let store = memory_store(HistoryConfig { retention_days: 1, ..Default::default() });
+        let now = unix_ms(SystemTime::now()) as u64;
+        let day_ms = 24 * 60 * 60 * 1000;
+        record(&store, "old", "a", "python", ExecutionStatus::Completed, now - 2 * day_ms);
+        record(&store, "recent", "a", "python", ExecutionStatus::Completed, now - day_ms / 2);
+
+        assert_eq!(store.prune().unwrap(), 1);
+        assert_eq!(ids(&store, ListExecutionsRequest::default()), ["recent"]);
+        assert_eq!(store.prune().unwrap(), 0);
+    }
+
+    #[test]
+    fn outputs_are_stored_up_to_max_output_bytes() {
+        let store = memory_store(HistoryConfig { max_output_bytes: 8, ..Default::default() });
+        let response = ExecuteResponse {
+            execution_id: "exec-1".to_string(),
+            stdout: "0123456789".to_string(),
+            stderr: "에러 발생".to_string(),
+            error_message: "not truncated at all".to_string(),
+            ..Default::default()
+        };
+        store.record(&ExecuteRequest::default(), &response, SystemTime::now()).unwrap();
+
+        let record = store.list(&ListExecutionsRequest::default()).unwrap().remove(0);
+        assert_eq!(record.stdout, "01234567");
+        // "에러 " 7바이트 다음의 "발"은 8바이트 경계에 걸치므로 통째로 빠짐
+        assert_eq!(record.stderr, "에러 ");
+        assert_eq!(record.error_message, "not truncated at all");
+    }
+
+    #[test]
+    fn truncation_never_splits_a_character() {
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("abc", 10), "abc");
+        assert_eq!(truncate("abc", 0), "");
+        // 한글은 3바이트, 이모지는 4바이트
+        assert_eq!(truncate("가나다", 6), "가나");
+        assert_eq!(truncate("가나다", 5), "가");
+        assert_eq!(truncate("가나다", 2), "");
+        assert_eq!(truncate("a🦀b", 4), "a");
+        assert_eq!(truncate("a🦀b", 5), "a🦀");
+    }
+}
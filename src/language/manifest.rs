@@ -0,0 +1,277 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+/// Name of the manifest file inside each `<language>:<version>` directory
+pub const MANIFEST_FILE: &str = "language.toml";
+
+/// Placeholders that may appear in `compile` and `run` arguments
+const SOURCE_PLACEHOLDER: &str = "{source}";
+const WORKDIR_PLACEHOLDER: &str = "{workdir}";
+
+/// Defaults used when a request leaves a limit at 0
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageLimits {
+    pub timeout_seconds: Option<u32>,
+    pub memory_limit_mb: Option<u32>,
+}
+
+/// Limits of the compile step, separate from the run's; requests cannot change them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompileLimits {
+    pub timeout_seconds: u32,
+    pub memory_limit_mb: u32,
+}
+
+impl Default for CompileLimits {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 30,
+            memory_limit_mb: 1024,
+        }
+    }
+}
+
+/// Changes to the curated syscall filter for a runtime that needs them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageSeccomp {
+    /// Normally denied syscalls this runtime may use
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Further syscalls to deny, e.g. `socket` for a language that never needs one
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Everything needed to run one language version, loaded from `language.toml`.
+///
+/// `compile` and `run` are argument vectors; `{source}` is replaced with the path of the
+/// source file and `{workdir}` with the directory it lives in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageManifest {
+    pub language: String,
+    pub version: String,
+    /// Human readable name for `ListLanguages`, e.g. "Python 3.12"
+    #[serde(default)]
+    pub display_name: String,
+    /// Other names clients may use for the language, e.g. `py` and `python3`
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Version used when a request gives no version; the newest one otherwise
+    #[serde(default, rename = "default")]
+    pub is_default: bool,
+    /// Docker image with the toolchain installed
+    pub image: String,
+    /// File name the submitted code is written to, e.g. `Main.java`
+    pub source_file: String,
+    #[serde(default)]
+    pub compile: Option<Vec<String>>,
+    pub run: Vec<String>,
+    #[serde(default)]
+    pub limits: LanguageLimits,
+    /// Used by the native backend, which compiles in a step of its own
+    #[serde(default)]
+    pub compile_limits: CompileLimits,
+    /// Scales the requested timeout for slow runtimes (e.g. 2.0 for the JVM)
+    #[serde(default = "default_time_multiplier")]
+    pub time_multiplier: f64,
+    #[serde(default)]
+    pub seccomp: LanguageSeccomp,
+}
+
+fn default_time_multiplier() -> f64 {
+    1.0
+}
+
+/// `compile` and `run` with the placeholders filled in
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageCommands {
+    pub compile: Option<Vec<String>>,
+    pub run: Vec<String>,
+}
+
+impl LanguageManifest {
+    /// `<language>:<version>`, the key used for lookups and the manifest directory name
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.language, self.version)
+    }
+
+    pub fn display_name(&self) -> String {
+        if self.display_name.is_empty() {
+            format!("{} {}", self.language, self.version)
+        } else {
+            self.display_name.clone()
+        }
+    }
+
+    pub fn is_compiled(&self) -> bool {
+        self.compile.is_some()
+    }
+
+    /// Programs that must be installed to run the language: the compiler of a compiled language
+    /// and the program `run` starts, unless that is what the compiler built in `{workdir}`
+    pub fn toolchain_programs(&self) -> Vec<&str> {
+        self.compile.iter().filter_map(|compile| compile.first())
+            .chain(self.run.first().filter(|program| !program.contains(WORKDIR_PLACEHOLDER)))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Wall clock limit after applying the time multiplier, never below one second
+    pub fn scaled_timeout(&self, timeout_seconds: u32) -> u32 {
+        ((timeout_seconds as f64 * self.time_multiplier).ceil() as u32).max(1)
+    }
+
+    /// Commands for a source file written to `workdir/source_file`
+    pub fn commands(&self, workdir: &Path) -> LanguageCommands {
+        let source = workdir.join(&self.source_file);
+        let substitute = |args: &Vec<String>| -> Vec<String> {
+            args.iter()
+                .map(|arg| arg
+                    .replace(SOURCE_PLACEHOLDER, &source.to_string_lossy())
+                    .replace(WORKDIR_PLACEHOLDER, &workdir.to_string_lossy()))
+                .collect()
+        };
+        LanguageCommands {
+            compile: self.compile.as_ref().map(substitute),
+            run: substitute(&self.run),
+        }
+    }
+
+    /// Describe every invalid field
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (field, value) in [("language", &self.language), ("version", &self.version), ("image", &self.image)] {
+            if value.trim().is_empty() {
+                errors.push(format!("{} must not be empty", field));
+            }
+        }
+        if self.language.contains(':') || self.version.contains(':') {
+            errors.push("language and version must not contain ':'".to_string());
+        }
+        for alias in &self.aliases {
+            if alias.trim().is_empty() || alias.contains(':') || *alias != alias.to_lowercase() {
+                errors.push(format!("alias '{}' must be a non-empty lowercase name without ':'", alias));
+            }
+        }
+        if self.source_file.is_empty() || self.source_file.contains('/') || self.source_file.starts_with('.') {
+            errors.push(format!("source_file '{}' must be a plain file name", self.source_file));
+        }
+        if self.run.is_empty() {
+            errors.push("run must not be empty".to_string());
+        }
+        if self.compile.as_ref().is_some_and(|compile| compile.is_empty()) {
+            errors.push("compile must not be empty when present".to_string());
+        }
+        if !(self.time_multiplier.is_finite() && self.time_multiplier > 0.0) {
+            errors.push(format!("time_multiplier ({}) must be greater than 0", self.time_multiplier));
+        }
+        if self.limits.timeout_seconds == Some(0) {
+            errors.push("limits.timeout_seconds must be greater than 0".to_string());
+        }
+        if self.limits.memory_limit_mb == Some(0) {
+            errors.push("limits.memory_limit_mb must be greater than 0".to_string());
+        }
+        if self.compile_limits.timeout_seconds == 0 || self.compile_limits.memory_limit_mb == 0 {
+            errors.push("compile_limits must be greater than 0".to_string());
+        }
+        for syscall in self.seccomp.allow.iter().chain(&self.seccomp.deny) {
+            if !crate::security::is_known_syscall(syscall) {
+                errors.push(format!("seccomp: '{}' is not a syscall the filter can allow or deny", syscall));
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn java() -> LanguageManifest {
+        toml::from_str(r#"
+            language = "java"
+            version = "15"
+            image = "code-executor-java-15"
+            source_file = "Main.java"
+            compile = ["javac", "-d", "{workdir}/classes", "{source}"]
+            run = ["java", "-cp", "{workdir}/classes", "Main"]
+            time_multiplier = 2.0
+
+            [limits]
+            memory_limit_mb = 512
+        "#).unwrap()
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let commands = java().commands(Path::new("/tmp/exec-1"));
+        assert_eq!(commands.compile.unwrap(), vec!["javac", "-d", "/tmp/exec-1/classes", "/tmp/exec-1/Main.java"]);
+        assert_eq!(commands.run, vec!["java", "-cp", "/tmp/exec-1/classes", "Main"]);
+    }
+
+    #[test]
+    fn toolchains_leave_out_what_the_compiler_built() {
+        assert_eq!(java().toolchain_programs(), ["javac", "java"]);
+        let mut cpp = java();
+        cpp.compile = Some(vec!["g++".into(), "-o".into(), "{workdir}/main".into(), "{source}".into()]);
+        cpp.run = vec!["{workdir}/main".into()];
+        assert_eq!(cpp.toolchain_programs(), ["g++"]);
+    }
+
+    #[test]
+    fn optional_fields_take_defaults() {
+        let manifest: LanguageManifest = toml::from_str(r#"
+            language = "python"
+            version = "3.12"
+            image = "code-executor-python-3.12"
+            source_file = "main.py"
+            run = ["python3", "{source}"]
+        "#).unwrap();
+
+        assert!(!manifest.is_compiled());
+        assert!(manifest.aliases.is_empty() && !manifest.is_default);
+        assert_eq!(manifest.time_multiplier, 1.0);
+        assert_eq!((manifest.compile_limits.timeout_seconds, manifest.compile_limits.memory_limit_mb), (30, 1024));
+        assert_eq!(manifest.display_name(), "python 3.12");
+        assert!(manifest.validate().is_empty());
+    }
+
+    #[test]
+    fn scales_timeouts_up() {
+        let mut manifest = java();
+        assert_eq!(manifest.scaled_timeout(5), 10);
+        manifest.time_multiplier = 1.3;
+        assert_eq!(manifest.scaled_timeout(1), 2);
+        manifest.time_multiplier = 0.1;
+        assert_eq!(manifest.scaled_timeout(1), 1);
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let mut manifest = java();
+        manifest.source_file = "../Main.java".to_string();
+        manifest.run.clear();
+        manifest.time_multiplier = 0.0;
+        manifest.limits.timeout_seconds = Some(0);
+        manifest.compile_limits.memory_limit_mb = 0;
+
+        assert_eq!(manifest.validate().len(), 5);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let parsed = toml::from_str::<LanguageManifest>(r#"
+            language = "python"
+            version = "3.12"
+            image = "code-executor-python-3.12"
+            source_file = "main.py"
+            run = ["python3", "{source}"]
+            entrypoint = "/run_user_code.sh"
+        "#);
+        assert!(parsed.is_err());
+    }
+}
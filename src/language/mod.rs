@@ -0,0 +1,5 @@
+mod manifest;
+mod registry;
+
+pub use manifest::{CompileLimits, LanguageCommands, LanguageLimits, LanguageManifest, LanguageSeccomp, MANIFEST_FILE};
+pub use registry::LanguageRegistry;
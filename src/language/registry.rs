@@ -0,0 +1,314 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{Context, Result, anyhow};
+
+use super::manifest::{LanguageManifest, MANIFEST_FILE};
+use crate::config::LimitsConfig;
+
+/// Versions a request may ask for besides exact ones and prefixes
+const DEFAULT_VERSION: &str = "default";
+const LATEST_VERSION: &str = "latest";
+
+/// All language manifests known to the server, keyed by `<language>:<version>`
+#[derive(Debug, Clone, Default)]
+pub struct LanguageRegistry {
+    languages: BTreeMap<String, Arc<LanguageManifest>>,
+    /// Canonical language name for every name and alias, all lowercase
+    names: BTreeMap<String, String>,
+    /// Directory the manifests were loaded from, holding each language's Dockerfile
+    dir: Option<PathBuf>,
+}
+
+impl LanguageRegistry {
+    /// Load `<dir>/<language>:<version>/language.toml` for every subdirectory that has one,
+    /// reporting all invalid manifests at once
+    pub fn load(dir: &Path) -> Result<Self> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read languages directory {}", dir.display()))?;
+
+        let mut manifests = Vec::new();
+        let mut errors = Vec::new();
+        let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+        paths.sort();
+        for path in paths.into_iter().map(|path| path.join(MANIFEST_FILE)).filter(|path| path.is_file()) {
+            let manifest = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| toml::from_str::<LanguageManifest>(&contents).map_err(anyhow::Error::from));
+            match manifest {
+                Ok(manifest) => {
+                    let directory = path.parent().and_then(Path::file_name).map(|name| name.to_string_lossy().into_owned());
+                    if directory.as_deref() != Some(manifest.key().as_str()) {
+                        errors.push(format!("{}: directory must be named '{}'", path.display(), manifest.key()));
+                    }
+                    errors.extend(manifest.validate().into_iter().map(|error| format!("{}: {}", path.display(), error)));
+                    manifests.push(manifest);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(anyhow!("Invalid language manifests:\n  - {}", errors.join("\n  - ")));
+        }
+        if manifests.is_empty() {
+            return Err(anyhow!("No {} found under {}", MANIFEST_FILE, dir.display()));
+        }
+        let mut registry = Self::from_manifests(manifests)?;
+        registry.dir = Some(dir.to_path_buf());
+        Ok(registry)
+    }
+
+    /// Build a registry from already parsed manifests, rejecting duplicates, aliases that
+    /// point at two languages and languages with more than one default version
+    pub fn from_manifests(manifests: impl IntoIterator<Item = LanguageManifest>) -> Result<Self> {
+        let mut languages = BTreeMap::new();
+        for manifest in manifests {
+            let key = manifest.key();
+            if languages.insert(key.clone(), Arc::new(manifest)).is_some() {
+                return Err(anyhow!("Language {} is declared more than once", key));
+            }
+        }
+
+        let mut names = BTreeMap::new();
+        let mut defaults = BTreeMap::new();
+        for manifest in languages.values() {
+            let canonical = manifest.language.to_lowercase();
+            for name in std::iter::once(&manifest.language).chain(&manifest.aliases) {
+                let previous = names.insert(name.to_lowercase(), canonical.clone());
+                if previous.is_some_and(|previous| previous != canonical) {
+                    return Err(anyhow!("Name '{}' is used by more than one language", name));
+                }
+            }
+            if manifest.is_default {
+                if let Some(other) = defaults.insert(canonical, manifest.version.clone()) {
+                    return Err(anyhow!("{} declares both {} and {} as the default version",
+                                       manifest.language, other, manifest.version));
+                }
+            }
+        }
+        Ok(Self { languages, names, dir: None })
+    }
+
+    /// Find the manifest a request means. `language` may be the canonical name or an alias in
+    /// any case; `version` may be exact, empty or `default` for the default version, `latest`
+    /// for the newest one, or a prefix such as `3` for the newest `3.x`.
+    pub fn resolve(&self, language: &str, version: &str) -> Result<Arc<LanguageManifest>> {
+        let requested = language.trim().to_lowercase();
+        let canonical = self.names.get(&requested).ok_or_else(|| {
+            let known: BTreeSet<_> = self.languages.values().map(|manifest| manifest.language.as_str()).collect();
+            anyhow!("Unsupported language: {} (available: {})",
+                    language, known.into_iter().collect::<Vec<_>>().join(", "))
+        })?;
+
+        let mut versions: Vec<_> = self.languages.values()
+            .filter(|manifest| manifest.language.to_lowercase() == *canonical)
+            .collect();
+        versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+
+        let version = version.trim();
+        let found = match version.to_lowercase().as_str() {
+            "" | DEFAULT_VERSION => versions.iter().find(|manifest| manifest.is_default).or(versions.last()),
+            LATEST_VERSION => versions.last(),
+            _ => versions.iter().find(|manifest| manifest.version == version).or_else(|| {
+                let prefix = format!("{}.", version);
+                versions.iter().rev().find(|manifest| manifest.version.starts_with(&prefix))
+            }),
+        };
+        found.map(|manifest| Arc::clone(manifest)).ok_or_else(|| {
+            let available: Vec<_> = versions.iter().map(|manifest| manifest.version.as_str()).collect();
+            anyhow!("Unsupported version of {}: {} (available: {})", canonical, version, available.join(", "))
+        })
+    }
+
+    pub fn get(&self, language: &str, version: &str) -> Result<Arc<LanguageManifest>> {
+        self.languages
+            .get(&format!("{}:{}", language, version))
+            .cloned()
+            .ok_or_else(|| anyhow!("Unsupported language or version: {} {}", language, version))
+    }
+
+    /// Manifests ordered by language and version
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<LanguageManifest>> {
+        self.languages.values()
+    }
+
+    /// Distinct images used by the manifests
+    pub fn images(&self) -> BTreeSet<String> {
+        self.languages.values().map(|manifest| manifest.image.clone()).collect()
+    }
+
+    /// Directory the manifest's image can be built from, if it has a `Dockerfile`
+    pub fn build_context(&self, manifest: &LanguageManifest) -> Option<PathBuf> {
+        let context = self.dir.as_ref()?.join(manifest.key());
+        context.join("Dockerfile").is_file().then_some(context)
+    }
+
+    /// Describe manifest default limits that exceed what the server allows
+    pub fn validate_against(&self, limits: &LimitsConfig) -> Vec<String> {
+        let mut errors = Vec::new();
+        for manifest in self.iter() {
+            if let Some(timeout) = manifest.limits.timeout_seconds.filter(|timeout| *timeout > limits.max_timeout_seconds) {
+                errors.push(format!("{}: limits.timeout_seconds ({}) exceeds max_timeout_seconds ({})",
+                                    manifest.key(), timeout, limits.max_timeout_seconds));
+            }
+            if let Some(memory) = manifest.limits.memory_limit_mb.filter(|memory| *memory > limits.max_memory_limit_mb) {
+                errors.push(format!("{}: limits.memory_limit_mb ({}) exceeds max_memory_limit_mb ({})",
+                                    manifest.key(), memory, limits.max_memory_limit_mb));
+            }
+        }
+        errors
+    }
+}
+
+/// Compare dotted versions component by component, numerically where both sides are numbers,
+/// so that `3.10` sorts after `3.9`
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split(['.', '-']);
+    let mut b_parts = b.split(['.', '-']);
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTHON: &str = r#"
+        language = "python"
+        version = "3.12"
+        image = "code-executor-python-3.12"
+        source_file = "main.py"
+        run = ["python3", "{source}"]
+    "#;
+
+    fn write_manifest(root: &Path, directory: &str, contents: &str) {
+        std::fs::create_dir_all(root.join(directory)).unwrap();
+        std::fs::write(root.join(directory).join(MANIFEST_FILE), contents).unwrap();
+    }
+
+    #[test]
+    fn loads_manifests_from_language_directories() {
+        let root = tempfile::tempdir().unwrap();
+        write_manifest(root.path(), "python:3.12", PYTHON);
+        std::fs::create_dir_all(root.path().join("scripts")).unwrap();
+
+        let registry = LanguageRegistry::load(root.path()).unwrap();
+        assert_eq!(registry.get("python", "3.12").unwrap().source_file, "main.py");
+        assert!(registry.get("python", "2.7").is_err());
+        assert_eq!(registry.images().into_iter().collect::<Vec<_>>(), vec!["code-executor-python-3.12"]);
+        assert_eq!(registry.build_context(&registry.get("python", "3.12").unwrap()), None);
+
+        std::fs::write(root.path().join("python:3.12").join("Dockerfile"), "FROM python:3.12-slim\n").unwrap();
+        assert_eq!(registry.build_context(&registry.get("python", "3.12").unwrap()), Some(root.path().join("python:3.12")));
+    }
+
+    #[test]
+    fn reports_all_broken_manifests() {
+        let root = tempfile::tempdir().unwrap();
+        write_manifest(root.path(), "python:3.11", PYTHON);
+        write_manifest(root.path(), "ruby:3.2", "language = \"ruby\"");
+
+        let error = LanguageRegistry::load(root.path()).unwrap_err().to_string();
+        assert!(error.contains("directory must be named 'python:3.12'"), "{}", error);
+        assert!(error.contains("ruby:3.2"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_an_empty_directory() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(LanguageRegistry::load(root.path()).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_languages() {
+        let manifest: LanguageManifest = toml::from_str(PYTHON).unwrap();
+        assert!(LanguageRegistry::from_manifests([manifest.clone(), manifest]).is_err());
+    }
+
+    fn python(version: &str, is_default: bool) -> LanguageManifest {
+        let mut manifest: LanguageManifest = toml::from_str(PYTHON).unwrap();
+        manifest.version = version.to_string();
+        manifest.aliases = vec!["py".to_string(), "python3".to_string()];
+        manifest.is_default = is_default;
+        manifest
+    }
+
+    fn resolved(registry: &LanguageRegistry, language: &str, version: &str) -> Option<String> {
+        registry.resolve(language, version).ok().map(|manifest| manifest.key())
+    }
+
+    #[test]
+    fn resolves_aliases_and_versions() {
+        let registry = LanguageRegistry::from_manifests([
+            python("3.9", false),
+            python("3.10", false),
+            python("3.12", true),
+            python("2.7", false),
+        ]).unwrap();
+
+        assert_eq!(resolved(&registry, "Python3", ""), Some("python:3.12".to_string()));
+        assert_eq!(resolved(&registry, "py", "default"), Some("python:3.12".to_string()));
+        assert_eq!(resolved(&registry, "python", "latest"), Some("python:3.12".to_string()));
+        assert_eq!(resolved(&registry, "python", "3.10"), Some("python:3.10".to_string()));
+        assert_eq!(resolved(&registry, "python", "2"), Some("python:2.7".to_string()));
+        assert_eq!(resolved(&registry, "python", "3.1"), None);
+        assert_eq!(resolved(&registry, "python", "4"), None);
+        assert_eq!(resolved(&registry, "perl", ""), None);
+    }
+
+    #[test]
+    fn newest_version_is_the_default_unless_declared() {
+        let registry = LanguageRegistry::from_manifests([python("3.9", false), python("3.10", false)]).unwrap();
+        assert_eq!(resolved(&registry, "python", ""), Some("python:3.10".to_string()));
+        assert_eq!(resolved(&registry, "python", "3"), Some("python:3.10".to_string()));
+    }
+
+    #[test]
+    fn rejects_ambiguous_aliases_and_defaults() {
+        let mut ruby: LanguageManifest = toml::from_str(PYTHON).unwrap();
+        ruby.language = "ruby".to_string();
+        ruby.aliases = vec!["py".to_string()];
+        assert!(LanguageRegistry::from_manifests([python("3.12", false), ruby]).is_err());
+
+        assert!(LanguageRegistry::from_manifests([python("3.11", true), python("3.12", true)]).is_err());
+    }
+
+    #[test]
+    fn orders_versions_numerically() {
+        assert_eq!(compare_versions("3.10", "3.9"), Ordering::Greater);
+        assert_eq!(compare_versions("3", "3.0"), Ordering::Less);
+        assert_eq!(compare_versions("23", "17"), Ordering::Greater);
+        assert_eq!(compare_versions("3.12.1", "3.12.1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn default_limits_must_fit_the_server_maximum() {
+        let mut manifest: LanguageManifest = toml::from_str(PYTHON).unwrap();
+        manifest.limits.timeout_seconds = Some(600);
+        let registry = LanguageRegistry::from_manifests([manifest]).unwrap();
+
+        assert_eq!(registry.validate_against(&LimitsConfig::default()).len(), 1);
+    }
+
+    #[test]
+    fn bundled_manifests_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("dockerfiles");
+        let registry = LanguageRegistry::load(&dir).unwrap();
+        assert!(registry.validate_against(&LimitsConfig::default()).is_empty());
+        assert_eq!(registry.iter().count(), 8);
+    }
+}
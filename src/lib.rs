@@ -1,10 +1,29 @@
+pub mod backend;
+pub mod cache;
+pub mod config;
 pub mod container;
+pub mod execution;
+pub mod health;
+pub mod history;
+pub mod language;
+pub mod scheduler;
 pub mod security;
 pub mod runner;
+pub mod tracker;
+pub mod webhook;
 pub mod proto {
     tonic::include_proto!("code_executor");
 }
 
-pub use container::{ContainerManager, ExecutionResult, ExecutionStatus};
-pub use security::{SecurityConfig, create_seccomp_profile};
-pub use runner::{Runner, ExecutionConfig};
\ No newline at end of file
+pub use backend::{BackendConfig, BackendKind, Backends, ExecutionBackend, NativeBackend, NativeConfig};
+pub use cache::{CacheConfig, ResultCache};
+pub use config::ServerConfig;
+pub use history::{HistoryConfig, HistoryStore};
+pub use language::{LanguageManifest, LanguageRegistry};
+pub use container::{ContainerManager, DockerConfig};
+pub use execution::{CompileResult, ExecutionResult, ExecutionStatus};
+pub use security::{Landlock, SeccompProfile, SyscallFilter};
+pub use runner::{Runner, ExecutionConfig};
+pub use scheduler::{Scheduler, SchedulerConfig};
+pub use tracker::ExecutionTracker;
+pub use webhook::{WebhookConfig, WebhookNotifier};
\ No newline at end of file
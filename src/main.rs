@@ -1,20 +1,260 @@
 use tonic::{transport::Server, Request, Response, Status};
 use std::error::Error;
-use tokio;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use clap::Parser;
+use tokio::signal::unix::{signal, SignalKind};
 
-mod container;
-mod security;
-mod proto {
-    tonic::include_proto!("code_executor");
+use code_executor::backend::{BackendKind, Backends, NativeBackend};
+use code_executor::cache::{CacheKey, ResultCache};
+use code_executor::config::{LimitsConfig, ServerConfig};
+use code_executor::proto::code_executor_server::{CodeExecutor, CodeExecutorServer};
+use code_executor::health::proto::health_server::HealthServer;
+use code_executor::health::HealthReporter;
+use code_executor::history::HistoryStore;
+use code_executor::language::{LanguageManifest, LanguageRegistry};
+use code_executor::proto::{
+    ExecuteRequest, ExecuteResponse, ExecutionStatus, ListExecutionsRequest, ListExecutionsResponse,
+    ListLanguagesRequest, ListLanguagesResponse, StatusRequest, StatusResponse,
+};
+use code_executor::container::ImageSource;
+use code_executor::container::manager::ContainerManager;
+use code_executor::proto;
+use code_executor::scheduler::{AdmissionError, Priority, Scheduler, Ticket};
+use code_executor::tracker::{Delivery, ExecutionTracker};
+use code_executor::webhook::WebhookNotifier;
+
+const LATENCY_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// gRPC code execution server
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// TOML configuration file
+    #[arg(long, env = "CODE_EXECUTOR_CONFIG")]
+    config: Option<PathBuf>,
+    /// Address to listen on, e.g. [::]:50051
+    #[arg(long)]
+    listen: Option<String>,
+    /// Directory of <language>:<version>/language.toml manifests
+    #[arg(long)]
+    languages_dir: Option<PathBuf>,
+    /// Docker daemon address (unix:///var/run/docker.sock or tcp://host:port)
+    #[arg(long)]
+    docker_host: Option<String>,
+    /// CPU share of a single core per container, in percent
+    #[arg(long)]
+    cpu_quota_percent: Option<u32>,
+    /// Build missing language images from their manifest directory at startup
+    #[arg(long)]
+    build_missing_images: bool,
+    /// Backend for languages without their own entry: docker or native
+    #[arg(long)]
+    backend: Option<BackendKind>,
+    /// Keep pre-started containers for each language image
+    #[arg(long)]
+    warm_pool: bool,
+    /// Maximum number of executions running at once
+    #[arg(long)]
+    max_concurrent: Option<usize>,
+    /// Maximum number of queued requests
+    #[arg(long)]
+    max_queue_length: Option<usize>,
+    #[arg(long)]
+    default_timeout_seconds: Option<u32>,
+    #[arg(long)]
+    max_timeout_seconds: Option<u32>,
+    #[arg(long)]
+    default_memory_limit_mb: Option<u32>,
+    #[arg(long)]
+    max_memory_limit_mb: Option<u32>,
+    /// SQLite database for execution history
+    #[arg(long)]
+    history_db: Option<PathBuf>,
+    /// Seconds to let running executions finish on shutdown
+    #[arg(long)]
+    drain_timeout_seconds: Option<u64>,
+    /// Validate and print the effective configuration, then exit
+    #[arg(long)]
+    check_config: bool,
 }
 
-use proto::code_executor_server::{CodeExecutor, CodeExecutorServer};
-use proto::{ExecuteRequest, ExecuteResponse, StatusRequest, StatusResponse, ExecutionStatus};
-use container::manager::ContainerManager;
+impl Cli {
+    /// Command line flags take precedence over the config file and environment
+    fn apply(&self, config: &mut ServerConfig) {
+        if let Some(listen) = &self.listen {
+            config.listen_address = listen.clone();
+        }
+        if let Some(dir) = &self.languages_dir {
+            config.languages_dir = dir.clone();
+        }
+        if let Some(host) = &self.docker_host {
+            config.docker.host = host.clone();
+        }
+        if let Some(percent) = self.cpu_quota_percent {
+            config.docker.cpu_quota_percent = percent;
+        }
+        if let Some(backend) = self.backend {
+            config.backend.default = backend;
+        }
+        if self.build_missing_images {
+            config.docker.build_missing_images = true;
+        }
+        if self.warm_pool {
+            config.pool.enabled = true;
+        }
+        if let Some(max_concurrent) = self.max_concurrent {
+            config.scheduler.max_concurrent = max_concurrent;
+        }
+        if let Some(max_queue_length) = self.max_queue_length {
+            config.scheduler.max_queue_length = max_queue_length;
+        }
+        if let Some(seconds) = self.default_timeout_seconds {
+            config.limits.default_timeout_seconds = seconds;
+        }
+        if let Some(seconds) = self.max_timeout_seconds {
+            config.limits.max_timeout_seconds = seconds;
+        }
+        if let Some(mb) = self.default_memory_limit_mb {
+            config.limits.default_memory_limit_mb = mb;
+        }
+        if let Some(mb) = self.max_memory_limit_mb {
+            config.limits.max_memory_limit_mb = mb;
+        }
+        if let Some(path) = &self.history_db {
+            config.history.path = Some(path.clone());
+        }
+        if let Some(seconds) = self.drain_timeout_seconds {
+            config.drain_timeout_seconds = seconds;
+        }
+    }
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CodeExecutorService {
-    container_manager: ContainerManager,
+    limits: LimitsConfig,
+    languages: Arc<LanguageRegistry>,
+    backends: Arc<Backends>,
+    scheduler: Arc<Scheduler>,
+    cache: Arc<ResultCache>,
+    tracker: Arc<ExecutionTracker>,
+    notifier: Arc<WebhookNotifier>,
+    history: Option<Arc<HistoryStore>>,
+    health: HealthReporter,
+}
+
+impl CodeExecutorService {
+    /// Resolve the cache key for opted-in requests; the image digest is part of the key
+    /// so results from a rebuilt image never match
+    fn cache_key(&self, req: &ExecuteRequest, manifest: &LanguageManifest, digest: &str) -> Option<CacheKey> {
+        req.use_cache.then(|| CacheKey::new(req, &manifest.image, digest))
+    }
+
+    fn enqueue(&self, req: &ExecuteRequest) -> Result<Ticket, AdmissionError> {
+        let priority = match req.priority() {
+            proto::Priority::Interactive => Priority::Interactive,
+            proto::Priority::Normal => Priority::Normal,
+            proto::Priority::Batch => Priority::Batch,
+        };
+        self.scheduler.enqueue(&req.language, priority)
+    }
+
+    /// Wait for the execution slot, run the code and record the outcome.
+    /// The timeout only starts once the container is created, never while queued.
+    async fn run(&self, execution_id: String, req: &ExecuteRequest, manifest: &LanguageManifest,
+                 ticket: Ticket, cache_key: Option<CacheKey>) -> Result<ExecuteResponse, Status> {
+        let admission = ticket.wait().await.map_err(admission_status)?;
+        self.tracker.start(&execution_id);
+
+        // Execute code on the language's backend
+        let result = self.backends
+            .for_language(manifest)
+            .execute(
+                manifest,
+                &req.code,
+                manifest.scaled_timeout(req.timeout_seconds as u32),
+                req.memory_limit_mb as u32,
+                &req.input,
+            )
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        drop(admission.permit);
+
+        let response = ExecuteResponse {
+            execution_id,
+            queue_position: admission.queue_position.try_into().unwrap_or(i32::MAX),
+            queue_wait_ms: admission.queue_wait.as_secs_f64() * 1000.0,
+            resolved_language: manifest.language.clone(),
+            resolved_version: manifest.version.clone(),
+            ..result.into()
+        };
+        if let Some(key) = cache_key {
+            self.cache.insert(key, &response);
+        }
+
+        Ok(response)
+    }
+
+    /// Execute in the background and post the final result to `callback_url`
+    fn spawn_with_callback(&self, execution_id: String, req: ExecuteRequest, manifest: Arc<LanguageManifest>,
+                           ticket: Ticket, cache_key: Option<CacheKey>, submitted_at: SystemTime) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let response = match service.run(execution_id.clone(), &req, &manifest, ticket, cache_key).await {
+                Ok(response) => response,
+                Err(status) => failed_response(execution_id, &req, &status),
+            };
+            let delivery = service.tracker.start_delivery();
+            service.finish(&req, &response, submitted_at);
+            service.spawn_callback(delivery, response, req.callback_url);
+        });
+    }
+
+    /// Record the final status and, when enabled, persist the execution to history
+    fn finish(&self, req: &ExecuteRequest, response: &ExecuteResponse, submitted_at: SystemTime) {
+        self.tracker.finish(&response.execution_id, response.status());
+
+        if let Some(history) = &self.history {
+            let history = history.clone();
+            let req = req.clone();
+            let response = response.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = history.record(&req, &response, submitted_at) {
+                    tracing::error!("Failed to record execution {}: {}", response.execution_id, e);
+                }
+            });
+        }
+    }
+
+    /// Post the result in the background; `delivery` keeps a drain waiting until it is done
+    fn spawn_callback(&self, delivery: Delivery, response: ExecuteResponse, callback_url: String) {
+        let notifier = self.notifier.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notifier.deliver(&callback_url, &response).await {
+                tracing::error!("{}", e);
+            }
+            drop(delivery);
+        });
+    }
+}
+
+fn failed_response(execution_id: String, req: &ExecuteRequest, status: &Status) -> ExecuteResponse {
+    ExecuteResponse {
+        execution_id,
+        status: ExecutionStatus::Failed as i32,
+        error_message: status.message().to_string(),
+        resolved_language: req.language.clone(),
+        resolved_version: req.version.clone(),
+        ..Default::default()
+    }
+}
+
+fn admission_status(e: AdmissionError) -> Status {
+    match e {
+        AdmissionError::QueueFull(_) | AdmissionError::Preempted => Status::resource_exhausted(e.to_string()),
+        AdmissionError::Cancelled => Status::cancelled(e.to_string()),
+    }
 }
 
 #[tonic::async_trait]
@@ -23,30 +263,92 @@ impl CodeExecutor for CodeExecutorService {
         &self,
         request: Request<ExecuteRequest>,
     ) -> Result<Response<ExecuteResponse>, Status> {
-        let req = request.into_inner();
+        // 종료 중에는 새 실행을 받지 않음
+        if !self.health.is_serving() {
+            return Err(Status::unavailable("Server is shutting down"));
+        }
 
-        // Execute code using container manager
-        let result = self.container_manager
-            .execute_code(
-                &req.code,
-                &req.language,
-                &req.version,
-                req.timeout_seconds.try_into().unwrap(),
-                req.memory_limit_mb.try_into().unwrap(),
-                &req.input,
-            )
-            .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+        let mut req = request.into_inner();
+        let execution_id = uuid::Uuid::new_v4().to_string();
+        let submitted_at = SystemTime::now();
 
-        Ok(Response::new(ExecuteResponse {
-            execution_id: uuid::Uuid::new_v4().to_string(),
-            status: result.status as i32,
-            stdout: result.stdout,
-            stderr: result.stderr,
-            memory_used_kb: result.memory_used.try_into().unwrap(),
-            execution_time_ms: result.execution_time,
-            error_message: String::new(),
-        }))
+        // Aliases and version shorthands are replaced by the canonical pair before anything else sees them
+        let manifest = self.languages
+            .resolve(&req.language, &req.version)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        req.language = manifest.language.clone();
+        req.version = manifest.version.clone();
+        let digest = self.backends
+            .for_language(&manifest)
+            .toolchain(&manifest)
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+
+        // Unset limits take the language or server defaults; everything downstream sees resolved values
+        let (timeout_seconds, memory_limit_mb) = self.limits
+            .resolve(req.timeout_seconds, req.memory_limit_mb, &manifest.limits)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        req.timeout_seconds = timeout_seconds as i32;
+        req.memory_limit_mb = memory_limit_mb as i32;
+
+        let asynchronous = !req.callback_url.is_empty();
+        if asynchronous {
+            self.notifier.validate_url(&req.callback_url).await
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        }
+
+        let cache_key = self.cache_key(&req, &manifest, &digest);
+        if let Some(cached) = cache_key.as_ref().and_then(|key| self.cache.get(key)) {
+            let response = ExecuteResponse {
+                execution_id,
+                queue_position: 0,
+                queue_wait_ms: 0.0,
+                ..cached
+            };
+            self.tracker.register(&response.execution_id);
+            let delivery = asynchronous.then(|| self.tracker.start_delivery());
+            self.finish(&req, &response, submitted_at);
+            if let Some(delivery) = delivery {
+                self.spawn_callback(delivery, response.clone(), req.callback_url);
+            }
+            return Ok(Response::new(response));
+        }
+
+        // Reject right away when the queue is full, even for asynchronous requests
+        let ticket = self.enqueue(&req).map_err(admission_status)?;
+        self.tracker.register(&execution_id);
+
+        if asynchronous {
+            let queue_position = ticket.queue_position().try_into().unwrap_or(i32::MAX);
+            let pending = ExecuteResponse {
+                execution_id: execution_id.clone(),
+                status: ExecutionStatus::Pending as i32,
+                queue_position,
+                resolved_language: manifest.language.clone(),
+                resolved_version: manifest.version.clone(),
+                ..Default::default()
+            };
+            self.spawn_with_callback(execution_id, req, manifest, ticket, cache_key, submitted_at);
+            return Ok(Response::new(pending));
+        }
+
+        // 클라이언트가 끊겨 이 future가 버려져도 실행이 끝나고 finish가 기록되도록 별도 태스크에서 실행
+        let service = self.clone();
+        let tracked_id = execution_id.clone();
+        let execution = tokio::spawn(async move {
+            let result = service.run(execution_id.clone(), &req, &manifest, ticket, cache_key).await;
+            match &result {
+                Ok(response) => service.finish(&req, response, submitted_at),
+                Err(status) => service.finish(&req, &failed_response(execution_id, &req, status), submitted_at),
+            }
+            result
+        });
+        match execution.await {
+            Ok(result) => result.map(Response::new),
+            Err(e) => {
+                self.tracker.finish(&tracked_id, ExecutionStatus::RuntimeError);
+                Err(Status::internal(format!("Execution task failed: {}", e)))
+            }
+        }
     }
 
     async fn get_status(
@@ -55,12 +357,61 @@ impl CodeExecutor for CodeExecutorService {
     ) -> Result<Response<StatusResponse>, Status> {
         let req = request.into_inner();
 
-        // For MVP, we don't store execution status
+        let status = self.tracker
+            .status(&req.execution_id)
+            .ok_or_else(|| Status::not_found(format!("Unknown execution id: {}", req.execution_id)))?;
+
         Ok(Response::new(StatusResponse {
             execution_id: req.execution_id,
-            status: ExecutionStatus::Completed as i32,
+            status: status as i32,
         }))
     }
+
+    async fn list_executions(
+        &self,
+        request: Request<ListExecutionsRequest>,
+    ) -> Result<Response<ListExecutionsResponse>, Status> {
+        let req = request.into_inner();
+        let history = self.history
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("Execution history is disabled"))?;
+
+        let executions = tokio::task::spawn_blocking(move || history.list(&req))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListExecutionsResponse { executions }))
+    }
+
+    async fn list_languages(
+        &self,
+        _request: Request<ListLanguagesRequest>,
+    ) -> Result<Response<ListLanguagesResponse>, Status> {
+        let languages = self.languages.iter()
+            .map(|manifest| (manifest, self.backends.for_language(manifest)))
+            .map(|(manifest, backend)| (manifest, backend.kind(), backend.toolchain(manifest).ok()))
+            .map(|(manifest, backend, digest)| proto::Language {
+                language: manifest.language.clone(),
+                version: manifest.version.clone(),
+                display_name: manifest.display_name(),
+                source_file: manifest.source_file.clone(),
+                compiled: manifest.is_compiled(),
+                default_timeout_seconds: manifest.limits.timeout_seconds
+                    .unwrap_or(self.limits.default_timeout_seconds) as i32,
+                default_memory_limit_mb: manifest.limits.memory_limit_mb
+                    .unwrap_or(self.limits.default_memory_limit_mb) as i32,
+                time_multiplier: manifest.time_multiplier,
+                aliases: manifest.aliases.clone(),
+                is_default: manifest.is_default,
+                backend: backend.to_string(),
+                available: digest.is_some(),
+                image_digest: digest.unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(Response::new(ListLanguagesResponse { languages }))
+    }
 }
 
 #[tokio::main]
@@ -68,22 +419,156 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Initialize tracing
     tracing_subscriber::fmt::init();
 
+    // Load configuration: defaults < config file < environment < command line
+    let cli = Cli::parse();
+    let config = ServerConfig::load(cli.config.as_deref(), |config| cli.apply(config))?;
+    config.validate()?;
+    println!("Effective configuration:\n{}", config.to_redacted_toml()?);
+
+    // Load language manifests; a broken manifest stops startup
+    let languages = Arc::new(LanguageRegistry::load(&config.languages_dir)?);
+    let limit_errors = languages.validate_against(&config.limits);
+    if !limit_errors.is_empty() {
+        return Err(format!("Invalid language manifests:\n  - {}", limit_errors.join("\n  - ")).into());
+    }
+    let backend_errors = config.backend.validate_against(&languages);
+    if !backend_errors.is_empty() {
+        return Err(format!("Invalid configuration:\n  - {}", backend_errors.join("\n  - ")).into());
+    }
+    let keys: Vec<_> = languages.iter()
+        .map(|manifest| format!("{} ({})", manifest.key(), config.backend.kind_for(manifest)))
+        .collect();
+    println!("Languages: {}", keys.join(", "));
+    if cli.check_config {
+        return Ok(());
+    }
+
     // Create container manager
-    let container_manager = ContainerManager::new().await?;
+    // Pooled containers may be handed out to the slowest language at the maximum timeout
+    let max_scaled_timeout = languages.iter()
+        .map(|manifest| manifest.scaled_timeout(config.limits.max_timeout_seconds))
+        .max()
+        .unwrap_or(config.limits.max_timeout_seconds);
+    let container_manager = ContainerManager::new(config.docker.clone()).await?;
+
+    // Record image digests; languages whose image is missing stay listed but reject executions
+    let sources: Vec<_> = languages.iter()
+        .filter(|manifest| config.backend.kind_for(manifest) == BackendKind::Docker)
+        .map(|manifest| ImageSource { image: manifest.image.clone(), context: languages.build_context(manifest) })
+        .collect();
+    let images = container_manager.check_images(&sources).await;
+    for (image, digest) in &images.digests {
+        tracing::info!("Image {} is {}{}", image, digest, if images.built.contains(image) { " (built)" } else { "" });
+    }
+    for (image, reason) in &images.missing {
+        tracing::error!("Image {} is unavailable: {}", image, reason);
+    }
+    if !images.missing.is_empty() && !config.docker.build_missing_images {
+        tracing::warn!("Build the missing images with environments/dev/scripts/build-language-images.sh or start with --build-missing-images");
+    }
+
+    let container_manager = Arc::new(
+        container_manager
+            .with_pool(config.pool.clone(), max_scaled_timeout, images.digests.into_keys())
+            .with_seccomp_profiles(languages.iter().map(|manifest| manifest.as_ref())),
+    );
+    tracing::info!("Server instance {}", container_manager.identity().id);
+
+    // Set up execution cgroups only when some language actually runs natively
+    let native_backend = if languages.iter().any(|manifest| config.backend.kind_for(manifest) == BackendKind::Native) {
+        NativeBackend::new(&config.backend.native)
+    } else {
+        NativeBackend::default()
+    };
+    let backends = Arc::new(Backends::new(
+        config.backend.clone(),
+        container_manager.clone(),
+        Arc::new(native_backend),
+    ));
+    if backends.uses(BackendKind::Docker, &languages) {
+        // Remove containers left behind by crashed instances, then keep sweeping
+        container_manager.spawn_reaper();
+
+        // Pre-start containers when the warm pool is enabled and report latency per start kind
+        container_manager.spawn_pool_refill();
+        container_manager.spawn_latency_reporter(LATENCY_REPORT_INTERVAL);
+    }
+
+    // Create scheduler for admission control
+    let scheduler = Scheduler::new(config.scheduler.clone());
+
+    // Create result cache for opt-in requests
+    let cache = Arc::new(ResultCache::new(config.cache.clone()));
+
+    // Create completion callback delivery
+    let notifier = Arc::new(WebhookNotifier::new(config.webhook.clone())?);
+
+    // Open execution history database when configured
+    let history = HistoryStore::open(config.history.clone())?.map(Arc::new);
+    if let Some(history) = &history {
+        history.spawn_pruner();
+    }
 
     // Create service
+    let health = HealthReporter::new();
+    let tracker = Arc::new(ExecutionTracker::new());
     let service = CodeExecutorService {
-        container_manager,
+        limits: config.limits.clone(),
+        languages,
+        backends,
+        scheduler,
+        cache,
+        tracker: tracker.clone(),
+        notifier,
+        history,
+        health: health.clone(),
     };
 
     // Start server
-    let addr = "[::]:50051".parse()?;
+    let addr = config.listen_address()?;
     println!("CodeExecutor server listening on {}", addr);
 
+    let shutdown = drain_on_signal(health.clone(), tracker, container_manager, config.drain_timeout());
     Server::builder()
+        .add_service(HealthServer::new(health.service()))
         .add_service(CodeExecutorServer::new(service))
-        .serve(addr)
+        .serve_with_shutdown(addr, shutdown)
         .await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Wait for SIGTERM or SIGINT, then shut down gracefully:
+/// report NOT_SERVING and reject new executions, let in-flight executions finish and their
+/// callbacks be delivered until the drain deadline, and finally kill and remove whatever
+/// containers are left.
+/// The server stops once this returns.
+async fn drain_on_signal(health: HealthReporter, tracker: Arc<ExecutionTracker>,
+                         container_manager: Arc<ContainerManager>, drain_timeout: Duration) {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            tracing::error!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    health.set_not_serving();
+    tracing::info!("Shutting down, waiting up to {:?} for {} executions and {} callbacks",
+                   drain_timeout, tracker.in_flight(), tracker.deliveries());
+
+    if tokio::time::timeout(drain_timeout, tracker.wait_idle()).await.is_err() {
+        tracing::warn!("Drain deadline passed with {} executions still running and {} callbacks undelivered",
+                       tracker.in_flight(), tracker.deliveries());
+    }
+
+    let removed = container_manager.remove_active_containers().await;
+    if removed > 0 {
+        tracing::warn!("Removed {} leftover containers", removed);
+    }
+}
@@ -0,0 +1,287 @@
+use std::fs::File;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result, anyhow};
+
+/// Controllers every execution cgroup needs
+const CONTROLLERS: [&str; 3] = ["cpu", "memory", "pids"];
+/// `cpu.max` period in microseconds
+const CPU_PERIOD_US: u64 = 100_000;
+/// Leaf the server moves itself into when its own cgroup is the parent, because cgroup v2
+/// only lets a cgroup without processes hand controllers to its children
+const SERVER_LEAF: &str = "code-executor-server";
+/// How long to wait for killed processes to leave a cgroup before removing it
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Limits every execution cgroup gets besides its memory limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CgroupLimits {
+    /// Share of one CPU, written to `cpu.max`
+    pub cpu_quota_percent: u32,
+    /// `pids.max`: processes and threads the execution may have at once
+    pub max_processes: u32,
+}
+
+/// A cgroup v2 directory whose children get the cpu, memory and pids controllers.
+/// One child is created per execution.
+#[derive(Debug)]
+pub struct CgroupParent {
+    path: PathBuf,
+    limits: CgroupLimits,
+}
+
+/// What the kernel accounted to one execution cgroup
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CgroupUsage {
+    /// `memory.peak` in kilobytes; `None` on kernels before 5.19
+    pub memory_peak_kb: Option<u64>,
+    /// Processes killed because the cgroup reached `memory.max`
+    pub oom_kills: u64,
+    pub cpu_user_ms: f64,
+    pub cpu_system_ms: f64,
+}
+
+impl CgroupParent {
+    /// Prepare `path` (the server's own cgroup when `None`) for execution cgroups.
+    /// Fails when cgroup v2 is not mounted, a controller is not delegated to it or it is not writable,
+    /// in which case the caller falls back to rlimits.
+    pub fn delegated(path: Option<&Path>, limits: CgroupLimits) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => own_cgroup()?,
+        };
+        let available = std::fs::read_to_string(path.join("cgroup.controllers"))
+            .with_context(|| format!("{} is not a cgroup v2 directory", path.display()))?;
+        let missing: Vec<_> = CONTROLLERS.iter()
+            .filter(|controller| !available.split_whitespace().any(|available| available == **controller))
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!("controllers {:?} are not available in {}", missing, path.display()));
+        }
+
+        if let Err(e) = enable_controllers(&path) {
+            // 프로세스가 있는 cgroup은 자식에게 컨트롤러를 줄 수 없으므로 서버를 leaf로 옮기고 다시 시도
+            let leaf = path.join(SERVER_LEAF);
+            std::fs::create_dir_all(&leaf)?;
+            std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())
+                .with_context(|| format!("enabling controllers failed ({}) and the server could not move to {}", e, leaf.display()))?;
+            enable_controllers(&path)?;
+        }
+        Ok(Self { path, limits })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the cgroup for one execution with its limits applied
+    pub fn create(&self, name: &str, memory_bytes: u64) -> Result<ExecutionCgroup> {
+        let path = self.path.join(name);
+        std::fs::create_dir(&path).with_context(|| format!("Failed to create cgroup {}", path.display()))?;
+        let mut cgroup = ExecutionCgroup { path, procs: None };
+        // 실패하면 Drop이 빈 cgroup을 삭제
+        self.apply_limits(&cgroup, memory_bytes)?;
+        cgroup.procs = Some(File::create(cgroup.path.join("cgroup.procs"))?);
+        Ok(cgroup)
+    }
+
+    fn apply_limits(&self, cgroup: &ExecutionCgroup, memory_bytes: u64) -> Result<()> {
+        let quota = CPU_PERIOD_US * self.limits.cpu_quota_percent as u64 / 100;
+        cgroup.write("memory.max", &memory_bytes.to_string())?;
+        cgroup.write("memory.swap.max", "0")?;
+        cgroup.write("pids.max", &self.limits.max_processes.to_string())?;
+        cgroup.write("cpu.max", &format!("{} {}", quota, CPU_PERIOD_US))?;
+        // OOM이 나면 일부가 아니라 실행 전체를 종료
+        let _ = cgroup.write("memory.oom.group", "1");
+        Ok(())
+    }
+}
+
+/// The cgroup of one execution; removed by `destroy`, or on drop when the execution is cancelled
+#[derive(Debug)]
+pub struct ExecutionCgroup {
+    path: PathBuf,
+    /// Open `cgroup.procs`, written by the child before exec to join the cgroup
+    procs: Option<File>,
+}
+
+impl Drop for ExecutionCgroup {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty() {
+            self.kill();
+            let _ = std::fs::remove_dir(&self.path);
+        }
+    }
+}
+
+impl ExecutionCgroup {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Descriptor of `cgroup.procs`; writing `0` to it moves the writing process into the cgroup
+    pub fn procs_fd(&self) -> Option<RawFd> {
+        self.procs.as_ref().map(AsRawFd::as_raw_fd)
+    }
+
+    fn write(&self, file: &str, value: &str) -> Result<()> {
+        std::fs::write(self.path.join(file), value)
+            .with_context(|| format!("Failed to write {} to {}", value, self.path.join(file).display()))
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        std::fs::read_to_string(self.path.join(file)).ok()
+    }
+
+    pub fn usage(&self) -> CgroupUsage {
+        let cpu = self.read("cpu.stat").unwrap_or_default();
+        CgroupUsage {
+            memory_peak_kb: self.read("memory.peak").and_then(|peak| peak.trim().parse::<u64>().ok()).map(|bytes| bytes / 1024),
+            oom_kills: self.read("memory.events").and_then(|events| stat(&events, "oom_kill")).unwrap_or(0),
+            cpu_user_ms: stat(&cpu, "user_usec").unwrap_or(0) as f64 / 1000.0,
+            cpu_system_ms: stat(&cpu, "system_usec").unwrap_or(0) as f64 / 1000.0,
+        }
+    }
+
+    /// SIGKILL every process in the cgroup, including ones that left the process group
+    pub fn kill(&self) {
+        // cgroup.kill은 5.14부터 지원
+        if self.write("cgroup.kill", "1").is_ok() {
+            return;
+        }
+        for pid in self.read("cgroup.procs").unwrap_or_default().lines().filter_map(|pid| pid.trim().parse().ok()) {
+            let _ = nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), nix::sys::signal::Signal::SIGKILL);
+        }
+    }
+
+    /// Kill what is left, wait for the cgroup to empty and remove it
+    pub async fn destroy(mut self) {
+        self.procs = None;
+        self.kill();
+        let path = std::mem::take(&mut self.path);
+        let populated = || std::fs::read_to_string(path.join("cgroup.events"))
+            .is_ok_and(|events| stat(&events, "populated") == Some(1));
+        let deadline = tokio::time::Instant::now() + DRAIN_TIMEOUT;
+        while populated() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        if let Err(e) = std::fs::remove_dir(&path) {
+            tracing::warn!("Failed to remove cgroup {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn enable_controllers(path: &Path) -> Result<()> {
+    let enabled = std::fs::read_to_string(path.join("cgroup.subtree_control")).unwrap_or_default();
+    let missing: Vec<_> = CONTROLLERS.iter()
+        .filter(|controller| !enabled.split_whitespace().any(|enabled| enabled == **controller))
+        .map(|controller| format!("+{}", controller))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    std::fs::write(path.join("cgroup.subtree_control"), missing.join(" "))
+        .with_context(|| format!("Failed to enable {} in {}", missing.join(" "), path.display()))
+}
+
+/// Directory of the server's cgroup in the cgroup v2 hierarchy
+fn own_cgroup() -> Result<PathBuf> {
+    let cgroups = std::fs::read_to_string("/proc/self/cgroup")?;
+    let relative = cgroups.lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| anyhow!("the server is not in a cgroup v2 hierarchy"))?;
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")?;
+    let mount = cgroup2_mount(&mountinfo).ok_or_else(|| anyhow!("cgroup v2 is not mounted"))?;
+    Ok(mount.join(relative.trim_start_matches('/')))
+}
+
+/// Mount point of the cgroup2 filesystem in `/proc/self/mountinfo`
+fn cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, filesystem) = line.split_once(" - ")?;
+        if filesystem.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        mount.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// Value of `key` in a flat keyed file such as `cpu.stat` or `memory.events`
+fn stat(contents: &str, key: &str) -> Option<u64> {
+    contents.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: CgroupLimits = CgroupLimits { cpu_quota_percent: 50, max_processes: 32 };
+
+    /// A directory laid out like a delegated cgroup; plain files stand in for the control files
+    fn fake_parent(controllers: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("cgroup.controllers"), controllers).unwrap();
+        std::fs::write(dir.path().join("cgroup.subtree_control"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn enables_controllers_for_children() {
+        let dir = fake_parent("cpuset cpu io memory pids\n");
+        CgroupParent::delegated(Some(dir.path()), LIMITS).unwrap();
+
+        assert_eq!(std::fs::read_to_string(dir.path().join("cgroup.subtree_control")).unwrap(), "+cpu +memory +pids");
+    }
+
+    #[test]
+    fn requires_every_controller() {
+        let dir = fake_parent("cpu io\n");
+        let error = CgroupParent::delegated(Some(dir.path()), LIMITS).unwrap_err().to_string();
+
+        assert!(error.contains("memory") && error.contains("pids"), "{}", error);
+        assert!(CgroupParent::delegated(Some(&dir.path().join("missing")), LIMITS).is_err());
+    }
+
+    #[test]
+    fn execution_cgroups_get_their_limits() {
+        let dir = fake_parent("cpu memory pids");
+        let parent = CgroupParent::delegated(Some(dir.path()), LIMITS).unwrap();
+        let cgroup = parent.create("exec-1", 256 * 1024 * 1024).unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(cgroup.path().join(file)).unwrap();
+        assert_eq!(read("memory.max"), "268435456");
+        assert_eq!(read("memory.swap.max"), "0");
+        assert_eq!(read("pids.max"), "32");
+        assert_eq!(read("cpu.max"), "50000 100000");
+        assert!(cgroup.procs_fd().is_some());
+        assert!(parent.create("exec-1", 1024).is_err());
+    }
+
+    #[test]
+    fn reads_usage_from_control_files() {
+        let dir = fake_parent("cpu memory pids");
+        let cgroup = CgroupParent::delegated(Some(dir.path()), LIMITS).unwrap().create("exec-1", 1024).unwrap();
+        std::fs::write(cgroup.path().join("memory.peak"), "10485760\n").unwrap();
+        std::fs::write(cgroup.path().join("memory.events"), "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 1\n").unwrap();
+        std::fs::write(cgroup.path().join("cpu.stat"), "usage_usec 1500\nuser_usec 1200\nsystem_usec 300\n").unwrap();
+
+        assert_eq!(cgroup.usage(), CgroupUsage {
+            memory_peak_kb: Some(10240),
+            oom_kills: 1,
+            cpu_user_ms: 1.2,
+            cpu_system_ms: 0.3,
+        });
+    }
+
+    #[test]
+    fn finds_the_cgroup2_mount() {
+        let mountinfo = "\
+            35 24 0:30 / /sys/fs/cgroup/memory rw,relatime shared:12 - cgroup cgroup rw,memory\n\
+            40 24 0:35 / /sys/fs/cgroup/unified rw,relatime shared:17 - cgroup2 cgroup2 rw\n";
+        assert_eq!(cgroup2_mount(mountinfo), Some(PathBuf::from("/sys/fs/cgroup/unified")));
+        assert_eq!(cgroup2_mount("35 24 0:30 / /proc rw - proc proc rw\n"), None);
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, anyhow};
+use nix::errno::Errno;
+use nix::unistd::{setgroups, setresgid, setuid, Gid, Uid};
+
+/// Uid and gid ranges executions run as. Each concurrent execution gets ids of its own,
+/// so `RLIMIT_NPROC`, which counts per uid, and file ownership are per execution.
+#[derive(Debug)]
+pub struct SandboxIds {
+    uid: u32,
+    gid: u32,
+    /// Which ids are taken, and where to look for a free one next so ids are not reused right away
+    state: Mutex<(Vec<bool>, usize)>,
+}
+
+impl SandboxIds {
+    /// `count` uids from `uid` and as many gids from `gid`, paired by offset
+    pub fn new(uid: u32, gid: u32, count: u32) -> Self {
+        Self { uid, gid, state: Mutex::new((vec![false; count as usize], 0)) }
+    }
+
+    /// Ids for one execution. When the server is not root it cannot switch ids, so executions
+    /// keep the server's own.
+    pub fn acquire(self: &Arc<Self>) -> Result<SandboxIdentity> {
+        if !Uid::effective().is_root() {
+            return Ok(SandboxIdentity::new(Uid::effective(), Gid::effective(), false, None));
+        }
+        let mut state = self.state.lock().unwrap();
+        let (taken, next) = &mut *state;
+        let count = taken.len();
+        let offset = (0..count).map(|i| (*next + i) % count).find(|&offset| !taken[offset])
+            .ok_or_else(|| anyhow!("All {} sandbox uids are in use", count))?;
+        taken[offset] = true;
+        *next = (offset + 1) % count;
+        let lease = Lease { ids: self.clone(), offset };
+        Ok(SandboxIdentity::new(
+            Uid::from_raw(self.uid + offset as u32),
+            Gid::from_raw(self.gid + offset as u32),
+            true,
+            Some(Arc::new(lease)),
+        ))
+    }
+}
+
+/// Gives an id back to its range when the execution holding it is done
+#[derive(Debug)]
+struct Lease {
+    ids: Arc<SandboxIds>,
+    offset: usize,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.ids.state.lock().unwrap().0[self.offset] = false;
+    }
+}
+
+/// Uid and gid one execution runs as
+#[derive(Debug, Clone)]
+pub struct SandboxIdentity {
+    uid: Uid,
+    gid: Gid,
+    /// Switch to `uid`/`gid` in the child; only possible when the server runs as root
+    switch: bool,
+    /// `/proc/<pid>/uid_map` and `gid_map` mapping the ids to themselves in a user namespace
+    pub(crate) uid_map: Vec<u8>,
+    pub(crate) gid_map: Vec<u8>,
+    _lease: Option<Arc<Lease>>,
+}
+
+impl SandboxIdentity {
+    fn new(uid: Uid, gid: Gid, switch: bool, lease: Option<Arc<Lease>>) -> Self {
+        Self {
+            uid,
+            gid,
+            switch,
+            uid_map: format!("{0} {0} 1\n", uid).into_bytes(),
+            gid_map: format!("{0} {0} 1\n", gid).into_bytes(),
+            _lease: lease,
+        }
+    }
+
+    /// The server's own ids, which executions keep
+    pub fn current() -> Self {
+        Self::new(Uid::effective(), Gid::effective(), false, None)
+    }
+
+    pub fn uid(&self) -> Uid {
+        self.uid
+    }
+
+    pub fn gid(&self) -> Gid {
+        self.gid
+    }
+
+    /// Drop every supplementary group and switch to the ids for good.
+    /// Runs in the forked child, so it must not allocate.
+    pub fn switch(&self) -> nix::Result<()> {
+        if !self.switch {
+            return Ok(());
+        }
+        setgroups(&[])?;
+        setresgid(self.gid, self.gid, self.gid)?;
+        // 실제, 유효, 저장 uid를 모두 바꾸므로 root로 돌아갈 수 없음
+        setuid(self.uid)?;
+        // uid를 바꾸면 dumpable이 꺼져 /proc/self/uid_map을 쓸 수 없게 됨
+        if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 1) } != 0 {
+            return Err(Errno::last());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_executions_get_ids_of_their_own() {
+        if !Uid::effective().is_root() {
+            eprintln!("Not root, skipping");
+            return;
+        }
+        let ids = Arc::new(SandboxIds::new(200000, 300000, 2));
+        let first = ids.acquire().unwrap();
+        let second = ids.acquire().unwrap();
+        assert_eq!((first.uid().as_raw(), first.gid().as_raw()), (200000, 300000));
+        assert_eq!((second.uid().as_raw(), second.gid().as_raw()), (200001, 300001));
+        assert!(ids.acquire().is_err());
+
+        drop(first);
+        assert_eq!(ids.acquire().unwrap().uid().as_raw(), 200000);
+    }
+}
@@ -1,46 +1,124 @@
-use std::process::{Command, Stdio};
+pub mod cgroup;
+pub mod identity;
+pub mod namespace;
+pub mod rootfs;
+
+use std::process::{Command, ExitStatus, Stdio};
 use std::time::{Duration, Instant};
-use std::io::Read;
 use std::path::Path;
+use std::sync::Arc;
 use anyhow::{Result, anyhow};
-use serde::Serialize;
 use nix::sys::resource::{setrlimit, Resource};
-use nix::unistd::{setuid, Uid};
-use nix::sys::signal::{self, Signal};
-use nix::sys::wait::{waitpid, WaitStatus};
-use nix::unistd::ForkResult::{Child, Parent};
-use nix::unistd::fork;
-use std::os::unix::process::CommandExt;
-use std::os::unix::io::{FromRawFd, IntoRawFd};
-
-/// Represents the status of code execution
-#[derive(Debug, Serialize)]
-pub enum ExecutionStatus {
-    Completed,
-    TimeLimitExceeded,
-    MemoryLimitExceeded,
-    RuntimeError,
-    SystemError,
-}
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use std::os::unix::io::RawFd;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{ChildStderr, ChildStdin, ChildStdout};
+use tokio::sync::watch;
 
-/// Holds the result of code execution
-#[derive(Debug, Serialize)]
-pub struct ExecutionResult {
-    pub status: ExecutionStatus,
-    pub stdout: String,
-    pub stderr: String,
-    pub execution_time: f64,
-    pub memory_used: u64,
-    pub exit_code: i32,
-}
+use crate::execution::{CompileResult, ExecutionResult, ExecutionStatus};
+use crate::language::LanguageManifest;
+use crate::security::{Landlock, SyscallFilter};
+
+pub use cgroup::{CgroupLimits, CgroupParent, CgroupUsage};
+pub use identity::{SandboxIds, SandboxIdentity};
+pub use namespace::Namespaces;
+pub use rootfs::{Rootfs, Workspace};
+
+/// Share of the memory limit a failed run's peak RSS must reach to count as out of memory
+/// under rlimits, where allocations past the limit fail instead of the process being killed
+const OUT_OF_MEMORY_PEAK_PERCENT: u64 = 75;
+/// How long output is still read once the child is gone; a descendant that escaped the process
+/// group can keep the pipes open, and what it writes later is discarded
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
 
 /// Configuration for code execution
 #[derive(Debug)]
 pub struct ExecutionConfig {
     pub timeout_seconds: u32,
     pub memory_limit_mb: u64,
-    pub language: String,
+    /// Look this up with `LanguageRegistry::resolve` so aliases and version shorthands
+    /// behave exactly as they do for `ExecuteCode`
+    pub language: Arc<LanguageManifest>,
     pub code: String,
+    /// Lines written to the child's stdin, each followed by a newline
+    pub input: Vec<String>,
+    /// Processes and threads the child may have at once when no cgroup limits them
+    pub max_processes: u32,
+    /// Bytes kept of each of stdout and stderr; the rest is read and discarded
+    pub max_output_bytes: usize,
+    /// Where to create the execution's cgroup; memory and process limits fall back to rlimits when `None`
+    pub cgroups: Option<Arc<CgroupParent>>,
+    /// Uid and gid to run the child as; it keeps the server's when `None`
+    pub identity: Option<SandboxIdentity>,
+    /// Namespaces to isolate the child in; it shares the server's view of the host when `None`
+    pub namespaces: Option<Arc<Namespaces>>,
+    /// Root filesystem to pivot into; needs `namespaces`. The child sees the host's filesystem when `None`
+    pub rootfs: Option<Arc<Rootfs>>,
+    /// Filesystem access the child is restricted to, besides read/write access to its working directory
+    pub landlock: Option<Arc<Landlock>>,
+    /// Seccomp filter installed right before exec
+    pub seccomp: Option<Arc<SyscallFilter>>,
+    /// Seccomp filter of the compile step, which runs the toolchain rather than the submitted code
+    pub compile_seccomp: Option<Arc<SyscallFilter>>,
+}
+
+/// One child of an execution: the compile step or the run
+struct Phase<'a> {
+    args: Vec<String>,
+    timeout_seconds: u32,
+    memory_limit_mb: u64,
+    input: &'a [String],
+    seccomp: Option<Arc<SyscallFilter>>,
+    /// The compile step, which alone may write to the host working directory
+    compile: bool,
+}
+
+/// Resource limits applied in the child between fork and exec
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    timeout_seconds: u64,
+    memory_bytes: u64,
+    max_processes: u64,
+    /// `cgroup.procs` of the execution cgroup, which then limits memory and processes
+    cgroup_procs: Option<RawFd>,
+}
+
+impl Limits {
+    /// Join the execution cgroup and set the rlimits of the calling process.
+    /// Runs in the forked child, so it must not allocate.
+    fn apply(self) -> nix::Result<()> {
+        if let Some(procs) = self.cgroup_procs {
+            // exec 전에 cgroup에 들어가야 자식이 만드는 모든 프로세스가 같은 cgroup에 속함
+            if unsafe { libc::write(procs, b"0".as_ptr().cast(), 1) } != 1 {
+                return Err(nix::errno::Errno::last());
+            }
+        } else {
+            // Set memory limit (heap and other private writable mappings). RLIMIT_AS would also
+            // count the address space runtimes such as V8 reserve up front, so node could not start.
+            setrlimit(Resource::RLIMIT_DATA, self.memory_bytes, self.memory_bytes)?;
+
+            // Set maximum number of processes (prevent fork bombs). Counted per uid, including
+            // threads, so it limits the execution alone when it has a sandbox uid of its own
+            setrlimit(Resource::RLIMIT_NPROC, self.max_processes, self.max_processes)?;
+        }
+
+        // Set CPU time limit (slightly higher than wall clock time)
+        setrlimit(Resource::RLIMIT_CPU, self.timeout_seconds + 1, self.timeout_seconds + 1)?;
+
+        // Disable core dumps
+        setrlimit(Resource::RLIMIT_CORE, 0, 0)?;
+
+        // Set maximum file size to prevent disk filling
+        setrlimit(Resource::RLIMIT_FSIZE, 50 * 1024 * 1024, 50 * 1024 * 1024)?;
+
+        // Set open file limit
+        setrlimit(Resource::RLIMIT_NOFILE, 100, 100)?;
+
+        Ok(())
+    }
 }
 
 /// Runner implementation for executing code in a controlled environment
@@ -54,163 +132,750 @@ impl Runner {
         Self { config }
     }
 
-    /// Execute the code with specified constraints
+    /// Execute the code with specified constraints.
+    ///
+    /// Compiled languages are first built in a compile step, a child of its own with the
+    /// manifest's compile limits whose output is captured apart from the run's; when it fails the
+    /// program never runs and the result is a `CompileError` with the compiler's output.
+    /// Both children share the working directory, so the run finds what the compile step left there.
+    ///
+    /// Each child runs in its own process group. Its stdin is fed and its stdout/stderr are
+    /// drained while it runs, so neither side can block on a full pipe, and the whole group
+    /// is killed when the child exits or the timeout passes, so no descendant outlives the run.
+    /// A descendant that left the group survives that without a cgroup, so once the child is gone
+    /// output is read only for a moment longer and anything after that is cut off as truncated.
+    /// Resource usage comes from `wait4`, which also reaps the child.
+    /// The child gets only `PATH`, `HOME`, `TMPDIR` and `LANG` from the environment, runs with
+    /// `no_new_privs` and, given an identity, as that uid and gid with no supplementary groups.
+    ///
+    /// With a cgroup parent configured the child runs in a cgroup of its own, which limits the
+    /// memory and processes of the whole run, lets it be killed even if a descendant left the
+    /// process group, and accounts peak memory, CPU time and OOM kills for all of it.
+    /// With namespaces configured it also runs isolated from the host's processes, network and mounts,
+    /// and with a root filesystem it sees only that, working in a tmpfs at `/workspace`
+    /// (what a compile step built is bound into it read-only; only the compile step writes to the host).
+    /// With Landlock configured it can only read the configured paths and write its working directory.
     pub async fn execute(&self) -> Result<ExecutionResult> {
-        let start_time = Instant::now();
-
-        // Create temporary file for code
+        if self.config.rootfs.is_some() && self.config.namespaces.is_none() {
+            return Err(anyhow!("A root filesystem needs namespaces"));
+        }
+        let manifest = &self.config.language;
+        // Create temporary directory for code
         let temp_dir = tempfile::Builder::new()
             .prefix("code-executor")
             .tempdir()?;
-        let code_path = temp_dir.path().join("code");
-
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace)?;
+        std::fs::set_permissions(&workspace, std::fs::Permissions::from_mode(0o700))?;
         // Write code to temporary file
-        std::fs::write(&code_path, &self.config.code)?;
-
-        // Prepare command based on language
-        let (cmd, args) = self.get_language_command(&code_path)?;
-
-        // Create pipes for stdout and stderr
-        let (stdout_read, stdout_write) = os_pipe::pipe()?;
-        let (stderr_read, stderr_write) = os_pipe::pipe()?;
-
-        // Convert pipe ends to stdio
-        let stdout_write = unsafe { Stdio::from_raw_fd(stdout_write.into_raw_fd()) };
-        let stderr_write = unsafe { Stdio::from_raw_fd(stderr_write.into_raw_fd()) };
-
-        // Fork process for isolation
-        match unsafe { fork()? } {
-            Child => {
-                // Child process: Set up restrictions and run code
-                if let Err(e) = self.setup_restrictions() {
-                    eprintln!("Failed to set up restrictions: {}", e);
-                    std::process::exit(1);
-                }
+        let source = workspace.join(&manifest.source_file);
+        std::fs::write(&source, &self.config.code)?;
+        let identity = self.config.identity.clone().unwrap_or_else(SandboxIdentity::current);
+        // 샌드박스 uid만 작업 디렉터리를 읽고 쓸 수 있음 (tempdir은 0700)
+        for path in [temp_dir.path(), &workspace, &source] {
+            std::os::unix::fs::chown(path, Some(identity.uid().as_raw()), Some(identity.gid().as_raw()))?;
+        }
+        if self.config.rootfs.is_some() {
+            std::fs::create_dir(temp_dir.path().join("root"))?;
+        }
 
-                let err = Command::new(cmd)
-                    .args(args)
-                    .stdout(stdout_write)
-                    .stderr(stderr_write)
-                    .exec(); // Replace current process
+        // Prepare commands based on language; inside a root filesystem the workspace is at /workspace
+        let workdir = match &self.config.rootfs {
+            Some(_) => Path::new(rootfs::WORKSPACE),
+            None => workspace.as_path(),
+        };
+        let commands = manifest.commands(workdir);
 
-                // If exec returns, it means it failed
-                eprintln!("Failed to execute command: {}", err);
-                std::process::exit(1);
+        let compile = match commands.compile {
+            Some(args) => {
+                let compile = self.run_phase(temp_dir.path(), &identity, Phase {
+                    args,
+                    timeout_seconds: manifest.compile_limits.timeout_seconds,
+                    memory_limit_mb: manifest.compile_limits.memory_limit_mb as u64,
+                    input: &[],
+                    seccomp: self.config.compile_seccomp.clone(),
+                    compile: true,
+                }).await?;
+                if compile.status != ExecutionStatus::Completed {
+                    return Ok(compile_error(compile));
+                }
+                Some(CompileResult::from(compile))
             }
-            Parent { child } => {
-                let mut result = ExecutionResult {
-                    status: ExecutionStatus::Completed,
-                    stdout: String::new(),
-                    stderr: String::new(),
-                    execution_time: 0.0,
-                    memory_used: 0,
-                    exit_code: 0,
+            None => None,
+        };
+
+        let mut result = self.run_phase(temp_dir.path(), &identity, Phase {
+            args: commands.run,
+            timeout_seconds: self.config.timeout_seconds,
+            memory_limit_mb: self.config.memory_limit_mb,
+            input: &self.config.input,
+            seccomp: self.config.seccomp.clone(),
+            compile: false,
+        }).await?;
+        result.compile = compile;
+
+        // Clean up temporary files
+        let _ = temp_dir.close();
+
+        Ok(result)
+    }
+
+    /// Run one child in the working directory `execute` prepared under `temp_dir`
+    async fn run_phase(&self, temp_dir: &Path, identity: &SandboxIdentity, phase: Phase<'_>) -> Result<ExecutionResult> {
+        let manifest = &self.config.language;
+        let (cmd, args) = phase.args.split_first().ok_or_else(|| anyhow!("Empty command for {}", manifest.key()))?;
+        let workspace = temp_dir.join("workspace");
+        let memory_bytes = phase.memory_limit_mb * 1024 * 1024;
+        let cgroup = match &self.config.cgroups {
+            Some(parent) => Some(parent.create(&format!("exec-{}", uuid::Uuid::new_v4()), memory_bytes)?),
+            None => None,
+        };
+        let limits = Limits {
+            timeout_seconds: phase.timeout_seconds as u64,
+            memory_bytes,
+            max_processes: self.config.max_processes as u64,
+            cgroup_procs: cgroup.as_ref().and_then(|cgroup| cgroup.procs_fd()),
+        };
+        let rootfs = match &self.config.rootfs {
+            Some(rootfs) => {
+                // 컴파일 단계만 호스트 디렉터리에 결과를 남기고, 실행은 그 결과를 읽기 전용으로 연결한 tmpfs에서 함
+                let mounted = if phase.compile {
+                    Workspace::Host(&workspace)
+                } else {
+                    Workspace::Tmpfs(&workspace)
                 };
+                Some(rootfs.mounts(&temp_dir.join("root"), mounted, identity.uid(), identity.gid())?)
+            }
+            None => None,
+        };
+        let (workdir, tmp) = match &rootfs {
+            Some(_) => (Path::new(rootfs::WORKSPACE), Path::new("/tmp")),
+            None => (workspace.as_path(), workspace.as_path()),
+        };
+        let writable = match &rootfs {
+            Some(_) => vec![workdir, tmp],
+            None => vec![workdir],
+        };
+        let landlock = self.config.landlock.as_ref().map(|landlock| landlock.rules(&writable)).transpose()?;
+        let protections = self.protections(cgroup.is_some(), phase.seccomp.is_some());
+        let namespaces = self.config.namespaces.clone();
+        let identity = identity.clone();
+        let seccomp = phase.seccomp.clone();
+        let mut command = Command::new(cmd);
+        // 서버의 환경 변수(자격 증명 등)는 넘기지 않음. 프로그램은 exec 시점의 PATH로 찾음
+        let path = match &rootfs {
+            Some(_) => rootfs::SANDBOX_PATH.into(),
+            None => std::env::var_os("PATH").unwrap_or_else(|| rootfs::SANDBOX_PATH.into()),
+        };
+        command
+            .env_clear()
+            .env("PATH", path)
+            .env("HOME", workdir)
+            .env("TMPDIR", tmp)
+            .env("LANG", "C.UTF-8")
+            .args(args)
+            .current_dir(&workspace)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // 자식을 새 프로세스 그룹의 리더로 만들어 자손까지 한 번에 kill
+            .process_group(0);
+        unsafe {
+            command.pre_exec(move || {
+                limits.apply()?;
+                identity.switch()?;
+                // exec한 프로그램이 setuid 바이너리 등으로 권한을 다시 얻지 못하도록 함
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if let Some(namespaces) = &namespaces {
+                    namespaces.enter(&identity, rootfs.as_ref())?;
+                }
+                // 네임스페이스 안에서 마운트한 뒤의 경로로 규칙을 만들어야 함
+                if let Some(landlock) = &landlock {
+                    landlock.restrict_self()?;
+                }
+                // 필터는 마운트와 네임스페이스 설정을 막으므로 exec 직전에 설치
+                if let Some(seccomp) = &seccomp {
+                    seccomp.install()?;
+                }
+                Ok(())
+            });
+        }
+
+        let mut child = command.spawn()?;
+        let started = Instant::now();
+        let pid = Pid::from_raw(child.id() as i32);
+        // 실행이 취소되거나 중간에 실패해도 프로세스 그룹이 남지 않도록 함
+        let group = KillGroupOnDrop(pid);
+
+        // wait4는 블로킹 호출이므로 전용 스레드에서 기다림. 자식은 여기서만 회수(reap)함
+        let mut waiter = tokio::task::spawn_blocking(move || wait4(pid));
+
+        // 입력 쓰기와 출력 읽기는 자식 실행과 동시에 진행해야 파이프 버퍼가 차도 멈추지 않음
+        let stdin = child.stdin.take().map(ChildStdin::from_std).transpose()?;
+        let stdout = child.stdout.take().map(ChildStdout::from_std).transpose()?;
+        let stderr = child.stderr.take().map(ChildStderr::from_std).transpose()?;
+        let input = stdin_bytes(phase.input);
+        let feeder = tokio::spawn(async move {
+            if let Some(mut stdin) = stdin {
+                // 자식이 입력을 다 읽지 않고 끝나면 EPIPE가 나므로 무시
+                let _ = stdin.write_all(&input).await;
+            }
+        });
+        let (stop_reading, reading) = watch::channel(false);
+        let stdout = tokio::spawn(read_capped(stdout, self.config.max_output_bytes, reading.clone()));
+        let stderr = tokio::spawn(read_capped(stderr, self.config.max_output_bytes, reading));
+
+        // Wait for child with timeout
+        let timeout = Duration::from_secs(phase.timeout_seconds as u64);
+        let (timed_out, waited) = match tokio::time::timeout(timeout, &mut waiter).await {
+            Ok(waited) => (false, waited),
+            Err(_) => {
+                let _ = killpg(pid, Signal::SIGKILL);
+                (true, waiter.await)
+            }
+        };
+        // 자식이 끝났어도 백그라운드로 남은 자손이 파이프를 잡고 있을 수 있으므로 그룹 전체를 종료
+        drop(group);
+        if let Some(cgroup) = &cgroup {
+            cgroup.kill();
+        }
+        let (exit, usage) = waited??;
+        let execution_time_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        // cgroup 없이 setsid 등으로 그룹을 벗어난 자손은 살아남아 파이프를 계속 열어 둘 수 있으므로
+        // 자식이 끝난 뒤에는 잠시만 더 읽고 멈춤
+        let drain_deadline = tokio::spawn(async move {
+            tokio::time::sleep(OUTPUT_DRAIN_TIMEOUT).await;
+            let _ = stop_reading.send(true);
+        });
+        let (stdout, stdout_truncated) = stdout.await??;
+        let (stderr, stderr_truncated) = stderr.await??;
+        drain_deadline.abort();
+        let mut result = ExecutionResult {
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+            output_truncated: stdout_truncated || stderr_truncated,
+            execution_time_ms,
+            memory_used_kb: usage.ru_maxrss.max(0) as u64, // Linux reports kilobytes
+            cpu_user_ms: timeval_ms(usage.ru_utime),
+            cpu_system_ms: timeval_ms(usage.ru_stime),
+            voluntary_context_switches: usage.ru_nvcsw.max(0) as u64,
+            involuntary_context_switches: usage.ru_nivcsw.max(0) as u64,
+            protections,
+            ..Default::default()
+        };
+        feeder.abort();
+
+        // Docker 실행 스크립트와 같이 시그널 종료는 128 + 시그널 번호를 종료 코드로 보고
+        match (exit.code(), exit.signal()) {
+            _ if timed_out => result.status = ExecutionStatus::Timeout,
+            (Some(code), _) => {
+                result.status = ExecutionStatus::from_exit_code(code);
+                result.exit_code = Some(code);
+            }
+            (None, Some(signal)) if signal == libc::SIGSYS && phase.seccomp.is_some() => {
+                result.status = ExecutionStatus::SecurityViolation;
+                result.exit_code = Some(128 + signal);
+                result.stderr.push_str("Process killed for a forbidden system call\n");
+            }
+            (None, Some(signal)) => {
+                result.status = ExecutionStatus::Failed;
+                result.exit_code = Some(128 + signal);
+                let signal = Signal::try_from(signal).map_or_else(|_| signal.to_string(), |signal| signal.to_string());
+                result.stderr.push_str(&format!("Process terminated by signal: {}\n", signal));
+            }
+            (None, None) => return Err(anyhow!("Child process ended without an exit status")),
+        }
+        if let Some(cgroup) = cgroup {
+            // cgroup은 rusage가 놓치는 자손(회수되지 않은 프로세스)까지 집계
+            let usage = cgroup.usage();
+            cgroup.destroy().await;
+            result.memory_used_kb = usage.memory_peak_kb.unwrap_or(result.memory_used_kb);
+            result.cpu_user_ms = result.cpu_user_ms.max(usage.cpu_user_ms);
+            result.cpu_system_ms = result.cpu_system_ms.max(usage.cpu_system_ms);
+            if usage.oom_kills > 0 && !timed_out {
+                result.status = ExecutionStatus::MemoryLimitExceeded;
+            }
+        } else if out_of_memory(&result, phase.memory_limit_mb) {
+            result.status = ExecutionStatus::MemoryLimitExceeded;
+        }
+
+        Ok(result)
+    }
+
+    /// Names of the protections a run with this configuration gets
+    fn protections(&self, cgroup: bool, seccomp: bool) -> Vec<String> {
+        let limits = if cgroup { "cgroup" } else { "rlimits" };
+        let isolation = [
+            ("namespaces", self.config.namespaces.is_some()),
+            ("rootfs", self.config.rootfs.is_some()),
+            ("landlock", self.config.landlock.is_some()),
+            ("seccomp", seccomp),
+        ];
+        std::iter::once(limits)
+            .chain(isolation.into_iter().filter(|(_, active)| *active).map(|(name, _)| name))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// The result of a failed compile step: the compiler's output and usage, reported as a compile error
+fn compile_error(compile: ExecutionResult) -> ExecutionResult {
+    let reason = match compile.status {
+        ExecutionStatus::Timeout => "Compilation exceeded its time limit\n",
+        ExecutionStatus::MemoryLimitExceeded => "Compilation exceeded its memory limit\n",
+        _ => "Compilation failed\n",
+    };
+    ExecutionResult {
+        status: ExecutionStatus::CompileError,
+        stderr: compile.stderr.clone() + reason,
+        compile: Some(CompileResult::from(compile.clone())),
+        ..compile
+    }
+}
+
+/// Input lines as written to stdin, each terminated by a newline
+fn stdin_bytes(input: &[String]) -> Vec<u8> {
+    input.iter().flat_map(|line| line.bytes().chain(std::iter::once(b'\n'))).collect()
+}
 
-                // Set up timeout
-                let timeout = Duration::from_secs(self.config.timeout_seconds as u64);
-                let timeout_instant = start_time + timeout;
-
-                // Read output asynchronously
-                let mut stdout_reader = std::io::BufReader::new(stdout_read);
-                let mut stderr_reader = std::io::BufReader::new(stderr_read);
-
-                // Wait for child with timeout
-                loop {
-                    match waitpid(child, None) {
-                        Ok(WaitStatus::Exited(_, code)) => {
-                            result.exit_code = code;
-                            break;
-                        }
-                        Ok(WaitStatus::Signaled(_, signal, _)) => {
-                            result.status = ExecutionStatus::RuntimeError;
-                            result.stderr = format!("Process terminated by signal: {}", signal);
-                            break;
-                        }
-                        Err(e) => {
-                            return Err(anyhow!("Error waiting for child process: {}", e));
-                        }
-                        _ => {
-                            if Instant::now() > timeout_instant {
-                                // Kill the process if it exceeded timeout
-                                let _ = signal::kill(child, Signal::SIGKILL);
-                                result.status = ExecutionStatus::TimeLimitExceeded;
-                                break;
-                            }
-                            std::thread::sleep(Duration::from_millis(10));
-                        }
-                    }
+/// The first `max_bytes` a child writes to one of its output pipes, and whether it wrote more.
+/// The pipe is drained to the end so the child never blocks on it; when `stop` turns true
+/// before the end, reading stops there and the output counts as truncated
+async fn read_capped(pipe: Option<impl AsyncRead + Unpin>, max_bytes: usize,
+                     mut stop: watch::Receiver<bool>) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut output = Vec::new();
+    let mut truncated = false;
+    if let Some(mut pipe) = pipe {
+        let mut buffer = [0; 8192];
+        loop {
+            let read = tokio::select! {
+                read = pipe.read(&mut buffer) => read?,
+                _ = stop.wait_for(|stop| *stop) => {
+                    truncated = true;
+                    break;
                 }
+            };
+            if read == 0 {
+                break;
+            }
+            let kept = read.min(max_bytes - output.len());
+            output.extend_from_slice(&buffer[..kept]);
+            truncated |= kept < read;
+        }
+    }
+    Ok((output, truncated))
+}
 
-                // Read remaining output
-                stdout_reader.read_to_string(&mut result.stdout)?;
-                stderr_reader.read_to_string(&mut result.stderr)?;
+/// Block until `pid` exits and return its exit status with the resource usage of it and
+/// every descendant it waited for
+fn wait4(pid: Pid) -> std::io::Result<(ExitStatus, libc::rusage)> {
+    let mut status = 0;
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    loop {
+        if unsafe { libc::wait4(pid.as_raw(), &mut status, 0, usage.as_mut_ptr()) } >= 0 {
+            return Ok((ExitStatus::from_raw(status), unsafe { usage.assume_init() }));
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
 
-                // Calculate execution time
-                result.execution_time = start_time.elapsed().as_secs_f64() * 1000.0; // Convert to milliseconds
+fn timeval_ms(time: libc::timeval) -> f64 {
+    time.tv_sec as f64 * 1000.0 + time.tv_usec as f64 / 1000.0
+}
+
+/// Kills a process group when dropped
+struct KillGroupOnDrop(Pid);
+
+impl Drop for KillGroupOnDrop {
+    fn drop(&mut self) {
+        let _ = killpg(self.0, Signal::SIGKILL);
+    }
+}
+
+/// A failed run that used nearly all of its memory under rlimits ran out of it. Only the usage
+/// the kernel accounted counts, like the OOM kills of a cgroup or container: what the program
+/// printed proves nothing
+fn out_of_memory(result: &ExecutionResult, memory_limit_mb: u64) -> bool {
+    result.status == ExecutionStatus::Failed
+        && result.memory_used_kb * 100 >= memory_limit_mb * 1024 * OUT_OF_MEMORY_PEAK_PERCENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn runner(run: &str, code: &str, input: &[&str]) -> Runner {
+        let manifest: LanguageManifest = toml::from_str(&format!(r#"
+            language = "sh"
+            version = "1"
+            image = "unused"
+            source_file = "main.sh"
+            run = ["{}", "{{source}}"]
+        "#, run)).unwrap();
+        Runner::new(ExecutionConfig {
+            timeout_seconds: 5,
+            memory_limit_mb: 256,
+            language: Arc::new(manifest),
+            code: code.to_string(),
+            input: input.iter().map(|line| line.to_string()).collect(),
+            max_processes: 64,
+            max_output_bytes: 1024 * 1024,
+            cgroups: None,
+            identity: None,
+            namespaces: None,
+            rootfs: None,
+            landlock: None,
+            seccomp: None,
+            compile_seccomp: None,
+        })
+    }
+
+    /// A runner that compiles `code` with `sh -c compile` and runs the `main` it leaves in the working directory
+    fn compiled(compile: &str, code: &str) -> Runner {
+        let mut runner = runner("/bin/sh", code, &[]);
+        let mut manifest = (*runner.config.language).clone();
+        manifest.compile = Some(["/bin/sh", "-c", compile, "sh", "{source}", "{workdir}/main"].map(str::to_string).to_vec());
+        manifest.run = vec!["{workdir}/main".to_string()];
+        runner.config.language = Arc::new(manifest);
+        runner
+    }
+
+    /// `runner` isolated in namespaces as a sandbox uid, or `None` where the host does not allow it
+    fn isolated(mut runner: Runner) -> Option<Runner> {
+        let identity = Arc::new(SandboxIds::new(65534, 65534, 1)).acquire().unwrap();
+        if let Err(e) = Namespaces.probe(&identity) {
+            eprintln!("skipping, namespaces are not available: {:#}", e);
+            return None;
+        }
+        runner.config.namespaces = Some(Arc::new(Namespaces));
+        runner.config.identity = Some(identity);
+        Some(runner)
+    }
+
+    #[tokio::test]
+    async fn feeds_input_lines_to_stdin() {
+        let result = runner("/bin/sh", "read a; read b; echo \"$b $a\"; exit 3", &["1", "2"]).execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.stdout, "2 1\n");
+        assert_eq!(result.exit_code, Some(3));
+        assert!(result.memory_used_kb > 0);
+    }
+
+    #[tokio::test]
+    async fn fatal_signals_fail_with_the_exit_code_a_shell_reports() {
+        let result = runner("/bin/sh", "kill -SEGV $$", &[]).execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.exit_code, Some(128 + libc::SIGSEGV));
+        assert!(result.stderr.contains("SIGSEGV"), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn reports_cpu_time_of_the_child_and_its_descendants() {
+        let code = "sh -c 'i=0; while [ $i -lt 100000 ]; do i=$((i+1)); done'";
+        let result = runner("/bin/sh", code, &[]).execute().await.unwrap();
 
-                // Clean up temporary files
-                let _ = temp_dir.close();
+        let cpu_time = result.cpu_user_ms + result.cpu_system_ms;
+        assert!(cpu_time > 50.0, "{:?}", result);
+        assert!(result.execution_time_ms >= cpu_time * 0.9, "{:?}", result);
+        assert!(result.voluntary_context_switches + result.involuntary_context_switches > 0);
+    }
+
+    #[tokio::test]
+    async fn drains_output_larger_than_the_pipe_buffer() {
+        let result = runner("/bin/sh", "head -c 1000000 /dev/zero | tr '\\0' x; echo done >&2", &[]).execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.stdout.len(), 1_000_000);
+        assert_eq!(result.stderr, "done\n");
+        assert!(!result.output_truncated);
+    }
+
+    #[tokio::test]
+    async fn keeps_only_max_output_bytes_of_each_stream() {
+        let mut noisy = runner("/bin/sh", "head -c 5000000 /dev/zero | tr '\\0' x; echo done >&2", &[]);
+        noisy.config.max_output_bytes = 1000;
+        let result = noisy.execute().await.unwrap();
+
+        // 나머지 출력도 끝까지 읽어서 버리므로 자식은 막히지 않고 정상 종료
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert_eq!(result.stdout, "x".repeat(1000));
+        assert_eq!(result.stderr, "done\n");
+        assert!(result.output_truncated);
 
-                Ok(result)
+        let mut exact = runner("/bin/sh", "printf 12345 >&2", &[]);
+        exact.config.max_output_bytes = 5;
+        let result = exact.execute().await.unwrap();
+        assert_eq!(result.stderr, "12345");
+        assert!(!result.output_truncated);
+    }
+
+    /// Whether `pid` is still alive a moment after being killed; SIGKILL is delivered asynchronously
+    async fn is_running(pid: &str) -> bool {
+        let alive = || std::fs::read_to_string(format!("/proc/{}/status", pid.trim()))
+            .is_ok_and(|status| !status.lines().any(|line| line.starts_with("State:") && line.contains('Z')));
+        for _ in 0..50 {
+            if !alive() {
+                return false;
             }
+            tokio::time::sleep(Duration::from_millis(20)).await;
         }
+        true
     }
 
-    /// Set up security restrictions for the child process
-    fn setup_restrictions(&self) -> Result<()> {
-        // Set memory limit (virtual memory)
-        setrlimit(
-            Resource::RLIMIT_AS,
-            self.config.memory_limit_mb * 1024 * 1024,
-            self.config.memory_limit_mb * 1024 * 1024,
-        )?;
+    #[tokio::test]
+    async fn background_processes_do_not_outlive_the_run() {
+        let started = Instant::now();
+        let result = runner("/bin/sh", "sleep 30 & echo $!", &[]).execute().await.unwrap();
 
-        // Set CPU time limit (slightly higher than wall clock time)
-        setrlimit(
-            Resource::RLIMIT_CPU,
-            self.config.timeout_seconds as u64 + 1,
-            self.config.timeout_seconds as u64 + 1,
-        )?;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert!(!is_running(&result.stdout).await);
+    }
 
-        // Disable core dumps
-        setrlimit(Resource::RLIMIT_CORE, 0, 0)?;
+    #[tokio::test]
+    async fn descendants_that_leave_the_group_do_not_hold_the_run() {
+        let started = Instant::now();
+        let result = runner("/bin/sh", "setsid sleep 30 & echo $!", &[]).execute().await.unwrap();
+        let escaped = result.stdout.trim().to_string();
+        let _ = nix::sys::signal::kill(Pid::from_raw(escaped.parse().unwrap()), Signal::SIGKILL);
 
-        // Set maximum file size to prevent disk filling
-        setrlimit(Resource::RLIMIT_FSIZE, 50 * 1024 * 1024, 50 * 1024 * 1024)?;
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(result.status, ExecutionStatus::Completed);
+        assert!(result.output_truncated);
+    }
 
-        // Set maximum number of processes (prevent fork bombs)
-        setrlimit(
-            Resource::RLIMIT_NPROC,
-            10,  // Allow a few processes
-            10,
-        )?;
+    #[tokio::test]
+    async fn timeouts_kill_the_whole_process_group() {
+        let mut runner = runner("/bin/sh", "sleep 30 & echo $!; wait", &[]);
+        runner.config.timeout_seconds = 1;
+        let started = Instant::now();
+        let result = runner.execute().await.unwrap();
 
-        // Set open file limit
-        setrlimit(Resource::RLIMIT_NOFILE, 100, 100)?;
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(result.status, ExecutionStatus::Timeout);
+        assert_eq!(result.exit_code, None);
+        assert!(!is_running(&result.stdout).await);
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn isolates_the_child_in_its_own_namespaces() {
+        let code = format!("echo $$; cat /proc/sys/kernel/hostname; id -u; test -e /proc/{} && echo host; \
+                            tail -n +3 /proc/net/dev | cut -d: -f1 | tr -d ' '; exit 3", std::process::id());
+        let Some(runner) = isolated(runner("/bin/sh", &code, &[])) else {
+            return;
+        };
+        let uid = runner.config.identity.as_ref().unwrap().uid();
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Failed, "{:?}", result);
+        assert_eq!(result.stdout, format!("1\nsandbox\n{}\nlo\n", uid));
+        assert_eq!(result.exit_code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn timeouts_kill_isolated_runs() {
+        let Some(mut runner) = isolated(runner("/bin/sh", "sleep 30", &[])) else {
+            return;
+        };
+        runner.config.timeout_seconds = 1;
+        let started = Instant::now();
+        let result = runner.execute().await.unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(result.status, ExecutionStatus::Timeout);
+    }
+
+    #[tokio::test]
+    async fn forbidden_system_calls_are_security_violations() {
+        let code = "echo before; exec unshare --user true";
+        let mut runner = isolated(runner("/bin/sh", code, &[])).unwrap_or_else(|| runner("/bin/sh", code, &[]));
+        runner.config.seccomp = Some(Arc::new(crate::security::SeccompProfile::default().filter()));
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::SecurityViolation, "{:?}", result);
+        assert_eq!(result.stdout, "before\n");
+        assert_eq!(result.exit_code, Some(128 + libc::SIGSYS));
+    }
+
+    #[tokio::test]
+    async fn rootfs_hides_the_host_filesystem() {
+        let code = "pwd; ls /; cat main.sh > copy && echo copied; touch /usr/new || echo read-only; echo > main.sh || echo code read-only";
+        let Some(mut runner) = isolated(runner("sh", code, &[])) else {
+            return;
+        };
+        runner.config.rootfs = Some(Arc::new(Rootfs::host(&["/usr", "/lib", "/lib64", "/bin"].map(PathBuf::from), 16)));
+        let result = runner.execute().await.unwrap();
+
+        let lines: Vec<_> = result.stdout.lines().collect();
+        assert_eq!(lines.first(), Some(&"/workspace"), "{:?}", result);
+        assert_eq!(lines[lines.len() - 3..], ["copied", "read-only", "code read-only"]);
+        let root = &lines[1..lines.len() - 3];
+        for entry in ["bin", "dev", "proc", "tmp", "usr", "workspace"] {
+            assert!(root.contains(&entry), "{:?}", root);
+        }
+        assert!(!root.contains(&"root") && !root.contains(&"etc"), "{:?}", root);
+        assert_eq!(result.protections, ["rlimits", "namespaces", "rootfs"]);
     }
 
-    /// Get the appropriate command and arguments for the specified language
-    fn get_language_command(&self, code_path: &Path) -> Result<(&str, Vec<String>)> {
-        // Add file extension based on language
-        let code_path_with_ext = match self.config.language.as_str() {
-            "python" | "python3" => code_path.with_extension("py"),
-            "javascript" | "node" => code_path.with_extension("js"),
-            "ruby" => code_path.with_extension("rb"),
-            _ => code_path.to_path_buf(),
+    #[tokio::test]
+    async fn drops_privileges_and_scrubs_the_environment() {
+        if !nix::unistd::Uid::effective().is_root() {
+            eprintln!("Not root, skipping");
+            return;
+        }
+        std::env::set_var("CODE_EXECUTOR_TEST_SECRET", "secret");
+        let code = "id -u; id -g; id -G; env | cut -d= -f1 | sort | tr '\\n' ' '; echo; grep NoNewPrivs /proc/self/status | tr -d '\\t'";
+        let mut runner = runner("/bin/sh", code, &[]);
+        runner.config.identity = Some(Arc::new(SandboxIds::new(65000, 65100, 4)).acquire().unwrap());
+        let result = runner.execute().await.unwrap();
+
+        // sh는 PWD, SHLVL 등을 직접 설정함
+        let lines: Vec<_> = result.stdout.lines().collect();
+        assert_eq!(lines[..3], ["65000", "65100", "65100"], "{:?}", result);
+        assert!(lines[3].starts_with("HOME LANG PATH "), "{:?}", result);
+        assert!(!lines[3].contains("CODE_EXECUTOR_TEST_SECRET"));
+        assert_eq!(lines[4], "NoNewPrivs:1");
+    }
+
+    #[tokio::test]
+    async fn landlock_confines_the_child_to_its_working_directory() {
+        let Ok(landlock) = Landlock::supported(["/usr", "/bin", "/lib", "/lib64", "/etc"].map(PathBuf::from).to_vec()) else {
+            eprintln!("Landlock is not supported here, skipping");
+            return;
         };
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret"), "secret").unwrap();
+        let code = format!("echo ok > out; cat out; cat {}/secret", outside.path().display());
+        let mut runner = runner("/bin/sh", &code, &[]);
+        runner.config.landlock = Some(Arc::new(landlock));
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.stdout, "ok\n");
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert!(result.stderr.contains("Permission denied"), "{:?}", result);
+        assert_eq!(result.protections, ["rlimits", "landlock"]);
+    }
+
+    #[tokio::test]
+    async fn runs_what_the_compile_step_built() {
+        let compile = r#"echo compiling; echo warning >&2; { echo '#!/bin/sh'; cat "$1"; } > "$2" && chmod +x "$2""#;
+        let mut runner = compiled(compile, "read a; echo \"ran $a\"");
+        runner.config.input = vec!["input".to_string()];
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Completed, "{:?}", result);
+        assert_eq!(result.stdout, "ran input\n");
+        assert_eq!(result.stderr, "");
+        let compile = result.compile.unwrap();
+        assert_eq!(compile.status, ExecutionStatus::Completed);
+        assert_eq!((compile.stdout.as_str(), compile.stderr.as_str()), ("compiling\n", "warning\n"));
+    }
+
+    #[tokio::test]
+    async fn compile_failures_are_compile_errors() {
+        let result = compiled("echo \"$1:1: error: unexpected token\" >&2; exit 1", "").execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::CompileError, "{:?}", result);
+        assert!(result.stderr.starts_with("/"), "{:?}", result);
+        assert!(result.stderr.ends_with("main.sh:1: error: unexpected token\nCompilation failed\n"), "{:?}", result);
+        let compile = result.compile.unwrap();
+        assert_eq!(compile.status, ExecutionStatus::Failed);
+        assert_eq!(compile.exit_code, Some(1));
+    }
+
+    #[tokio::test]
+    async fn the_compile_step_has_limits_of_its_own() {
+        let mut runner = compiled("sleep 30", "");
+        let mut manifest = (*runner.config.language).clone();
+        manifest.compile_limits.timeout_seconds = 1;
+        runner.config.language = Arc::new(manifest);
+        let started = Instant::now();
+        let result = runner.execute().await.unwrap();
 
-        // Rename the file with proper extension
-        std::fs::rename(code_path, &code_path_with_ext)?;
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(result.status, ExecutionStatus::CompileError, "{:?}", result);
+        assert_eq!(result.compile.unwrap().status, ExecutionStatus::Timeout);
+    }
 
-        match self.config.language.as_str() {
-            "python" | "python3" => Ok(("python3", vec![code_path_with_ext.to_str().unwrap().to_string()])),
-            "javascript" | "node" => Ok(("node", vec![code_path_with_ext.to_str().unwrap().to_string()])),
-            "ruby" => Ok(("ruby", vec![code_path_with_ext.to_str().unwrap().to_string()])),
-            // Add more languages here
-            _ => Err(anyhow!("Unsupported language: {}", self.config.language)),
+    #[tokio::test]
+    async fn compiles_c_inside_the_root_filesystem() {
+        if find_gcc().is_none() {
+            eprintln!("gcc is not installed, skipping");
+            return;
         }
+        let code = "#include <stdio.h>\nint main(void) { char name[16]; scanf(\"%15s\", name); printf(\"hello %s\\n\", name); return 0; }\n";
+        let Some(mut runner) = isolated(runner("/bin/sh", code, &["world"])) else {
+            return;
+        };
+        let mut manifest = (*runner.config.language).clone();
+        manifest.source_file = "main.c".to_string();
+        manifest.compile = Some(["gcc", "-O2", "-o", "{workdir}/main", "{source}"].map(str::to_string).to_vec());
+        manifest.run = vec!["{workdir}/main".to_string()];
+        runner.config.language = Arc::new(manifest);
+        runner.config.rootfs = Some(Arc::new(Rootfs::host(&["/usr", "/lib", "/lib64", "/bin"].map(PathBuf::from), 16)));
+        runner.config.seccomp = Some(Arc::new(crate::security::SeccompProfile::default().filter()));
+        runner.config.compile_seccomp = runner.config.seccomp.clone();
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Completed, "{:?}", result);
+        assert_eq!(result.stdout, "hello world\n");
+        assert_eq!(result.compile.unwrap().exit_code, Some(0));
+    }
+
+    #[tokio::test]
+    async fn compiled_runs_write_only_to_the_size_limited_workspace() {
+        let compile = r#"{ echo '#!/bin/sh'; cat "$1"; } > "$2" && chmod +x "$2""#;
+        let code = "head -c 2000000 /dev/zero > big 2>/dev/null || echo full; { echo x >> /workspace/main; } 2>/dev/null || echo read-only";
+        let Some(mut runner) = isolated(compiled(compile, code)) else {
+            return;
+        };
+        runner.config.rootfs = Some(Arc::new(Rootfs::host(&["/usr", "/lib", "/lib64", "/bin"].map(PathBuf::from), 1)));
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Completed, "{:?}", result);
+        assert_eq!(result.stdout, "full\nread-only\n");
+    }
+
+    fn find_gcc() -> Option<PathBuf> {
+        std::env::split_paths(&std::env::var_os("PATH")?).map(|dir| dir.join("gcc")).find(|path| path.exists())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn failures_near_the_memory_limit_exceed_it() {
+        let result = |status, memory_used_kb| ExecutionResult {
+            status,
+            memory_used_kb,
+            ..Default::default()
+        };
+
+        assert!(out_of_memory(&result(ExecutionStatus::Failed, 200 * 1024), 256));
+        assert!(!out_of_memory(&result(ExecutionStatus::Failed, 100 * 1024), 256));
+        assert!(!out_of_memory(&result(ExecutionStatus::Completed, 250 * 1024), 256));
+    }
+
+    #[tokio::test]
+    async fn allocation_failures_under_rlimits_exceed_the_memory_limit() {
+        let mut runner = runner("/bin/bash", "x=a; while :; do x=$x$x; done", &[]);
+        runner.config.memory_limit_mb = 64;
+        let result = runner.execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::MemoryLimitExceeded, "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn printing_out_of_memory_messages_is_not_running_out_of_memory() {
+        let code = "echo 'java.lang.OutOfMemoryError'; echo MemoryError >&2; exit 1";
+        let result = runner("/bin/sh", code, &[]).execute().await.unwrap();
+
+        assert_eq!(result.status, ExecutionStatus::Failed);
+    }
+}
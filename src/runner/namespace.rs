@@ -0,0 +1,134 @@
+use std::ffi::CStr;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use nix::errno::Errno;
+use nix::mount::{mount, MsFlags};
+use nix::sched::{unshare, CloneFlags};
+use nix::sys::signal::{kill, Signal};
+use nix::sys::wait::{waitpid, WaitStatus};
+use nix::unistd::{fork, getpid, sethostname, ForkResult, Pid};
+
+use super::identity::SandboxIdentity;
+use super::rootfs::RootfsMounts;
+
+/// Hostname the child sees in its UTS namespace
+const HOSTNAME: &str = "sandbox";
+
+/// Puts an execution in new user, PID, mount, network, IPC and UTS namespaces, like nsjail:
+/// it is PID 1 of its own PID namespace with a fresh `/proc`, sees only a loopback interface
+/// and runs as its sandbox uid mapped to itself, so it has no capabilities after exec.
+#[derive(Debug, Clone, Default)]
+pub struct Namespaces;
+
+impl Namespaces {
+    /// Check that this host lets the server create the namespaces by running `true` in them as `identity`
+    pub fn probe(&self, identity: &SandboxIdentity) -> Result<()> {
+        let namespaces = self.clone();
+        let identity = identity.clone();
+        let mut command = Command::new("true");
+        unsafe {
+            command.pre_exec(move || {
+                identity.switch()?;
+                namespaces.enter(&identity, None).map_err(std::io::Error::from)
+            });
+        }
+        let status = command.status().context("Failed to start a process in new namespaces")?;
+        if !status.success() {
+            return Err(anyhow!("Process in new namespaces exited with {}", status));
+        }
+        Ok(())
+    }
+
+    /// Enter the namespaces. Runs in the forked child between fork and exec, so it must not allocate.
+    ///
+    /// A PID namespace only applies to children of the process that creates it, so this forks
+    /// once more: it returns in the grandchild, PID 1 of the new namespace, which goes on to exec.
+    /// The child stays behind to reap it and exits the same way, so the runner's `wait4` sees
+    /// the program's status and resource usage.
+    ///
+    /// The caller must already run as `identity`, which is mapped to itself.
+    /// With `rootfs` the grandchild pivots into it and never sees the host's filesystem.
+    pub fn enter(&self, identity: &SandboxIdentity, rootfs: Option<&RootfsMounts>) -> nix::Result<()> {
+        unshare(CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_NEWPID | CloneFlags::CLONE_NEWNS
+            | CloneFlags::CLONE_NEWNET | CloneFlags::CLONE_NEWIPC | CloneFlags::CLONE_NEWUTS)?;
+        // 권한 없는 프로세스는 gid_map 전에 setgroups를 막아야 함
+        write_file(c"/proc/self/setgroups", b"deny")?;
+        write_file(c"/proc/self/uid_map", &identity.uid_map)?;
+        write_file(c"/proc/self/gid_map", &identity.gid_map)?;
+
+        match unsafe { fork() }? {
+            ForkResult::Child => init(rootfs),
+            ForkResult::Parent { child } => reap(child),
+        }
+    }
+}
+
+/// Set up the namespaces from inside as PID 1, before exec drops the capabilities the
+/// new user namespace granted
+fn init(rootfs: Option<&RootfsMounts>) -> nix::Result<()> {
+    // 마운트 변경이 호스트로 전파되지 않도록 함
+    mount(None::<&str>, "/", None::<&str>, MsFlags::MS_REC | MsFlags::MS_PRIVATE, None::<&str>)?;
+    match rootfs {
+        Some(rootfs) => rootfs.pivot()?,
+        None => mount(Some("proc"), "/proc", Some("proc"), MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC, None::<&str>)?,
+    }
+    sethostname(HOSTNAME)?;
+    loopback_up()
+}
+
+/// Bring up `lo`, the only interface in a new network namespace
+fn loopback_up() -> nix::Result<()> {
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if socket < 0 {
+        return Err(Errno::last());
+    }
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    request.ifr_name[0] = b'l' as libc::c_char;
+    request.ifr_name[1] = b'o' as libc::c_char;
+    request.ifr_ifru.ifru_flags = (libc::IFF_UP | libc::IFF_LOOPBACK | libc::IFF_RUNNING) as libc::c_short;
+    let result = unsafe { libc::ioctl(socket, libc::SIOCSIFFLAGS, &request) };
+    let error = Errno::last();
+    unsafe { libc::close(socket) };
+    if result < 0 {
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Wait for PID 1 of the namespace and exit with its status
+fn reap(child: Pid) -> ! {
+    // spawn은 exec 오류 파이프가 모두 닫힐 때까지 기다리므로 exec 하지 않는 이 프로세스는 모든 fd를 닫음
+    unsafe { libc::close_range(0, libc::c_uint::MAX, 0) };
+    let status = loop {
+        match waitpid(child, None) {
+            Err(Errno::EINTR) => continue,
+            status => break status,
+        }
+    };
+    match status {
+        Ok(WaitStatus::Exited(_, code)) => unsafe { libc::_exit(code) },
+        Ok(WaitStatus::Signaled(_, signal, _)) => {
+            // 같은 시그널로 종료해야 러너가 시그널 종료로 판정
+            unsafe { libc::signal(signal as libc::c_int, libc::SIG_DFL) };
+            let _ = kill(getpid(), signal);
+            unsafe { libc::_exit(128 + signal as i32) }
+        }
+        _ => unsafe { libc::_exit(128 + Signal::SIGKILL as i32) },
+    }
+}
+
+/// `std::fs::write` without allocating
+fn write_file(path: &CStr, contents: &[u8]) -> nix::Result<()> {
+    let fd = unsafe { libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(Errno::last());
+    }
+    let written = unsafe { libc::write(fd, contents.as_ptr().cast(), contents.len()) };
+    let error = Errno::last();
+    unsafe { libc::close(fd) };
+    if written != contents.len() as isize {
+        return Err(error);
+    }
+    Ok(())
+}
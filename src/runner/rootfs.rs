@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use nix::errno::Errno;
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use nix::sys::stat::Mode;
+use nix::sys::statvfs::{statvfs, FsFlags};
+use nix::unistd::{chdir, mkdir, pivot_root, Gid, Uid};
+
+/// Working directory of executions inside their root filesystem, holding the code file
+pub const WORKSPACE: &str = "/workspace";
+/// `PATH` inside the root filesystem, the default of Debian based images
+pub const SANDBOX_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Top-level entries of an exported image that get fresh mounts instead
+const IMAGE_SKIPPED: &[&str] = &["dev", "proc", "sys", "tmp", "workspace"];
+/// Device files bound from the host's `/dev`
+const DEVICES: &[&str] = &["null", "zero", "full", "random", "urandom"];
+const DEVICE_LINKS: &[(&str, &str)] = &[
+    ("/proc/self/fd", "/dev/fd"),
+    ("/proc/self/fd/0", "/dev/stdin"),
+    ("/proc/self/fd/1", "/dev/stdout"),
+    ("/proc/self/fd/2", "/dev/stderr"),
+];
+
+/// What an execution finds at `/workspace`
+#[derive(Debug, Clone, Copy)]
+pub enum Workspace<'a> {
+    /// A fresh size-limited tmpfs with everything in a host directory bound read-only into it:
+    /// the code file, and for the run of a compiled language what the compile step built
+    Tmpfs(&'a Path),
+    /// A host directory bound read/write, where a compile step leaves what it built for the run
+    Host(&'a Path),
+}
+
+/// Root filesystem native executions pivot into instead of seeing the host's: a read-only
+/// tmpfs holding read-only bind mounts of the toolchain, a fresh `/proc`, a few device files
+/// and size-limited tmpfs mounts for the workspace and `/tmp`
+#[derive(Debug, Clone)]
+pub struct Rootfs {
+    /// Host paths and where they appear inside the root filesystem
+    binds: Vec<(PathBuf, PathBuf)>,
+    workspace_size_mb: u32,
+}
+
+impl Rootfs {
+    /// Host paths such as `/usr` mounted at the same place; missing ones are left out.
+    /// A `*` in the last component matches any name, e.g. `/etc/java-*` for the configuration of every JDK
+    pub fn host(paths: &[PathBuf], workspace_size_mb: u32) -> Self {
+        Self {
+            binds: paths.iter().flat_map(|path| expand(path)).map(|path| (path.clone(), path)).collect(),
+            workspace_size_mb,
+        }
+    }
+
+    /// An image exported to `dir`, e.g. with `docker export`, mounted at the root
+    pub fn image(dir: &Path, workspace_size_mb: u32) -> Result<Self> {
+        let mut binds = Vec::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read root filesystem {}", dir.display()))? {
+            let name = entry?.file_name();
+            if !IMAGE_SKIPPED.iter().any(|skipped| name == *skipped) {
+                binds.push((dir.join(&name), Path::new("/").join(name)));
+            }
+        }
+        binds.sort();
+        Ok(Self { binds, workspace_size_mb })
+    }
+
+    /// The mounts of one execution: the root filesystem is assembled on the empty directory `staging`
+    pub fn mounts(&self, staging: &Path, workspace: Workspace, uid: Uid, gid: Gid) -> Result<RootfsMounts> {
+        let mut plan = Plan { staging, steps: Vec::new(), dirs: BTreeSet::new() };
+        plan.push(Step::Tmpfs(plan.path("/")?, c"size=1m,mode=755".to_owned()));
+        for (host, target) in &self.binds {
+            let Ok(metadata) = std::fs::symlink_metadata(host) else {
+                continue;
+            };
+            if metadata.is_symlink() {
+                plan.symlink(&std::fs::read_link(host)?, target)?;
+            } else {
+                plan.bind(host, target, metadata.is_dir(), false)?;
+            }
+        }
+
+        for device in DEVICES {
+            plan.bind(&Path::new("/dev").join(device), &Path::new("/dev").join(device), false, false)?;
+        }
+        for (target, link) in DEVICE_LINKS {
+            plan.symlink(Path::new(target), Path::new(link))?;
+        }
+        plan.dir(Path::new("/proc"))?;
+        plan.push(Step::Proc(plan.path("/proc")?));
+
+        let size = format!("size={}m,uid={},gid={}", self.workspace_size_mb, uid, gid);
+        match workspace {
+            Workspace::Tmpfs(dir) => {
+                plan.dir(Path::new(WORKSPACE))?;
+                plan.push(Step::Tmpfs(plan.path(WORKSPACE)?, CString::new(format!("{},mode=700", size))?));
+                // 실행이 쓰는 것은 모두 크기 제한이 있는 tmpfs에 남고, 호스트의 파일은 읽기만 가능
+                let mut names: Vec<_> = std::fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.file_name())).collect::<std::io::Result<_>>()?;
+                names.sort();
+                for name in names {
+                    let (host, target) = (dir.join(&name), Path::new(WORKSPACE).join(&name));
+                    let metadata = std::fs::symlink_metadata(&host)?;
+                    // 심볼릭 링크는 따라가서 바인드하면 호스트 경로가 드러나므로 샌드박스 안에 다시 만듦
+                    if metadata.is_symlink() {
+                        plan.symlink(&std::fs::read_link(&host)?, &target)?;
+                    } else if metadata.is_dir() || metadata.is_file() {
+                        plan.bind(&host, &target, metadata.is_dir(), false)?;
+                    }
+                }
+            }
+            Workspace::Host(dir) => plan.bind(dir, Path::new(WORKSPACE), true, true)?,
+        }
+        plan.dir(Path::new("/tmp"))?;
+        plan.push(Step::Tmpfs(plan.path("/tmp")?, CString::new(format!("{},mode=1777", size))?));
+
+        Ok(RootfsMounts {
+            root: plan.path("/")?,
+            workspace: CString::new(WORKSPACE)?,
+            steps: plan.steps,
+        })
+    }
+}
+
+/// The paths `path` matches, itself unless its last component has a `*`
+fn expand(path: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some((prefix, suffix))) = (path.parent(), path.file_name().and_then(|name| name.to_str()?.split_once('*'))) else {
+        return vec![path.to_path_buf()];
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<_> = entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix))
+        .map(|name| parent.join(name))
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Builds the steps of `RootfsMounts`, creating every mount point's parent directories first
+struct Plan<'a> {
+    staging: &'a Path,
+    steps: Vec<Step>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl Plan<'_> {
+    fn push(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    /// `target` inside the staging directory
+    fn path(&self, target: impl AsRef<Path>) -> Result<CString> {
+        let relative = target.as_ref().strip_prefix("/").map_err(|_| anyhow!("{} is not absolute", target.as_ref().display()))?;
+        if relative.components().any(|component| !matches!(component, Component::Normal(_))) {
+            return Err(anyhow!("{} is not a normal path", target.as_ref().display()));
+        }
+        Ok(CString::new(self.staging.join(relative).as_os_str().as_bytes())?)
+    }
+
+    fn dir(&mut self, target: &Path) -> Result<()> {
+        if let Some(parent) = target.parent() {
+            self.dir(parent)?;
+        }
+        if target != Path::new("/") && self.dirs.insert(target.to_path_buf()) {
+            let path = self.path(target)?;
+            self.push(Step::Dir(path));
+        }
+        Ok(())
+    }
+
+    fn symlink(&mut self, destination: &Path, link: &Path) -> Result<()> {
+        self.dir(link.parent().unwrap_or(Path::new("/")))?;
+        let destination = CString::new(destination.as_os_str().as_bytes())?;
+        let link = self.path(link)?;
+        self.push(Step::Symlink(destination, link));
+        Ok(())
+    }
+
+    fn bind(&mut self, host: &Path, target: &Path, is_dir: bool, writable: bool) -> Result<()> {
+        if is_dir {
+            self.dir(target)?;
+        } else {
+            self.dir(target.parent().unwrap_or(Path::new("/")))?;
+            let path = self.path(target)?;
+            self.push(Step::File(path));
+        }
+        // 사용자 네임스페이스에서는 원래 마운트의 nosuid, nodev 등을 유지해야 읽기 전용으로 다시 마운트할 수 있음
+        let flags = statvfs(host).with_context(|| format!("Failed to stat {}", host.display()))?.flags();
+        let locked = [
+            (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+            (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+            (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+            (FsFlags::ST_NOATIME, MsFlags::MS_NOATIME),
+            (FsFlags::ST_NODIRATIME, MsFlags::MS_NODIRATIME),
+            (FsFlags::ST_RELATIME, MsFlags::MS_RELATIME),
+        ];
+        let read_only = if writable { MsFlags::empty() } else { MsFlags::MS_RDONLY };
+        let remount = locked.into_iter()
+            .filter(|(flag, _)| flags.contains(*flag))
+            .fold(MsFlags::MS_NOSUID | read_only, |remount, (_, flag)| remount | flag);
+        let source = CString::new(host.as_os_str().as_bytes())?;
+        let target = self.path(target)?;
+        self.push(Step::Bind { source, target, remount });
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Step {
+    Dir(CString),
+    /// Empty file to bind a file onto
+    File(CString),
+    Symlink(CString, CString),
+    /// Bind mount, remounted with `remount` (read-only unless writable) and the flags the kernel locks in a user namespace
+    Bind { source: CString, target: CString, remount: MsFlags },
+    Tmpfs(CString, CString),
+    Proc(CString),
+}
+
+/// Mounts of one execution's root filesystem, prepared so the child can make them without allocating
+#[derive(Debug)]
+pub struct RootfsMounts {
+    root: CString,
+    workspace: CString,
+    steps: Vec<Step>,
+}
+
+impl RootfsMounts {
+    /// Assemble the root filesystem, make it read-only and pivot into it, leaving the host's
+    /// filesystem unreachable. Runs in the new mount namespace between fork and exec.
+    pub fn pivot(&self) -> nix::Result<()> {
+        for step in &self.steps {
+            step.apply()?;
+        }
+        remount(&self.root, MsFlags::MS_RDONLY | MsFlags::MS_NOSUID | MsFlags::MS_NODEV)?;
+
+        // put_old을 새 루트와 같게 하면 이전 루트가 새 루트 아래에 쌓이므로 바로 떼어낼 수 있음
+        chdir(self.root.as_c_str())?;
+        pivot_root(c".", c".")?;
+        umount2(c".", MntFlags::MNT_DETACH)?;
+        chdir(self.workspace.as_c_str())
+    }
+}
+
+impl Step {
+    fn apply(&self) -> nix::Result<()> {
+        const NONE: Option<&CStr> = None;
+        match self {
+            Step::Dir(path) => mkdir(path.as_c_str(), Mode::from_bits_truncate(0o755)),
+            Step::File(path) => {
+                let fd = unsafe { libc::open(path.as_ptr(), libc::O_CREAT | libc::O_WRONLY | libc::O_CLOEXEC, 0o644) };
+                if fd < 0 {
+                    return Err(Errno::last());
+                }
+                unsafe { libc::close(fd) };
+                Ok(())
+            }
+            Step::Symlink(destination, link) => {
+                if unsafe { libc::symlink(destination.as_ptr(), link.as_ptr()) } != 0 {
+                    return Err(Errno::last());
+                }
+                Ok(())
+            }
+            Step::Bind { source, target, remount: flags } => {
+                mount(Some(source.as_c_str()), target.as_c_str(), NONE, MsFlags::MS_BIND | MsFlags::MS_REC, NONE)?;
+                remount(target, *flags)
+            }
+            Step::Tmpfs(target, options) => mount(Some(c"tmpfs"), target.as_c_str(), Some(c"tmpfs"),
+                                                  MsFlags::MS_NOSUID | MsFlags::MS_NODEV, Some(options.as_c_str())),
+            Step::Proc(target) => mount(Some(c"proc"), target.as_c_str(), Some(c"proc"),
+                                        MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC, NONE),
+        }
+    }
+}
+
+fn remount(target: &CStr, flags: MsFlags) -> nix::Result<()> {
+    mount(None::<&CStr>, target, None::<&CStr>, MsFlags::MS_BIND | MsFlags::MS_REMOUNT | flags, None::<&CStr>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_paths_may_match_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["java-17-openjdk", "java-21-openjdk", "javascript"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+        }
+
+        let rootfs = Rootfs::host(&[dir.path().join("java-*-openjdk"), dir.path().join("missing-*"), "/usr".into()], 16);
+        let targets: Vec<_> = rootfs.binds.iter().map(|(_, target)| target.strip_prefix(dir.path()).unwrap_or(target)).collect();
+        assert_eq!(targets, ["java-17-openjdk", "java-21-openjdk", "/usr"].map(Path::new));
+    }
+
+    #[test]
+    fn images_are_mounted_at_the_root_except_for_fresh_mounts() {
+        let image = tempfile::tempdir().unwrap();
+        for dir in ["usr", "etc", "proc", "dev", "tmp"] {
+            std::fs::create_dir(image.path().join(dir)).unwrap();
+        }
+        std::os::unix::fs::symlink("usr/bin", image.path().join("bin")).unwrap();
+
+        let rootfs = Rootfs::image(image.path(), 16).unwrap();
+        let targets: Vec<_> = rootfs.binds.iter().map(|(_, target)| target.to_str().unwrap()).collect();
+        assert_eq!(targets, ["/bin", "/etc", "/usr"]);
+
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("main.py"), "").unwrap();
+        let mounts = rootfs.mounts(Path::new("/staging"), Workspace::Tmpfs(workspace.path()), Uid::from_raw(1000), Gid::from_raw(1000)).unwrap();
+        let steps = format!("{:?}", mounts.steps);
+        assert!(steps.contains(r#"Symlink("usr/bin", "/staging/bin")"#), "{}", steps);
+        assert!(steps.contains(r#"Tmpfs("/staging/workspace", "size=16m,uid=1000,gid=1000,mode=700")"#), "{}", steps);
+        assert!(steps.contains(r#"target: "/staging/workspace/main.py""#), "{}", steps);
+        assert!(mounts.steps.iter().position(|step| matches!(step, Step::Dir(path) if path.as_bytes() == b"/staging/dev"))
+            < mounts.steps.iter().position(|step| matches!(step, Step::File(path) if path.as_bytes() == b"/staging/dev/null")));
+
+        let mounts = rootfs.mounts(Path::new("/staging"), Workspace::Host(image.path()), Uid::from_raw(1000), Gid::from_raw(1000)).unwrap();
+        let workspace = mounts.steps.iter().find_map(|step| match step {
+            Step::Bind { target, remount, .. } if target.as_bytes() == b"/staging/workspace" => Some(*remount),
+            _ => None,
+        });
+        assert!(workspace.is_some_and(|remount| !remount.contains(MsFlags::MS_RDONLY)), "{:?}", mounts.steps);
+    }
+
+    #[test]
+    fn what_the_compile_step_built_is_read_only_in_the_run_tmpfs() {
+        let rootfs = Rootfs::host(&[], 16);
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("main.c"), "").unwrap();
+        std::fs::create_dir(workspace.path().join("classes")).unwrap();
+        std::os::unix::fs::symlink("/etc/shadow", workspace.path().join("escape")).unwrap();
+
+        let mounts = rootfs.mounts(Path::new("/staging"), Workspace::Tmpfs(workspace.path()), Uid::from_raw(1000), Gid::from_raw(1000)).unwrap();
+        let binds: Vec<_> = mounts.steps.iter().filter_map(|step| match step {
+            Step::Bind { target, remount, .. } if target.as_bytes().starts_with(b"/staging/workspace/") => Some((target.to_str().unwrap(), *remount)),
+            _ => None,
+        }).collect();
+        assert_eq!(binds.iter().map(|(target, _)| *target).collect::<Vec<_>>(), ["/staging/workspace/classes", "/staging/workspace/main.c"]);
+        assert!(binds.iter().all(|(_, remount)| remount.contains(MsFlags::MS_RDONLY)));
+        let steps = format!("{:?}", mounts.steps);
+        assert!(steps.contains(r#"Symlink("/etc/shadow", "/staging/workspace/escape")"#), "{}", steps);
+    }
+}
@@ -0,0 +1,543 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::config::env_override;
+
+const DEFAULT_MAX_CONCURRENT: usize = 8;
+const DEFAULT_MAX_QUEUE_LENGTH: usize = 256;
+const DEFAULT_RESERVED_BATCH_SLOTS: usize = 1;
+
+/// Scheduling class of an execution, served in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    Interactive,
+    Normal,
+    Batch,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Interactive, Priority::Normal, Priority::Batch];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Limits applied before an execution is allowed to create its container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// Maximum number of executions running at the same time
+    pub max_concurrent: usize,
+    /// Maximum number of requests waiting for a slot before new ones are rejected
+    pub max_queue_length: usize,
+    /// Per-language concurrency limits; unlisted languages are only bound by `max_concurrent`
+    pub language_limits: BTreeMap<String, usize>,
+    /// Slots that go to waiting batch requests first, so bulk work keeps moving under interactive load
+    pub reserved_batch_slots: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            max_queue_length: DEFAULT_MAX_QUEUE_LENGTH,
+            language_limits: BTreeMap::new(),
+            reserved_batch_slots: DEFAULT_RESERVED_BATCH_SLOTS,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// Apply `CODE_EXECUTOR_MAX_CONCURRENT`, `CODE_EXECUTOR_MAX_QUEUE_LENGTH`,
+    /// `CODE_EXECUTOR_LANGUAGE_LIMITS` (e.g. `python=4,cpp=2`) and
+    /// `CODE_EXECUTOR_RESERVED_BATCH_SLOTS` on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        env_override("CODE_EXECUTOR_MAX_CONCURRENT", &mut self.max_concurrent)?;
+        env_override("CODE_EXECUTOR_MAX_QUEUE_LENGTH", &mut self.max_queue_length)?;
+        env_override("CODE_EXECUTOR_RESERVED_BATCH_SLOTS", &mut self.reserved_batch_slots)?;
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_LANGUAGE_LIMITS") {
+            self.language_limits.clear();
+            for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                let (language, limit) = entry.split_once('=')
+                    .ok_or_else(|| anyhow!("Invalid language limit '{}', expected <language>=<limit>", entry))?;
+                let limit = limit.trim().parse()
+                    .map_err(|e| anyhow!("Invalid limit for language '{}': {}", language, e))?;
+                self.language_limits.insert(language.trim().to_string(), limit);
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe every invalid setting
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_concurrent == 0 {
+            errors.push("max_concurrent must be greater than 0".to_string());
+        }
+        for (language, _) in self.language_limits.iter().filter(|(_, limit)| **limit == 0) {
+            errors.push(format!("language_limits.{} must be greater than 0", language));
+        }
+        // 모든 슬롯을 예약하면 배치 대기자가 항상 먼저라 대화형 요청이 굶을 수 있음
+        if self.max_concurrent > 0 && self.reserved_batch_slots >= self.max_concurrent {
+            errors.push(format!("reserved_batch_slots ({}) must be less than max_concurrent ({})",
+                                self.reserved_batch_slots, self.max_concurrent));
+        }
+        errors
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdmissionError {
+    #[error("Execution queue is full ({0} requests waiting)")]
+    QueueFull(usize),
+    #[error("Batch request was displaced from the execution queue by higher priority work")]
+    Preempted,
+    #[error("Request was removed from the execution queue")]
+    Cancelled,
+}
+
+/// Admission control in front of the execution backends.
+///
+/// Requests take a slot immediately when both the global and the per-language limit allow it,
+/// otherwise they wait in a bounded queue. Waiters are served by priority class, FIFO within
+/// a class, except that batch waiters go first while fewer than `reserved_batch_slots` batch
+/// executions are running. A waiting request is skipped (but keeps its place) while its own
+/// language is saturated, so one busy language can't block the others.
+///
+/// Batch requests can be preempted only while queued: when the queue is full, a newer
+/// interactive or normal request displaces the most recently queued batch request.
+#[derive(Debug)]
+pub struct Scheduler {
+    config: SchedulerConfig,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    running: usize,
+    running_by_language: HashMap<String, usize>,
+    running_batch: usize,
+    queues: [VecDeque<Waiter>; 3],
+    next_ticket: u64,
+}
+
+impl State {
+    fn queue_length(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    language: String,
+    tx: oneshot::Sender<Result<Permit, AdmissionError>>,
+}
+
+/// A granted execution slot together with how long it took to get it
+#[derive(Debug)]
+pub struct Admission {
+    pub permit: Permit,
+    /// 1-based queue position at enqueue time, 0 if the request started without waiting
+    pub queue_position: usize,
+    pub queue_wait: Duration,
+}
+
+/// Holds an execution slot; the slot is handed to the next waiter when this is dropped
+#[derive(Debug)]
+pub struct Permit {
+    scheduler: Arc<Scheduler>,
+    language: String,
+    priority: Priority,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.scheduler.release(&self.language, self.priority);
+    }
+}
+
+/// A request that passed the queue limit check, either already holding a slot or waiting for one
+#[derive(Debug)]
+pub struct Ticket {
+    state: TicketState,
+    queue_position: usize,
+    enqueued_at: Instant,
+}
+
+#[derive(Debug)]
+enum TicketState {
+    Granted(Permit),
+    Queued {
+        rx: oneshot::Receiver<Result<Permit, AdmissionError>>,
+        guard: QueueGuard,
+    },
+}
+
+impl Ticket {
+    /// 1-based queue position at enqueue time, 0 if a slot was free
+    pub fn queue_position(&self) -> usize {
+        self.queue_position
+    }
+
+    /// Wait until the request holds an execution slot
+    pub async fn wait(self) -> Result<Admission, AdmissionError> {
+        let permit = match self.state {
+            TicketState::Granted(permit) => permit,
+            TicketState::Queued { rx, mut guard } => {
+                let result = rx.await.unwrap_or(Err(AdmissionError::Cancelled));
+                guard.armed = false;
+                result?
+            }
+        };
+
+        Ok(Admission {
+            permit,
+            queue_position: self.queue_position,
+            queue_wait: self.enqueued_at.elapsed(),
+        })
+    }
+}
+
+/// Removes a waiter from the queue if its ticket is dropped before being served
+#[derive(Debug)]
+struct QueueGuard {
+    scheduler: Arc<Scheduler>,
+    ticket: u64,
+    armed: bool,
+}
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            let mut state = self.scheduler.state.lock().unwrap();
+            for queue in state.queues.iter_mut() {
+                queue.retain(|waiter| waiter.ticket != self.ticket);
+            }
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Wait for an execution slot for `language` in the given priority class.
+    ///
+    /// Fails with `AdmissionError::QueueFull` instead of waiting when the queue is at capacity
+    /// and holds no batch request that this one may displace.
+    pub async fn admit(self: &Arc<Self>, language: &str, priority: Priority) -> Result<Admission, AdmissionError> {
+        self.enqueue(language, priority)?.wait().await
+    }
+
+    /// Take a slot or a place in the queue without waiting, so callers can reject
+    /// a request before handing it off to a background task
+    pub fn enqueue(self: &Arc<Self>, language: &str, priority: Priority) -> Result<Ticket, AdmissionError> {
+        let enqueued_at = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        if self.has_capacity(&state, language) {
+            self.acquire(&mut state, language, priority);
+            return Ok(Ticket {
+                state: TicketState::Granted(Permit { scheduler: self.clone(), language: language.to_string(), priority }),
+                queue_position: 0,
+                enqueued_at,
+            });
+        }
+
+        if state.queue_length() >= self.config.max_queue_length {
+            let victim = match priority {
+                Priority::Batch => None,
+                _ => state.queues[Priority::Batch.index()].pop_back(),
+            };
+            match victim {
+                Some(victim) => {
+                    let _ = victim.tx.send(Err(AdmissionError::Preempted));
+                }
+                None => return Err(AdmissionError::QueueFull(state.queue_length())),
+            }
+        }
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        let (tx, rx) = oneshot::channel();
+        state.queues[priority.index()].push_back(Waiter { ticket, language: language.to_string(), tx });
+
+        // Requests of the same or a higher class are served first
+        let queue_position = state.queues[..=priority.index()].iter().map(VecDeque::len).sum();
+        Ok(Ticket {
+            state: TicketState::Queued {
+                rx,
+                guard: QueueGuard { scheduler: self.clone(), ticket, armed: true },
+            },
+            queue_position,
+            enqueued_at,
+        })
+    }
+
+    fn has_capacity(&self, state: &State, language: &str) -> bool {
+        state.running < self.config.max_concurrent && self.language_has_capacity(state, language)
+    }
+
+    fn language_has_capacity(&self, state: &State, language: &str) -> bool {
+        match self.config.language_limits.get(language) {
+            Some(limit) => state.running_by_language.get(language).copied().unwrap_or(0) < *limit,
+            None => true,
+        }
+    }
+
+    fn acquire(&self, state: &mut State, language: &str, priority: Priority) {
+        state.running += 1;
+        *state.running_by_language.entry(language.to_string()).or_insert(0) += 1;
+        if priority == Priority::Batch {
+            state.running_batch += 1;
+        }
+    }
+
+    fn release(self: &Arc<Self>, language: &str, priority: Priority) {
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            state.running -= 1;
+            if priority == Priority::Batch {
+                state.running_batch -= 1;
+            }
+            if let Some(count) = state.running_by_language.get_mut(language) {
+                *count -= 1;
+                if *count == 0 {
+                    state.running_by_language.remove(language);
+                }
+            }
+            self.dispatch(&mut state)
+        };
+
+        // Hand out permits outside the lock: a failed send drops the permit, which re-enters `release`
+        for (tx, permit) in ready {
+            let _ = tx.send(Ok(permit));
+        }
+    }
+
+    fn dispatch(self: &Arc<Self>, state: &mut State) -> Vec<(oneshot::Sender<Result<Permit, AdmissionError>>, Permit)> {
+        let mut ready = Vec::new();
+        while state.running < self.config.max_concurrent {
+            let Some((priority, index)) = self.next_waiter(state) else {
+                break;
+            };
+            let waiter = state.queues[priority.index()].remove(index).unwrap();
+            self.acquire(state, &waiter.language, priority);
+            ready.push((waiter.tx, Permit { scheduler: self.clone(), language: waiter.language, priority }));
+        }
+        ready
+    }
+
+    /// Pick the next waiter whose language has room: reserved batch share first, then by class
+    fn next_waiter(&self, state: &State) -> Option<(Priority, usize)> {
+        let eligible = |priority: Priority| {
+            state.queues[priority.index()]
+                .iter()
+                .position(|waiter| self.language_has_capacity(state, &waiter.language))
+                .map(|index| (priority, index))
+        };
+
+        if state.running_batch < self.config.reserved_batch_slots {
+            if let Some(next) = eligible(Priority::Batch) {
+                return Some(next);
+            }
+        }
+        Priority::ALL.into_iter().find_map(eligible)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(max_concurrent: usize, max_queue_length: usize, language_limits: &[(&str, usize)]) -> Arc<Scheduler> {
+        Scheduler::new(SchedulerConfig {
+            max_concurrent,
+            max_queue_length,
+            language_limits: language_limits.iter().map(|(language, limit)| (language.to_string(), *limit)).collect(),
+            reserved_batch_slots: 0,
+        })
+    }
+
+    /// Languages of the queued requests in the order they would be served, ignoring limits
+    fn waiting(scheduler: &Scheduler) -> Vec<String> {
+        let state = scheduler.state.lock().unwrap();
+        state.queues.iter().flatten().map(|waiter| waiter.language.clone()).collect()
+    }
+
+    async fn granted(ticket: Ticket) -> Permit {
+        ticket.wait().await.unwrap().permit
+    }
+
+    #[tokio::test]
+    async fn free_slots_are_taken_without_queueing() {
+        let scheduler = scheduler(2, 1, &[]);
+        let ticket = scheduler.enqueue("python", Priority::Normal).unwrap();
+        assert_eq!(ticket.queue_position(), 0);
+
+        let admission = scheduler.admit("cpp", Priority::Normal).await.unwrap();
+        assert_eq!(admission.queue_position, 0);
+        assert!(waiting(&scheduler).is_empty());
+        assert_eq!(scheduler.state.lock().unwrap().running, 2);
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_the_queue_length_are_rejected() {
+        let scheduler = scheduler(1, 1, &[]);
+        let _running = granted(scheduler.enqueue("python", Priority::Normal).unwrap()).await;
+        let _queued = scheduler.enqueue("python", Priority::Normal).unwrap();
+
+        assert!(matches!(scheduler.enqueue("python", Priority::Normal), Err(AdmissionError::QueueFull(1))));
+        assert!(matches!(scheduler.admit("cpp", Priority::Interactive).await, Err(AdmissionError::QueueFull(1))));
+        assert_eq!(waiting(&scheduler), ["python"]);
+    }
+
+    #[tokio::test]
+    async fn waiters_of_a_class_are_served_in_arrival_order() {
+        let scheduler = scheduler(1, 8, &[]);
+        let running = granted(scheduler.enqueue("first", Priority::Normal).unwrap()).await;
+        let second = scheduler.enqueue("second", Priority::Normal).unwrap();
+        let third = scheduler.enqueue("third", Priority::Normal).unwrap();
+
+        drop(running);
+        let running = granted(second).await;
+        assert_eq!(waiting(&scheduler), ["third"]);
+
+        drop(running);
+        let _running = granted(third).await;
+        assert!(waiting(&scheduler).is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_saturated_language_does_not_hold_up_others() {
+        let scheduler = scheduler(2, 8, &[("python", 1)]);
+        let _python = granted(scheduler.enqueue("python", Priority::Normal).unwrap()).await;
+        let python = scheduler.enqueue("python", Priority::Normal).unwrap();
+        assert_eq!(python.queue_position(), 1);
+
+        // 전역 슬롯이 남아 있으면 대기 중인 python 뒤라도 바로 실행
+        let cpp = scheduler.enqueue("cpp", Priority::Normal).unwrap();
+        assert_eq!(cpp.queue_position(), 0);
+
+        // 슬롯이 풀리면 python을 건너뛰고 뒤의 cpp가 받음
+        let queued_cpp = scheduler.enqueue("cpp", Priority::Normal).unwrap();
+        drop(cpp);
+        let _cpp = granted(queued_cpp).await;
+        assert_eq!(waiting(&scheduler), ["python"]);
+    }
+
+    #[tokio::test]
+    async fn queue_positions_count_the_waiters_served_first() {
+        let scheduler = scheduler(1, 8, &[]);
+        let _running = granted(scheduler.enqueue("python", Priority::Normal).unwrap()).await;
+
+        assert_eq!(scheduler.enqueue("python", Priority::Normal).unwrap().queue_position(), 1);
+        let _normal = scheduler.enqueue("python", Priority::Normal).unwrap();
+        let batch = scheduler.enqueue("python", Priority::Batch).unwrap();
+        assert_eq!(batch.queue_position(), 2);
+        assert_eq!(scheduler.enqueue("python", Priority::Normal).unwrap().queue_position(), 2);
+        assert_eq!(scheduler.enqueue("python", Priority::Interactive).unwrap().queue_position(), 1);
+    }
+
+    #[tokio::test]
+    async fn dropped_tickets_leave_the_queue() {
+        let scheduler = scheduler(1, 8, &[]);
+        let running = granted(scheduler.enqueue("first", Priority::Normal).unwrap()).await;
+        let abandoned = scheduler.enqueue("abandoned", Priority::Normal).unwrap();
+        let next = scheduler.enqueue("next", Priority::Normal).unwrap();
+
+        drop(abandoned);
+        assert_eq!(waiting(&scheduler), ["next"]);
+
+        drop(running);
+        let admission = next.wait().await.unwrap();
+        assert_eq!(admission.queue_position, 2);
+        assert_eq!(scheduler.state.lock().unwrap().running, 1);
+    }
+
+    #[tokio::test]
+    async fn classes_are_served_interactive_then_normal_then_batch() {
+        let scheduler = scheduler(1, 8, &[]);
+        let running = granted(scheduler.enqueue("first", Priority::Normal).unwrap()).await;
+        let batch = scheduler.enqueue("batch", Priority::Batch).unwrap();
+        let normal = scheduler.enqueue("normal", Priority::Normal).unwrap();
+        let interactive = scheduler.enqueue("interactive", Priority::Interactive).unwrap();
+        assert_eq!(waiting(&scheduler), ["interactive", "normal", "batch"]);
+
+        drop(running);
+        let running = granted(interactive).await;
+        drop(running);
+        let running = granted(normal).await;
+        assert_eq!(waiting(&scheduler), ["batch"]);
+        drop(running);
+        let _running = granted(batch).await;
+    }
+
+    #[tokio::test]
+    async fn a_full_queue_displaces_the_newest_batch_waiter() {
+        let scheduler = scheduler(1, 2, &[]);
+        let _running = granted(scheduler.enqueue("first", Priority::Normal).unwrap()).await;
+        let older = scheduler.enqueue("older", Priority::Batch).unwrap();
+        let newer = scheduler.enqueue("newer", Priority::Batch).unwrap();
+
+        // 배치 요청은 다른 배치 요청을 밀어내지 못함
+        assert!(matches!(scheduler.enqueue("batch", Priority::Batch), Err(AdmissionError::QueueFull(2))));
+
+        let interactive = scheduler.enqueue("interactive", Priority::Interactive).unwrap();
+        assert_eq!(interactive.queue_position(), 1);
+        assert!(matches!(newer.wait().await, Err(AdmissionError::Preempted)));
+        assert_eq!(waiting(&scheduler), ["interactive", "older"]);
+
+        let _normal = scheduler.enqueue("normal", Priority::Normal).unwrap();
+        assert!(matches!(older.wait().await, Err(AdmissionError::Preempted)));
+        assert!(matches!(scheduler.enqueue("normal", Priority::Normal), Err(AdmissionError::QueueFull(2))));
+    }
+
+    #[test]
+    fn reserved_batch_slots_leave_room_for_interactive_work() {
+        let config = |max_concurrent, reserved_batch_slots| SchedulerConfig {
+            max_concurrent,
+            reserved_batch_slots,
+            ..Default::default()
+        };
+
+        assert!(config(2, 1).validate().is_empty());
+        assert_eq!(config(2, 2).validate(), ["reserved_batch_slots (2) must be less than max_concurrent (2)"]);
+        assert_eq!(config(2, 3).validate().len(), 1);
+        assert_eq!(config(0, 1).validate(), ["max_concurrent must be greater than 0"]);
+    }
+
+    #[tokio::test]
+    async fn batch_work_gets_its_reserved_slots_under_interactive_load() {
+        let scheduler = Scheduler::new(SchedulerConfig {
+            max_concurrent: 2,
+            max_queue_length: 8,
+            language_limits: BTreeMap::new(),
+            reserved_batch_slots: 1,
+        });
+        let first = granted(scheduler.enqueue("python", Priority::Interactive).unwrap()).await;
+        let second = granted(scheduler.enqueue("python", Priority::Interactive).unwrap()).await;
+        let interactive = scheduler.enqueue("interactive", Priority::Interactive).unwrap();
+        let batch = scheduler.enqueue("batch", Priority::Batch).unwrap();
+        let _later_batch = scheduler.enqueue("later batch", Priority::Batch).unwrap();
+
+        // 예약분이 비어 있으므로 대화형 대기자보다 배치가 먼저
+        drop(first);
+        let _batch = granted(batch).await;
+
+        // 예약분이 찼으니 다음 배치는 다시 우선순위 순서를 따름
+        drop(second);
+        let _interactive = granted(interactive).await;
+        assert_eq!(waiting(&scheduler), ["later batch"]);
+    }
+}
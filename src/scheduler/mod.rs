@@ -0,0 +1,3 @@
+mod admission;
+
+pub use admission::{Admission, AdmissionError, Permit, Priority, Scheduler, SchedulerConfig, Ticket};
@@ -0,0 +1,182 @@
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, anyhow};
+use nix::errno::Errno;
+
+// linux/landlock.h, which libc does not define
+const CREATE_RULESET_VERSION: libc::c_uint = 1 << 0;
+const RULE_PATH_BENEATH: libc::c_int = 1;
+
+const EXECUTE: u64 = 1 << 0;
+const WRITE_FILE: u64 = 1 << 1;
+const READ_FILE: u64 = 1 << 2;
+const READ_DIR: u64 = 1 << 3;
+/// `REMOVE_DIR` through `MAKE_SYM`, which only apply to directories
+const MODIFY_DIR: u64 = 0b1_1111_1111 << 4;
+/// Linking and renaming between directories, from ABI 2
+const REFER: u64 = 1 << 13;
+/// From ABI 3
+const TRUNCATE: u64 = 1 << 14;
+/// ioctl on device files, from ABI 5
+const IOCTL_DEV: u64 = 1 << 15;
+/// Rights a rule for a file, rather than a directory, may grant
+const FILE_ACCESS: u64 = EXECUTE | WRITE_FILE | READ_FILE | TRUNCATE | IOCTL_DEV;
+
+const READ_ONLY: u64 = EXECUTE | READ_FILE | READ_DIR;
+const READ_WRITE: u64 = READ_ONLY | WRITE_FILE | MODIFY_DIR | REFER | TRUNCATE;
+
+/// Device files programs commonly open, readable and writable
+const DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/full", "/dev/random", "/dev/urandom"];
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: libc::c_int,
+}
+
+/// Filesystem access native executions get under Landlock: read-only access to the
+/// toolchain paths, read/write access to their working directory and nothing else
+#[derive(Debug, Clone)]
+pub struct Landlock {
+    /// Access rights this kernel's Landlock ABI knows; anything else cannot be restricted
+    handled: u64,
+    read_only: Vec<PathBuf>,
+}
+
+impl Landlock {
+    /// Landlock with read-only access to `read_only`, or an error when the kernel does not support it
+    pub fn supported(read_only: Vec<PathBuf>) -> Result<Self> {
+        let abi = unsafe {
+            libc::syscall(libc::SYS_landlock_create_ruleset, std::ptr::null::<RulesetAttr>(), 0, CREATE_RULESET_VERSION)
+        };
+        if abi < 1 {
+            return Err(anyhow!("Landlock is not supported by this kernel: {}", Errno::last()));
+        }
+        let mut handled = READ_WRITE & !(REFER | TRUNCATE);
+        if abi >= 2 {
+            handled |= REFER;
+        }
+        if abi >= 3 {
+            handled |= TRUNCATE;
+        }
+        if abi >= 5 {
+            handled |= IOCTL_DEV;
+        }
+        Ok(Self { handled, read_only })
+    }
+
+    /// Also allow reading `path`, e.g. the installation prefix of an interpreter
+    pub fn allow_read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.read_only.push(path.into());
+        self
+    }
+
+    /// The rules of an execution that may write to `writable`, its working directory and any scratch space
+    pub fn rules(&self, writable: &[&Path]) -> Result<LandlockRules> {
+        let read_only = self.read_only.iter().map(|path| (path.as_path(), READ_ONLY));
+        let devices = DEVICES.iter().map(|path| (Path::new(*path), READ_FILE | WRITE_FILE | TRUNCATE));
+        let writable = writable.iter().map(|path| (*path, READ_WRITE));
+        let paths = read_only.chain(devices).chain(writable)
+            .map(|(path, access)| Ok((CString::new(path.as_os_str().as_bytes())?, access & self.handled)))
+            .collect::<Result<_>>()?;
+        Ok(LandlockRules { handled: self.handled, paths })
+    }
+}
+
+/// Landlock rules prepared for one execution, applied in the child
+#[derive(Debug)]
+pub struct LandlockRules {
+    handled: u64,
+    paths: Vec<(CString, u64)>,
+}
+
+impl LandlockRules {
+    /// Restrict the calling process and everything it execs to the rules. Paths are opened
+    /// here, after any mounts the child made, and skipped when missing. Sets `no_new_privs`,
+    /// which unprivileged processes need for Landlock. Runs between fork and exec, so it must not allocate.
+    pub fn restrict_self(&self) -> nix::Result<()> {
+        let attr = RulesetAttr { handled_access_fs: self.handled };
+        let ruleset = unsafe {
+            libc::syscall(libc::SYS_landlock_create_ruleset, &attr as *const RulesetAttr, size_of::<RulesetAttr>(), 0)
+        };
+        if ruleset < 0 {
+            return Err(Errno::last());
+        }
+        let ruleset = ruleset as libc::c_int;
+        let result = self.add_rules(ruleset).and_then(|()| unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+                || libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0) != 0 {
+                return Err(Errno::last());
+            }
+            Ok(())
+        });
+        unsafe { libc::close(ruleset) };
+        result
+    }
+
+    fn add_rules(&self, ruleset: libc::c_int) -> nix::Result<()> {
+        for (path, access) in &self.paths {
+            let fd = unsafe { libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+            if fd < 0 {
+                match Errno::last() {
+                    Errno::ENOENT => continue,
+                    error => return Err(error),
+                }
+            }
+            let mut stat = std::mem::MaybeUninit::<libc::stat>::zeroed();
+            let is_dir = unsafe { libc::fstat(fd, stat.as_mut_ptr()) } == 0
+                && unsafe { stat.assume_init() }.st_mode & libc::S_IFMT == libc::S_IFDIR;
+            let rule = PathBeneathAttr {
+                allowed_access: if is_dir { *access } else { access & FILE_ACCESS },
+                parent_fd: fd,
+            };
+            let added = unsafe {
+                libc::syscall(libc::SYS_landlock_add_rule, ruleset, RULE_PATH_BENEATH, &rule as *const PathBeneathAttr, 0)
+            };
+            let error = Errno::last();
+            unsafe { libc::close(fd) };
+            if added != 0 {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+
+    #[test]
+    fn confines_the_child_to_the_rules() {
+        let Ok(landlock) = Landlock::supported(vec!["/usr".into(), "/bin".into(), "/lib".into(), "/lib64".into(), "/etc".into()]) else {
+            eprintln!("Landlock is not supported here, skipping");
+            return;
+        };
+        let workdir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret"), "secret").unwrap();
+        let rules = landlock.rules(&[workdir.path()]).unwrap();
+
+        let script = format!(
+            "echo ok > {0}/out && cat {0}/out && ! cat {1}/secret && ! touch {1}/new && ! touch /usr/new && echo done > /dev/null",
+            workdir.path().display(), outside.path().display());
+        let mut command = std::process::Command::new("/bin/sh");
+        command.args(["-c", &script]);
+        unsafe {
+            command.pre_exec(move || rules.restrict_self().map_err(std::io::Error::from));
+        }
+        let output = command.output().unwrap();
+
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(output.stdout, b"ok\n");
+        assert!(!outside.path().join("new").exists());
+    }
+}
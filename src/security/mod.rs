@@ -1,3 +1,5 @@
-mod sandbox;
+mod landlock;
+mod seccomp;
 
-pub use sandbox::{SecurityConfig, create_seccomp_profile};
\ No newline at end of file
+pub use landlock::{Landlock, LandlockRules};
+pub use seccomp::{SeccompProfile, SyscallFilter, is_known_syscall};
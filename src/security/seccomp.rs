@@ -0,0 +1,502 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use serde_json::json;
+
+use crate::language::LanguageManifest;
+
+/// System calls no submission needs: debugging other processes, changing mounts and
+/// namespaces, kernel keyrings, eBPF, modules and host-wide settings.
+/// Calling one kills the process, which is reported as a security violation.
+const DENIED: &[&str] = &[
+    "ptrace", "process_vm_readv", "process_vm_writev", "kcmp",
+    "mount", "umount2", "pivot_root", "open_tree", "move_mount", "fsopen", "fsconfig", "fsmount", "fspick", "mount_setattr",
+    "unshare", "setns",
+    "keyctl", "add_key", "request_key",
+    "bpf", "perf_event_open", "userfaultfd", "fanotify_init",
+    "init_module", "finit_module", "delete_module", "kexec_load", "kexec_file_load",
+    "reboot", "swapon", "swapoff", "acct", "quotactl", "syslog", "vhangup", "lookup_dcookie",
+    "settimeofday", "clock_settime", "clock_adjtime", "adjtimex",
+    "name_to_handle_at", "open_by_handle_at",
+    #[cfg(target_arch = "x86_64")] "iopl",
+    #[cfg(target_arch = "x86_64")] "ioperm",
+    #[cfg(target_arch = "x86_64")] "uselib",
+    #[cfg(target_arch = "x86_64")] "ustat",
+    #[cfg(target_arch = "x86_64")] "sysfs",
+    #[cfg(target_arch = "x86_64")] "_sysctl",
+];
+
+/// System calls that fail with `ENOSYS` instead: runtimes probe for them and fall back
+/// (glibc to `clone`, libuv to its thread pool), and `clone3` flags cannot be inspected
+const UNAVAILABLE: &[&str] = &["clone3", "io_uring_setup", "io_uring_enter", "io_uring_register"];
+
+/// `clone` flags that create namespaces; `clone` with any of them is treated like `unshare`
+const NAMESPACE_FLAGS: [(&str, libc::c_int); 8] = [
+    ("CLONE_NEWNS", libc::CLONE_NEWNS),
+    ("CLONE_NEWCGROUP", libc::CLONE_NEWCGROUP),
+    ("CLONE_NEWUTS", libc::CLONE_NEWUTS),
+    ("CLONE_NEWIPC", libc::CLONE_NEWIPC),
+    ("CLONE_NEWUSER", libc::CLONE_NEWUSER),
+    ("CLONE_NEWPID", libc::CLONE_NEWPID),
+    ("CLONE_NEWNET", libc::CLONE_NEWNET),
+    ("CLONE_NEWTIME", libc::CLONE_NEWTIME),
+];
+
+/// Syscalls a language manifest may additionally deny besides `DENIED`
+const DENIABLE: &[&str] = &[
+    "socket", "socketpair", "connect", "bind", "listen", "accept4", "execve", "execveat", "vfork",
+    "chroot", "personality", "mbind", "set_mempolicy", "get_mempolicy", "migrate_pages", "move_pages",
+];
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+/// Docker architectures the profile covers, including compat ABIs that could bypass it
+#[cfg(target_arch = "x86_64")]
+const DOCKER_ARCHITECTURES: &[&str] = &["SCMP_ARCH_X86_64", "SCMP_ARCH_X86", "SCMP_ARCH_X32"];
+#[cfg(target_arch = "aarch64")]
+const DOCKER_ARCHITECTURES: &[&str] = &["SCMP_ARCH_AARCH64", "SCMP_ARCH_ARM"];
+
+/// Syscalls Docker's default seccomp profile allows without conditions for a container with the
+/// default capabilities; anything else fails with `EPERM` there. Names of other architectures are
+/// skipped when the profile is loaded.
+const DOCKER_ALLOWED: &[&str] = &[
+    "accept", "accept4", "access", "adjtimex", "alarm", "bind", "brk", "cachestat", "capget", "capset", "chdir",
+    "chmod", "chown", "chown32", "chroot", "clock_adjtime", "clock_adjtime64", "clock_getres", "clock_getres_time64",
+    "clock_gettime", "clock_gettime64", "clock_nanosleep", "clock_nanosleep_time64", "close", "close_range",
+    "connect", "copy_file_range", "creat", "dup", "dup2", "dup3", "epoll_create", "epoll_create1", "epoll_ctl",
+    "epoll_ctl_old", "epoll_pwait", "epoll_pwait2", "epoll_wait", "epoll_wait_old", "eventfd", "eventfd2",
+    "execve", "execveat", "exit", "exit_group", "faccessat", "faccessat2", "fadvise64", "fadvise64_64",
+    "fallocate", "fanotify_mark", "fchdir", "fchmod", "fchmodat", "fchmodat2", "fchown", "fchown32", "fchownat",
+    "fcntl", "fcntl64", "fdatasync", "fgetxattr", "flistxattr", "flock", "fork", "fremovexattr", "fsetxattr",
+    "fstat", "fstat64", "fstatat64", "fstatfs", "fstatfs64", "fsync", "ftruncate", "ftruncate64", "futex",
+    "futex_requeue", "futex_time64", "futex_wait", "futex_waitv", "futex_wake", "futimesat", "getcpu", "getcwd",
+    "getdents", "getdents64", "getegid", "getegid32", "geteuid", "geteuid32", "getgid", "getgid32", "getgroups",
+    "getgroups32", "getitimer", "getpeername", "getpgid", "getpgrp", "getpid", "getppid", "getpriority",
+    "getrandom", "getresgid", "getresgid32", "getresuid", "getresuid32", "getrlimit", "get_robust_list",
+    "getrusage", "getsid", "getsockname", "getsockopt", "get_thread_area", "gettid", "gettimeofday", "getuid",
+    "getuid32", "getxattr", "inotify_add_watch", "inotify_init", "inotify_init1", "inotify_rm_watch", "io_cancel",
+    "ioctl", "io_destroy", "io_getevents", "io_pgetevents", "io_pgetevents_time64", "ioprio_get", "ioprio_set",
+    "io_setup", "io_submit", "ipc", "kill", "landlock_add_rule", "landlock_create_ruleset",
+    "landlock_restrict_self", "lchown", "lchown32", "lgetxattr", "link", "linkat", "listen", "listxattr",
+    "llistxattr", "_llseek", "lremovexattr", "lseek", "lsetxattr", "lstat", "lstat64", "madvise",
+    "map_shadow_stack", "membarrier", "memfd_create", "memfd_secret", "mincore", "mkdir", "mkdirat", "mknod",
+    "mknodat", "mlock", "mlock2", "mlockall", "mmap", "mmap2", "mprotect", "mq_getsetattr", "mq_notify", "mq_open",
+    "mq_timedreceive", "mq_timedreceive_time64", "mq_timedsend", "mq_timedsend_time64", "mq_unlink", "mremap",
+    "msgctl", "msgget", "msgrcv", "msgsnd", "msync", "munlock", "munlockall", "munmap", "name_to_handle_at",
+    "nanosleep", "newfstatat", "_newselect", "open", "openat", "openat2", "pause", "pidfd_open",
+    "pidfd_send_signal", "pipe", "pipe2", "pkey_alloc", "pkey_free", "pkey_mprotect", "poll", "ppoll",
+    "ppoll_time64", "prctl", "pread64", "preadv", "preadv2", "prlimit64", "process_mrelease", "pselect6",
+    "pselect6_time64", "pwrite64", "pwritev", "pwritev2", "read", "readahead", "readlink", "readlinkat", "readv",
+    "recv", "recvfrom", "recvmmsg", "recvmmsg_time64", "recvmsg", "remap_file_pages", "removexattr", "rename",
+    "renameat", "renameat2", "restart_syscall", "rmdir", "rseq", "rt_sigaction", "rt_sigpending",
+    "rt_sigprocmask", "rt_sigqueueinfo", "rt_sigreturn", "rt_sigsuspend", "rt_sigtimedwait",
+    "rt_sigtimedwait_time64", "rt_tgsigqueueinfo", "sched_getaffinity", "sched_getattr", "sched_getparam",
+    "sched_get_priority_max", "sched_get_priority_min", "sched_getscheduler", "sched_rr_get_interval",
+    "sched_rr_get_interval_time64", "sched_setaffinity", "sched_setattr", "sched_setparam", "sched_setscheduler",
+    "sched_yield", "seccomp", "select", "semctl", "semget", "semop", "semtimedop", "semtimedop_time64", "send",
+    "sendfile", "sendfile64", "sendmmsg", "sendmsg", "sendto", "setfsgid", "setfsgid32", "setfsuid", "setfsuid32",
+    "setgid", "setgid32", "setgroups", "setgroups32", "setitimer", "setpgid", "setpriority", "setregid",
+    "setregid32", "setresgid", "setresgid32", "setresuid", "setresuid32", "setreuid", "setreuid32", "setrlimit",
+    "set_robust_list", "setsid", "setsockopt", "set_thread_area", "set_tid_address", "setuid", "setuid32",
+    "setxattr", "shmat", "shmctl", "shmdt", "shmget", "shutdown", "sigaltstack", "signalfd", "signalfd4",
+    "sigprocmask", "sigreturn", "socketcall", "socketpair", "splice", "stat", "stat64", "statfs", "statfs64",
+    "statx", "symlink", "symlinkat", "sync", "sync_file_range", "syncfs", "sysinfo", "tee", "tgkill", "time",
+    "timer_create", "timer_delete", "timer_getoverrun", "timer_gettime", "timer_gettime64", "timer_settime",
+    "timer_settime64", "timerfd_create", "timerfd_gettime", "timerfd_gettime64", "timerfd_settime",
+    "timerfd_settime64", "times", "tkill", "truncate", "truncate64", "ugetrlimit", "umask", "uname", "unlink",
+    "unlinkat", "utime", "utimensat", "utimensat_time64", "utimes", "vfork", "vmsplice", "wait4", "waitid",
+    "waitpid", "write", "writev",
+    #[cfg(target_arch = "x86_64")] "arch_prctl",
+    #[cfg(target_arch = "aarch64")] "arm_fadvise64_64",
+    #[cfg(target_arch = "aarch64")] "arm_sync_file_range",
+    #[cfg(target_arch = "aarch64")] "sync_file_range2",
+    #[cfg(target_arch = "aarch64")] "breakpoint",
+    #[cfg(target_arch = "aarch64")] "cacheflush",
+    #[cfg(target_arch = "aarch64")] "set_tls",
+];
+/// `personality` values Docker's default profile allows: Linux, `UNAME26`, `READ_IMPLIES_EXEC`
+/// and queries
+const DOCKER_PERSONALITIES: [u64; 5] = [0x0, 0x8, 0x20000, 0x20008, 0xffff_ffff];
+/// `socket` family Docker's default profile refuses
+const AF_VSOCK: u64 = 40;
+
+/// Number of a syscall on this architecture, for the names used above
+fn syscall_number(name: &str) -> Option<libc::c_long> {
+    let number = match name {
+        "ptrace" => libc::SYS_ptrace,
+        "process_vm_readv" => libc::SYS_process_vm_readv,
+        "process_vm_writev" => libc::SYS_process_vm_writev,
+        "kcmp" => libc::SYS_kcmp,
+        "mount" => libc::SYS_mount,
+        "umount2" => libc::SYS_umount2,
+        "pivot_root" => libc::SYS_pivot_root,
+        "open_tree" => libc::SYS_open_tree,
+        "move_mount" => libc::SYS_move_mount,
+        "fsopen" => libc::SYS_fsopen,
+        "fsconfig" => libc::SYS_fsconfig,
+        "fsmount" => libc::SYS_fsmount,
+        "fspick" => libc::SYS_fspick,
+        "mount_setattr" => libc::SYS_mount_setattr,
+        "unshare" => libc::SYS_unshare,
+        "setns" => libc::SYS_setns,
+        "keyctl" => libc::SYS_keyctl,
+        "add_key" => libc::SYS_add_key,
+        "request_key" => libc::SYS_request_key,
+        "bpf" => libc::SYS_bpf,
+        "perf_event_open" => libc::SYS_perf_event_open,
+        "userfaultfd" => libc::SYS_userfaultfd,
+        "fanotify_init" => libc::SYS_fanotify_init,
+        "init_module" => libc::SYS_init_module,
+        "finit_module" => libc::SYS_finit_module,
+        "delete_module" => libc::SYS_delete_module,
+        "kexec_load" => libc::SYS_kexec_load,
+        "kexec_file_load" => libc::SYS_kexec_file_load,
+        "reboot" => libc::SYS_reboot,
+        "swapon" => libc::SYS_swapon,
+        "swapoff" => libc::SYS_swapoff,
+        "acct" => libc::SYS_acct,
+        "quotactl" => libc::SYS_quotactl,
+        "syslog" => libc::SYS_syslog,
+        "vhangup" => libc::SYS_vhangup,
+        "lookup_dcookie" => libc::SYS_lookup_dcookie,
+        "settimeofday" => libc::SYS_settimeofday,
+        "clock_settime" => libc::SYS_clock_settime,
+        "clock_adjtime" => libc::SYS_clock_adjtime,
+        "adjtimex" => libc::SYS_adjtimex,
+        "name_to_handle_at" => libc::SYS_name_to_handle_at,
+        "open_by_handle_at" => libc::SYS_open_by_handle_at,
+        #[cfg(target_arch = "x86_64")]
+        "iopl" => libc::SYS_iopl,
+        #[cfg(target_arch = "x86_64")]
+        "ioperm" => libc::SYS_ioperm,
+        #[cfg(target_arch = "x86_64")]
+        "uselib" => libc::SYS_uselib,
+        #[cfg(target_arch = "x86_64")]
+        "ustat" => libc::SYS_ustat,
+        #[cfg(target_arch = "x86_64")]
+        "sysfs" => libc::SYS_sysfs,
+        #[cfg(target_arch = "x86_64")]
+        "_sysctl" => libc::SYS__sysctl,
+        "clone3" => libc::SYS_clone3,
+        "io_uring_setup" => libc::SYS_io_uring_setup,
+        "io_uring_enter" => libc::SYS_io_uring_enter,
+        "io_uring_register" => libc::SYS_io_uring_register,
+        "socket" => libc::SYS_socket,
+        "socketpair" => libc::SYS_socketpair,
+        "connect" => libc::SYS_connect,
+        "bind" => libc::SYS_bind,
+        "listen" => libc::SYS_listen,
+        "accept4" => libc::SYS_accept4,
+        "execve" => libc::SYS_execve,
+        "execveat" => libc::SYS_execveat,
+        #[cfg(target_arch = "x86_64")]
+        "vfork" => libc::SYS_vfork,
+        "chroot" => libc::SYS_chroot,
+        "personality" => libc::SYS_personality,
+        "mbind" => libc::SYS_mbind,
+        "set_mempolicy" => libc::SYS_set_mempolicy,
+        "get_mempolicy" => libc::SYS_get_mempolicy,
+        "migrate_pages" => libc::SYS_migrate_pages,
+        "move_pages" => libc::SYS_move_pages,
+        _ => return None,
+    };
+    Some(number)
+}
+
+/// Whether a manifest may name `syscall` in its `[seccomp]` table
+pub fn is_known_syscall(syscall: &str) -> bool {
+    DENIED.contains(&syscall) || DENIABLE.contains(&syscall)
+}
+
+/// The syscall policy of one language: the curated deny list adjusted by the manifest's
+/// `[seccomp]` table. Docker gets it as a seccomp profile, the native runner as a BPF filter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeccompProfile {
+    denied: BTreeSet<&'static str>,
+}
+
+impl Default for SeccompProfile {
+    fn default() -> Self {
+        Self { denied: DENIED.iter().copied().collect() }
+    }
+}
+
+impl SeccompProfile {
+    pub fn for_language(manifest: &LanguageManifest) -> Self {
+        let mut profile = Self::for_compiler(manifest);
+        profile.denied.extend(DENIABLE.iter().filter(|syscall| manifest.seccomp.deny.iter().any(|denied| denied == *syscall)).copied());
+        profile
+    }
+
+    /// Profile of a compiled language's compile step: the language's allowances apply, as the
+    /// toolchain shares its runtime, but not its further denials, which are meant for the submitted
+    /// program (a compiler driver needs `execve`, for one)
+    pub fn for_compiler(manifest: &LanguageManifest) -> Self {
+        let mut profile = Self::default();
+        profile.denied.retain(|syscall| !manifest.seccomp.allow.iter().any(|allowed| allowed == syscall));
+        profile
+    }
+
+    /// Syscalls that kill the process
+    pub fn denied(&self) -> impl Iterator<Item = &str> {
+        self.denied.iter().copied()
+    }
+
+    /// The profile in Docker's seccomp JSON format: Docker's default profile, which refuses
+    /// everything it does not allow with `EPERM`, with this profile's denials killing the process
+    /// and curated denials the manifest allows added to its allowlist
+    pub fn docker_json(&self) -> String {
+        let allowed: BTreeSet<&str> = DOCKER_ALLOWED.iter()
+            .chain(DENIED)
+            .copied()
+            .filter(|syscall| !self.denied.contains(syscall) && !UNAVAILABLE.contains(syscall))
+            .collect();
+        let namespace_flags = NAMESPACE_FLAGS.iter().fold(0, |mask, (_, flag)| mask | *flag as u32);
+        let mut rules = vec![
+            json!({ "names": allowed, "action": "SCMP_ACT_ALLOW" }),
+            // 네임스페이스 플래그가 없는 clone만 허용 (Docker 기본 프로필과 같음)
+            json!({
+                "names": ["clone"],
+                "action": "SCMP_ACT_ALLOW",
+                "args": [{ "index": 0, "value": namespace_flags, "valueTwo": 0, "op": "SCMP_CMP_MASKED_EQ" }],
+            }),
+        ];
+        if !self.denied.contains("personality") {
+            rules.extend(DOCKER_PERSONALITIES.iter().map(|persona| json!({
+                "names": ["personality"],
+                "action": "SCMP_ACT_ALLOW",
+                "args": [{ "index": 0, "value": persona, "op": "SCMP_CMP_EQ" }],
+            })));
+        }
+        if !self.denied.contains("socket") {
+            rules.push(json!({
+                "names": ["socket"],
+                "action": "SCMP_ACT_ALLOW",
+                "args": [{ "index": 0, "value": AF_VSOCK, "op": "SCMP_CMP_NE" }],
+            }));
+        }
+        rules.push(json!({ "names": self.denied, "action": "SCMP_ACT_KILL_PROCESS" }));
+        rules.push(json!({ "names": UNAVAILABLE, "action": "SCMP_ACT_ERRNO", "errnoRet": libc::ENOSYS }));
+        // libseccomp에는 "마스크 결과가 0이 아님" 비교가 없으므로 플래그마다 규칙을 둠
+        for (_, flag) in NAMESPACE_FLAGS {
+            rules.push(json!({
+                "names": ["clone"],
+                "action": "SCMP_ACT_KILL_PROCESS",
+                "args": [{ "index": 0, "value": flag, "valueTwo": flag, "op": "SCMP_CMP_MASKED_EQ" }],
+            }));
+        }
+        json!({
+            "defaultAction": "SCMP_ACT_ERRNO",
+            "defaultErrnoRet": libc::EPERM,
+            "architectures": DOCKER_ARCHITECTURES,
+            "syscalls": rules,
+        }).to_string()
+    }
+
+    /// `security_opt` entry that applies the profile to a container
+    pub fn docker_security_opt(&self) -> String {
+        format!("seccomp={}", self.docker_json())
+    }
+
+    /// Compile the profile to a classic BPF program for `seccomp(2)`
+    pub fn filter(&self) -> SyscallFilter {
+        let denied: Vec<u32> = self.denied.iter().filter_map(|syscall| syscall_number(syscall)).map(|nr| nr as u32).collect();
+        let unavailable: Vec<u32> = UNAVAILABLE.iter().filter_map(|syscall| syscall_number(syscall)).map(|nr| nr as u32).collect();
+        let namespace_flags = NAMESPACE_FLAGS.iter().fold(0, |mask, (_, flag)| mask | *flag as u32);
+
+        let mut program = Program::default();
+        program.load(SECCOMP_DATA_ARCH);
+        program.jump_unless(libc::BPF_JEQ, AUDIT_ARCH, Target::Kill);
+        program.load(SECCOMP_DATA_NR);
+        #[cfg(target_arch = "x86_64")]
+        program.jump_if(libc::BPF_JGE, X32_SYSCALL_BIT, Target::Kill);
+        for nr in denied {
+            program.jump_if(libc::BPF_JEQ, nr, Target::Kill);
+        }
+        for nr in unavailable {
+            program.jump_if(libc::BPF_JEQ, nr, Target::Unavailable);
+        }
+        program.jump_unless(libc::BPF_JEQ, libc::SYS_clone as u32, Target::Allow);
+        program.load(SECCOMP_DATA_ARGS);
+        program.jump_if(libc::BPF_JSET, namespace_flags, Target::Kill);
+        SyscallFilter { program: program.finish() }
+    }
+}
+
+/// Offsets into `struct seccomp_data`
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+/// Low 32 bits of the first argument on little endian machines
+const SECCOMP_DATA_ARGS: u32 = 16;
+/// Set in the numbers of the x32 ABI, whose syscalls the native filter does not cover
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// Where a conditional jump goes; each is a return instruction at the end of the program
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Allow,
+    Unavailable,
+    Kill,
+}
+
+/// A BPF program being assembled. Jumps to a `Target` are resolved by `finish`, which
+/// appends the return instructions after the checks.
+#[derive(Default)]
+struct Program {
+    instructions: Vec<(libc::sock_filter, Option<(Target, bool)>)>,
+}
+
+impl Program {
+    fn load(&mut self, offset: u32) {
+        let load = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+        self.instructions.push((libc::sock_filter { code: load, jt: 0, jf: 0, k: offset }, None));
+    }
+
+    /// Go to `target` when the comparison holds, otherwise fall through
+    fn jump_if(&mut self, comparison: u32, k: u32, target: Target) {
+        let code = (libc::BPF_JMP | comparison | libc::BPF_K) as u16;
+        self.instructions.push((libc::sock_filter { code, jt: 0, jf: 0, k }, Some((target, true))));
+    }
+
+    /// Go to `target` when the comparison does not hold, otherwise fall through
+    fn jump_unless(&mut self, comparison: u32, k: u32, target: Target) {
+        let code = (libc::BPF_JMP | comparison | libc::BPF_K) as u16;
+        self.instructions.push((libc::sock_filter { code, jt: 0, jf: 0, k }, Some((target, false))));
+    }
+
+    fn finish(self) -> Vec<libc::sock_filter> {
+        let ret = (libc::BPF_RET | libc::BPF_K) as u16;
+        let returns = [
+            (Target::Allow, libc::SECCOMP_RET_ALLOW),
+            (Target::Unavailable, libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32),
+            (Target::Kill, libc::SECCOMP_RET_KILL_PROCESS),
+        ];
+        let checks = self.instructions.len();
+        let mut program: Vec<libc::sock_filter> = self.instructions.into_iter().enumerate()
+            .map(|(index, (mut instruction, jump))| {
+                if let Some((target, when)) = jump {
+                    let position = returns.iter().position(|(candidate, _)| *candidate == target).unwrap();
+                    // 점프 거리는 다음 명령 기준이며 8비트로 제한됨
+                    let offset = u8::try_from(checks + position - index - 1).expect("seccomp filter is too long");
+                    if when {
+                        instruction.jt = offset;
+                    } else {
+                        instruction.jf = offset;
+                    }
+                }
+                instruction
+            })
+            .collect();
+        // 마지막 검사를 통과하면 허용
+        program.extend(returns.iter().map(|(_, action)| libc::sock_filter { code: ret, jt: 0, jf: 0, k: *action }));
+        program
+    }
+}
+
+/// A compiled seccomp filter, installed in the runner's child right before exec
+#[derive(Clone)]
+pub struct SyscallFilter {
+    program: Vec<libc::sock_filter>,
+}
+
+impl fmt::Debug for SyscallFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyscallFilter").field("instructions", &self.program.len()).finish()
+    }
+}
+
+impl SyscallFilter {
+    /// Install the filter for the calling thread and everything it execs. Sets `no_new_privs`,
+    /// which unprivileged processes need for seccomp. Runs between fork and exec, so it must not allocate.
+    pub fn install(&self) -> nix::Result<()> {
+        let program = libc::sock_fprog {
+            len: self.program.len() as libc::c_ushort,
+            filter: self.program.as_ptr() as *mut libc::sock_filter,
+        };
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+                || libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program as *const libc::sock_fprog) != 0 {
+                return Err(nix::errno::Errno::last());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+    fn manifest(seccomp: &str) -> LanguageManifest {
+        toml::from_str(&format!(r#"
+            language = "sh"
+            version = "1"
+            image = "unused"
+            source_file = "main.sh"
+            run = ["sh", "{{source}}"]
+            {}
+        "#, seccomp)).unwrap()
+    }
+
+    #[test]
+    fn manifests_adjust_the_curated_list() {
+        let profile = SeccompProfile::for_language(&manifest("[seccomp]\nallow = [\"personality\", \"perf_event_open\"]\ndeny = [\"socket\"]"));
+        let denied: Vec<_> = profile.denied().collect();
+
+        assert!(denied.contains(&"ptrace") && denied.contains(&"socket"));
+        assert!(!denied.contains(&"perf_event_open"));
+        let compiler = SeccompProfile::for_compiler(&manifest("[seccomp]\nallow = [\"perf_event_open\"]\ndeny = [\"execve\"]"));
+        assert!(!compiler.denied().any(|syscall| syscall == "execve" || syscall == "perf_event_open"));
+        assert_eq!(SeccompProfile::for_language(&manifest("")), SeccompProfile::default());
+        assert!(DENIED.iter().chain(DENIABLE).chain(UNAVAILABLE).all(|syscall| syscall_number(syscall).is_some()
+            || cfg!(not(target_arch = "x86_64")) && *syscall == "vfork"), "every syscall has a number");
+    }
+
+    #[test]
+    fn docker_profile_is_the_default_profile_with_denials_killing() {
+        let profile = SeccompProfile::for_language(&manifest("[seccomp]\nallow = [\"perf_event_open\"]\ndeny = [\"socket\"]"));
+        let profile: serde_json::Value = serde_json::from_str(&profile.docker_json()).unwrap();
+        let rules = profile["syscalls"].as_array().unwrap();
+        let names = |action: &str| -> Vec<&str> {
+            rules.iter()
+                .filter(|rule| rule["action"] == action && rule.get("args").is_none())
+                .flat_map(|rule| rule["names"].as_array().unwrap().iter().map(|name| name.as_str().unwrap()))
+                .collect()
+        };
+        let (allowed, killed) = (names("SCMP_ACT_ALLOW"), names("SCMP_ACT_KILL_PROCESS"));
+
+        assert_eq!(profile["defaultAction"], "SCMP_ACT_ERRNO");
+        assert_eq!(profile["defaultErrnoRet"], libc::EPERM);
+        assert!(allowed.contains(&"read") && allowed.contains(&"execve") && allowed.contains(&"perf_event_open"));
+        // Docker 기본 프로필이 허용하지 않는 호출은 목록에 없으므로 EPERM
+        assert!(!allowed.contains(&"sethostname") && !allowed.contains(&"pidfd_getfd"));
+        assert!(killed.contains(&"ptrace") && killed.contains(&"socket"));
+        assert!(allowed.iter().all(|syscall| !killed.contains(syscall) && !UNAVAILABLE.contains(syscall)));
+        assert!(!rules.iter().any(|rule| rule["names"][0] == "socket" && rule["action"] == "SCMP_ACT_ALLOW"));
+        assert_eq!(rules.iter().filter(|rule| rule["names"][0] == "personality").count(), DOCKER_PERSONALITIES.len());
+        let clone_kills = rules.iter().filter(|rule| rule["names"][0] == "clone" && rule["action"] == "SCMP_ACT_KILL_PROCESS").count();
+        assert_eq!(clone_kills, NAMESPACE_FLAGS.len());
+    }
+
+    /// Run `sh -c script` under the filter
+    fn run_filtered(script: &str) -> std::process::ExitStatus {
+        let filter = SeccompProfile::default().filter();
+        let mut command = std::process::Command::new("/bin/sh");
+        command.args(["-c", script]);
+        unsafe {
+            command.pre_exec(move || filter.install().map_err(std::io::Error::from));
+        }
+        command.status().unwrap()
+    }
+
+    #[test]
+    fn filter_kills_denied_calls_and_allows_the_rest() {
+        assert_eq!(run_filtered("echo ok > /dev/null; exit 4").code(), Some(4));
+        // unshare(1)과 mount(8)는 바로 금지된 시스템 호출을 부름
+        assert_eq!(run_filtered("exec unshare --user true").signal(), Some(libc::SIGSYS));
+        assert_eq!(run_filtered("exec mount -t tmpfs none /mnt").signal(), Some(libc::SIGSYS));
+    }
+}
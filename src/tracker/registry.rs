@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::proto::ExecutionStatus;
+
+const FINISHED_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Tracked {
+    status: ExecutionStatus,
+    finished_at: Option<Instant>,
+}
+
+/// Status of every execution this instance has accepted, so `GetStatus` can answer
+/// for asynchronous executions. Finished executions are forgotten after an hour.
+///
+/// Also counts callbacks still being delivered, so a drain waits for them too.
+#[derive(Debug, Default)]
+pub struct ExecutionTracker {
+    executions: Mutex<HashMap<String, Tracked>>,
+    deliveries: Mutex<usize>,
+    finished: Notify,
+}
+
+/// A callback being delivered; dropping it marks the delivery as done
+#[derive(Debug)]
+pub struct Delivery {
+    tracker: Arc<ExecutionTracker>,
+}
+
+impl Drop for Delivery {
+    fn drop(&mut self) {
+        *self.tracker.deliveries.lock().unwrap() -= 1;
+        self.tracker.finished.notify_waiters();
+    }
+}
+
+impl ExecutionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a newly accepted execution as pending
+    pub fn register(&self, execution_id: &str) {
+        let mut executions = self.executions.lock().unwrap();
+        executions.retain(|_, tracked| {
+            tracked.finished_at.is_none_or(|finished_at| finished_at.elapsed() < FINISHED_RETENTION)
+        });
+        executions.insert(execution_id.to_string(), Tracked {
+            status: ExecutionStatus::Pending,
+            finished_at: None,
+        });
+    }
+
+    /// Mark an execution as holding a slot and running
+    pub fn start(&self, execution_id: &str) {
+        self.update(execution_id, ExecutionStatus::Running, None);
+    }
+
+    /// Record the final status of an execution
+    pub fn finish(&self, execution_id: &str, status: ExecutionStatus) {
+        self.update(execution_id, status, Some(Instant::now()));
+        self.finished.notify_waiters();
+    }
+
+    /// Count a callback delivery until the returned guard is dropped.
+    /// Take it before finishing the execution, so `wait_idle` can't return in between
+    pub fn start_delivery(self: &Arc<Self>) -> Delivery {
+        *self.deliveries.lock().unwrap() += 1;
+        Delivery { tracker: self.clone() }
+    }
+
+    /// Number of accepted executions that have not finished yet
+    pub fn in_flight(&self) -> usize {
+        self.executions.lock().unwrap().values().filter(|tracked| tracked.finished_at.is_none()).count()
+    }
+
+    /// Number of callbacks still being delivered
+    pub fn deliveries(&self) -> usize {
+        *self.deliveries.lock().unwrap()
+    }
+
+    /// Wait until every accepted execution has finished and its callback was delivered
+    pub async fn wait_idle(&self) {
+        loop {
+            // Notified를 먼저 만들어야 확인과 대기 사이의 finish 알림을 놓치지 않음
+            let finished = self.finished.notified();
+            if self.in_flight() == 0 && self.deliveries() == 0 {
+                return;
+            }
+            finished.await;
+        }
+    }
+
+    pub fn status(&self, execution_id: &str) -> Option<ExecutionStatus> {
+        self.executions.lock().unwrap().get(execution_id).map(|tracked| tracked.status)
+    }
+
+    fn update(&self, execution_id: &str, status: ExecutionStatus, finished_at: Option<Instant>) {
+        if let Some(tracked) = self.executions.lock().unwrap().get_mut(execution_id) {
+            tracked.status = status;
+            tracked.finished_at = finished_at;
+        }
+    }
+}
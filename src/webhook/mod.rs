@@ -0,0 +1,3 @@
+mod notifier;
+
+pub use notifier::{WebhookConfig, WebhookNotifier, WebhookPayload, SIGNATURE_HEADER};
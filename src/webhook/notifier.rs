@@ -0,0 +1,395 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use hmac::{Hmac, Mac};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::config::env_override;
+use crate::execution::ExecutionResult;
+use crate::proto::ExecuteResponse;
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a signing secret is configured
+pub const SIGNATURE_HEADER: &str = "X-Code-Executor-Signature";
+const EXECUTION_ID_HEADER: &str = "X-Code-Executor-Execution-Id";
+const ATTEMPT_HEADER: &str = "X-Code-Executor-Attempt";
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 1000;
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cloud metadata endpoints outside the link-local range (Alibaba Cloud, AWS over IPv6)
+const METADATA_V4: [Ipv4Addr; 1] = [Ipv4Addr::new(100, 100, 100, 200)];
+const METADATA_V6: [Ipv6Addr; 1] = [Ipv6Addr::new(0xfd00, 0xec2, 0, 0, 0, 0, 0, 0x254)];
+
+/// Delivery settings for completion callbacks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Secret used to sign payloads; unsigned when unset
+    pub secret: Option<String>,
+    /// Total number of delivery attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after every failed attempt
+    pub initial_backoff_ms: u64,
+    /// Callback hosts that may resolve to loopback, link-local or metadata addresses,
+    /// e.g. a receiver on the same machine; every other host is refused them
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Apply `CODE_EXECUTOR_WEBHOOK_SECRET`, `CODE_EXECUTOR_WEBHOOK_MAX_ATTEMPTS`,
+    /// `CODE_EXECUTOR_WEBHOOK_INITIAL_BACKOFF_MS` and `CODE_EXECUTOR_WEBHOOK_ALLOWED_HOSTS`
+    /// (e.g. `127.0.0.1,localhost`) on top of the current values
+    pub fn apply_env(&mut self) -> Result<()> {
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_WEBHOOK_SECRET") {
+            self.secret = Some(value).filter(|secret| !secret.is_empty());
+        }
+        env_override("CODE_EXECUTOR_WEBHOOK_MAX_ATTEMPTS", &mut self.max_attempts)?;
+        env_override("CODE_EXECUTOR_WEBHOOK_INITIAL_BACKOFF_MS", &mut self.initial_backoff_ms)?;
+        if let Ok(value) = std::env::var("CODE_EXECUTOR_WEBHOOK_ALLOWED_HOSTS") {
+            self.allowed_hosts = value.split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Ok(())
+    }
+
+    /// Describe every invalid setting
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.max_attempts == 0 {
+            errors.push("max_attempts must be greater than 0".to_string());
+        }
+        errors
+    }
+}
+
+/// JSON body posted to the callback URL: the execution's result with the fields of
+/// `ExecutionResult`, plus what the server added around the run
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub execution_id: &'a str,
+    #[serde(flatten)]
+    pub result: ExecutionResult,
+    pub error_message: &'a str,
+    pub queue_wait_ms: f64,
+    pub cached: bool,
+}
+
+impl<'a> From<&'a ExecuteResponse> for WebhookPayload<'a> {
+    fn from(response: &'a ExecuteResponse) -> Self {
+        Self {
+            execution_id: &response.execution_id,
+            result: ExecutionResult::from(response.clone()),
+            error_message: &response.error_message,
+            queue_wait_ms: response.queue_wait_ms,
+            cached: response.cached,
+        }
+    }
+}
+
+/// Whether callbacks to `ip` could reach the server itself or the cloud metadata service
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || METADATA_V4.contains(&ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal(IpAddr::V4(ip)),
+            None => ip.is_loopback() || ip.is_unspecified() || ip.segments()[0] & 0xffc0 == 0xfe80 || METADATA_V6.contains(&ip),
+        },
+    }
+}
+
+/// The address of a URL host that is an IP literal, `[...]` for IPv6
+fn host_ip(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// Resolves callback hosts like the system resolver, dropping internal addresses for hosts
+/// that are not allowed them. Checking at connect time also covers DNS answers that
+/// change between validation and delivery
+#[derive(Debug)]
+struct CallbackResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl Resolve for CallbackResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed = self.allowed_hosts.iter().any(|host| host.eq_ignore_ascii_case(name.as_str()));
+        Box::pin(async move {
+            let addrs = resolve(name.as_str(), allowed).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Addresses of `host` a callback may connect to, failing when there are none
+async fn resolve(host: &str, allowed: bool) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0)).await?
+        .filter(|addr| allowed || !is_internal(addr.ip()))
+        .collect();
+    if addrs.is_empty() {
+        return Err(anyhow!("callback_url host '{}' resolves only to loopback, link-local or metadata addresses", host));
+    }
+    Ok(addrs)
+}
+
+/// Posts final results of asynchronous executions to their `callback_url`
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let resolver = CallbackResolver { allowed_hosts: config.allowed_hosts.clone() };
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .dns_resolver(Arc::new(resolver))
+            // 리다이렉트로 내부 주소에 닿지 않도록 따라가지 않음
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { config, client })
+    }
+
+    /// Check that a callback URL can be delivered to before accepting the execution:
+    /// http(s) only, and not to loopback, link-local or metadata addresses unless its
+    /// host is in `allowed_hosts`
+    pub async fn validate_url(&self, url: &str) -> Result<()> {
+        let parsed = self.parse_url(url)?;
+        let host = parsed.host_str().unwrap_or_default();
+        if host_ip(host).is_none() && !self.is_allowed(host) {
+            resolve(host, false).await?;
+        }
+        Ok(())
+    }
+
+    /// Parse a callback URL, rejecting other schemes and internal IP literals, which the
+    /// resolver never sees
+    fn parse_url(&self, url: &str) -> Result<reqwest::Url> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| anyhow!("Invalid callback_url '{}': {}", url, e))?;
+        match parsed.scheme() {
+            "http" | "https" => {}
+            scheme => return Err(anyhow!("Unsupported callback_url scheme '{}'", scheme)),
+        }
+        let host = parsed.host_str()
+            .ok_or_else(|| anyhow!("callback_url '{}' has no host", url))?;
+        if host_ip(host).is_some_and(is_internal) && !self.is_allowed(host) {
+            return Err(anyhow!("callback_url '{}' points to a loopback, link-local or metadata address", url));
+        }
+        Ok(parsed)
+    }
+
+    fn is_allowed(&self, host: &str) -> bool {
+        self.config.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    /// POST the result, retrying with exponential backoff until a 2xx response or
+    /// `max_attempts` is reached
+    pub async fn deliver(&self, url: &str, response: &ExecuteResponse) -> Result<()> {
+        let url = self.parse_url(url)?;
+        let body = serde_json::to_vec(&WebhookPayload::from(response))?;
+        let signature = self.sign(&body)?;
+        let mut backoff = Duration::from_millis(self.config.initial_backoff_ms);
+
+        for attempt in 1..=self.config.max_attempts {
+            let mut request = self.client.post(url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EXECUTION_ID_HEADER, &response.execution_id)
+                .header(ATTEMPT_HEADER, attempt.to_string())
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            match request.send().await {
+                Ok(reply) if reply.status().is_success() => return Ok(()),
+                Ok(reply) => tracing::warn!(
+                    "Callback for {} returned {} (attempt {}/{})",
+                    response.execution_id, reply.status(), attempt, self.config.max_attempts
+                ),
+                Err(e) => tracing::warn!(
+                    "Callback for {} failed: {} (attempt {}/{})",
+                    response.execution_id, e, attempt, self.config.max_attempts
+                ),
+            }
+
+            if attempt < self.config.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+
+        Err(anyhow!("Giving up on callback for {} after {} attempts", response.execution_id, self.config.max_attempts))
+    }
+
+    fn sign(&self, body: &[u8]) -> Result<Option<String>> {
+        let Some(secret) = &self.config.secret else {
+            return Ok(None);
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| anyhow!("Invalid webhook secret: {}", e))?;
+        mac.update(body);
+        Ok(Some(format!("sha256={}", hex::encode(mac.finalize().into_bytes()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier(allowed_hosts: &[&str]) -> WebhookNotifier {
+        WebhookNotifier::new(WebhookConfig {
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    #[tokio::test]
+    async fn callbacks_to_the_server_or_metadata_are_refused() {
+        let notifier = notifier(&[]);
+        for url in [
+            "http://127.0.0.1:8080/callback",
+            "http://127.1/callback",
+            "http://[::1]/callback",
+            "http://[::ffff:127.0.0.1]/callback",
+            "http://0.0.0.0/callback",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[fe80::1]/callback",
+            "http://[fd00:ec2::254]/callback",
+            "http://100.100.100.200/callback",
+            "http://localhost:8080/callback",
+            "ftp://example.com/callback",
+            "not a url",
+        ] {
+            assert!(notifier.validate_url(url).await.is_err(), "{}", url);
+        }
+
+        assert!(notifier.validate_url("https://192.0.2.10/callback").await.is_ok());
+        assert!(notifier.validate_url("http://[2001:db8::1]:8080/callback").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn allowed_hosts_may_be_internal() {
+        let notifier = notifier(&["127.0.0.1", "LOCALHOST"]);
+        assert!(notifier.validate_url("http://127.0.0.1:8080/callback").await.is_ok());
+        assert!(notifier.validate_url("http://localhost:8080/callback").await.is_ok());
+        assert!(notifier.validate_url("http://169.254.169.254/").await.is_err());
+    }
+
+    /// A request received by `receiver`
+    struct Received {
+        headers: hyper::HeaderMap,
+        body: Vec<u8>,
+        at: std::time::Instant,
+    }
+
+    /// Local callback receiver answering with `statuses` in turn, then with the last one
+    fn receiver(statuses: &'static [u16]) -> (String, Arc<std::sync::Mutex<Vec<Received>>>) {
+        use std::convert::Infallible;
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Response, Server};
+
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = received.clone();
+        let make_service = make_service_fn(move |_| {
+            let log = log.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                    let log = log.clone();
+                    async move {
+                        let at = std::time::Instant::now();
+                        let headers = request.headers().clone();
+                        let body = hyper::body::to_bytes(request.into_body()).await.unwrap().to_vec();
+                        let mut log = log.lock().unwrap();
+                        log.push(Received { headers, body, at });
+                        let status = statuses[(log.len() - 1).min(statuses.len() - 1)];
+                        Ok::<_, Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}/callback", server.local_addr());
+        tokio::spawn(server);
+        (url, received)
+    }
+
+    fn delivering(max_attempts: u32) -> WebhookNotifier {
+        WebhookNotifier::new(WebhookConfig {
+            secret: Some("s3cret".to_string()),
+            max_attempts,
+            initial_backoff_ms: 50,
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+        }).unwrap()
+    }
+
+    fn response() -> ExecuteResponse {
+        ExecuteResponse {
+            execution_id: "exec-1".to_string(),
+            status: crate::proto::ExecutionStatus::Completed.into(),
+            stdout: "hello\n".to_string(),
+            exit_code: Some(0),
+            ..Default::default()
+        }
+    }
+
+    fn header<'a>(request: &'a Received, name: &str) -> &'a str {
+        request.headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_with_the_signed_payload() {
+        let (url, received) = receiver(&[500, 200]);
+        delivering(5).deliver(&url, &response()).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(header(&received[0], ATTEMPT_HEADER), "1");
+        assert_eq!(header(&received[1], ATTEMPT_HEADER), "2");
+        assert!(received[1].at - received[0].at >= Duration::from_millis(50));
+
+        for request in received.iter() {
+            assert_eq!(header(request, EXECUTION_ID_HEADER), "exec-1");
+            let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+            mac.update(&request.body);
+            let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+            assert_eq!(header(request, SIGNATURE_HEADER), expected);
+        }
+        let payload: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(payload["execution_id"], "exec-1");
+        assert_eq!(payload["status"], "COMPLETED");
+        assert_eq!(payload["stdout"], "hello\n");
+    }
+
+    #[tokio::test]
+    async fn delivery_gives_up_after_max_attempts() {
+        let (url, received) = receiver(&[500]);
+        assert!(delivering(3).deliver(&url, &response()).await.is_err());
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        // 재시도 간격은 매번 2배
+        assert!(received[1].at - received[0].at >= Duration::from_millis(50));
+        assert!(received[2].at - received[1].at >= Duration::from_millis(100));
+    }
+}